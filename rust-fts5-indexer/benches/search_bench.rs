@@ -136,6 +136,18 @@ fn benchmark_index(c: &mut Criterion) {
                 let _ = indexer.index_directory();
             });
         });
+        group.bench_with_input(
+            BenchmarkId::new("index_files_auto_batch", num_files),
+            &num_files,
+            |b, n| {
+                b.iter(|| {
+                    let (dir, db) = create_benchmark_db(*n);
+                    let config = IndexerConfig { auto_batch: true, ..Default::default() };
+                    let mut indexer = Indexer::new(dir.path(), db, config);
+                    let _ = indexer.index_directory();
+                });
+            },
+        );
     }
 
     group.finish();
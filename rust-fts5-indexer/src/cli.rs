@@ -1,10 +1,14 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use std::path::{Path, PathBuf};
 
 use crate::{
     DB_NAME,
+    db::{Bm25Weights, PragmaConfig, TokenizerKind},
     error::{IndexerError, Result},
     health::find_project_root,
+    language::is_known_language,
+    search::QueryMode,
 };
 
 #[cfg(target_os = "macos")]
@@ -13,12 +17,119 @@ const DEFAULT_MMAP_SIZE: i64 = 0;
 #[cfg(not(target_os = "macos"))]
 const DEFAULT_MMAP_SIZE: i64 = 256 * 1024 * 1024;
 
+/// Default number of search results (`search --limit`).
+pub const DEFAULT_SEARCH_LIMIT: u32 = 50;
+
+/// Upper bound for `search --limit`.
+pub const MAX_SEARCH_LIMIT: u32 = 10_000;
+
+/// `--max-file-size` values above this are accepted with a memory warning.
+pub const MAX_FILE_SIZE_WARN_THRESHOLD: u64 = 64 * 1024 * 1024;
+
 /// Output format for search results.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 pub enum OutputFormat {
     #[default]
     Plain,
     Json,
+    /// `path:line:text` per matching line, like `grep -n` / `rg --no-heading`
+    Grep,
+    /// One compact JSON object per result line (JSON Lines), flushed per result
+    Ndjson,
+}
+
+/// Line ending used when writing search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LineEnding {
+    /// `\n` on every platform
+    #[default]
+    Lf,
+    /// `\r\n` (e.g. for `PowerShell` pipelines)
+    #[value(name = "crlf")]
+    CrLf,
+    /// `\r\n` on Windows, `\n` elsewhere
+    Native,
+}
+
+impl LineEnding {
+    /// The terminator string for this setting on the current platform.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Native => {
+                if cfg!(windows) {
+                    "\r\n"
+                } else {
+                    "\n"
+                }
+            }
+        }
+    }
+}
+
+/// Ordering applied to search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// BM25 relevance (filename matches first)
+    #[default]
+    Rank,
+    /// Query-term matches per line, densest first
+    Density,
+    /// Path, alphabetically
+    #[value(name = "path")]
+    PathAsc,
+    /// Modification time, newest first
+    #[value(name = "mtime")]
+    MtimeDesc,
+    /// File size, largest first
+    #[value(name = "size")]
+    SizeDesc,
+}
+
+impl SortOrder {
+    /// Whether results are ordered by a stored file attribute rather than by score.
+    #[must_use]
+    pub const fn is_file_order(self) -> bool {
+        matches!(self, Self::PathAsc | Self::MtimeDesc | Self::SizeDesc)
+    }
+}
+
+/// Doctor severity that causes a non-zero exit code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FailOn {
+    /// Fail on warnings (exit 1) and errors (exit 2)
+    #[default]
+    Warn,
+    /// Fail only on errors (exit 2); warnings exit 0
+    Error,
+    /// Always exit 0
+    Never,
+}
+
+/// When plain search and doctor output is colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether output written to stdout should carry ANSI styles.
+    #[must_use]
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => {
+                anstream::AutoStream::choice(&std::io::stdout()) != anstream::ColorChoice::Never
+            }
+        }
+    }
 }
 
 /// CLI arguments for the FTS5 indexer.
@@ -62,14 +173,71 @@ pub struct Cli {
     #[arg(long, short = 'q')]
     pub quiet: bool,
 
+    /// Color plain search and doctor output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto, value_name = "WHEN")]
+    pub color: ColorChoice,
+
     /// Project directory (defaults to current directory)
     #[arg(long, env = "CLAUDE_PROJECT_DIR")]
     pub project_dir: Option<PathBuf>,
 
+    /// Config file to use instead of discovering `.ffts.toml` upward from the project root
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Database file to use instead of `.ffts-index.db` in the project root
+    /// (relative paths resolve against the project directory)
+    #[arg(long = "db-path", env = "FFTS_DB_PATH", value_name = "PATH")]
+    pub db_location: Option<PathBuf>,
+
     /// Follow symlinks while indexing (disabled by default for safety)
     #[arg(long)]
     pub follow_symlinks: bool,
 
+    /// Index files with invalid UTF-8, replacing bad bytes with U+FFFD, instead of skipping them
+    #[arg(long)]
+    pub lossy_utf8: bool,
+
+    /// Index only filenames and paths, not file content (much smaller database for path search)
+    #[arg(long)]
+    pub no_content: bool,
+
+    /// Index a hidden file by name even though dotfiles are skipped (repeatable)
+    #[arg(long = "index-hidden-file", value_name = "NAME")]
+    pub index_hidden_files: Vec<String>,
+
+    /// Index all hidden files and directories (`.github/`, `.env.example`, ...); `.git` stays excluded
+    #[arg(long)]
+    pub hidden: bool,
+
+    /// Only index files with these extensions (comma-separated, e.g. `rs,md,toml`)
+    #[arg(long = "ext", value_name = "EXTS", value_delimiter = ',')]
+    pub include_extensions: Vec<String>,
+
+    /// Never index files with these extensions (comma-separated; wins over `--ext`)
+    #[arg(long = "exclude-ext", value_name = "EXTS", value_delimiter = ',')]
+    pub exclude_extensions: Vec<String>,
+
+    /// Largest file to index, in bytes or with a K/M/G suffix (e.g. `512K`, `2M`; default 1M)
+    #[arg(long, value_name = "SIZE", value_parser = parse_file_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Skip files whose content hash is listed in this file (one hash per line)
+    #[arg(long, value_name = "PATH")]
+    pub hash_denylist_file: Option<PathBuf>,
+
+    /// Files per committed transaction while indexing (default 500)
+    #[arg(long, value_name = "N")]
+    pub batch_size: Option<usize>,
+
+    /// Files indexed in autocommit mode before a transaction is opened (default 50; at most --batch-size)
+    #[arg(long, value_name = "N")]
+    pub transaction_threshold: Option<usize>,
+
+    /// Also index this directory into the same database, with paths stored as `@<dirname>/...` (repeatable)
+    #[arg(long = "add-root", value_name = "PATH")]
+    pub add_roots: Vec<PathBuf>,
+
     /// Refresh index before searching (requires a non-empty query)
     #[arg(long, global = true)]
     pub refresh: bool,
@@ -93,6 +261,22 @@ pub struct Cli {
     /// `SQLite` synchronous mode (`OFF`, `NORMAL`, `FULL`, `EXTRA`)
     #[arg(long, default_value = "NORMAL", value_parser = validate_synchronous)]
     pub pragma_synchronous: String,
+
+    /// `SQLite` journal mode (`WAL`, `DELETE`, `TRUNCATE`); use `DELETE` where -wal/-shm files misbehave
+    #[arg(long, default_value = "WAL", value_parser = validate_journal_mode)]
+    pub journal_mode: String,
+
+    /// Store file content zstd-compressed (smaller database, slower snippets); the index stays compressed for later runs
+    #[arg(long, global = true)]
+    pub compress_content: bool,
+
+    /// FTS5 tokenizer (`porter`, `unicode61`, `trigram`); changing it rebuilds the index, omitting it keeps the index's
+    #[arg(long, global = true, value_parser = validate_tokenizer)]
+    pub tokenizer: Option<TokenizerKind>,
+
+    /// Argument ids set on the command line or via the environment (see [`Cli::parse_args`])
+    #[arg(skip)]
+    explicit_args: Vec<String>,
 }
 
 /// Subcommands for ffts-grep.
@@ -103,6 +287,47 @@ pub enum Commands {
         /// Force full reindex (atomic replace)
         #[arg(long)]
         reindex: bool,
+        /// Only index files changed since this git ref (e.g. `HEAD~1`), plus untracked files
+        #[arg(long, value_name = "REF", conflicts_with = "reindex")]
+        since: Option<String>,
+        /// Report what would be added/updated/skipped/pruned (with --reindex: deleted) without writing
+        #[arg(long, conflicts_with = "since")]
+        dry_run: bool,
+        /// Index content read from stdin under the virtual path given by --name (no directory walk)
+        #[arg(long, requires = "name", conflicts_with_all = ["reindex", "since"])]
+        stdin: bool,
+        /// Virtual path for --stdin content (relative, e.g. `generated/report.txt`)
+        #[arg(long, value_name = "PATH", requires = "stdin")]
+        name: Option<String>,
+        /// Print the run's statistics as one JSON object on stdout
+        #[arg(long, conflicts_with_all = ["dry_run", "stdin"])]
+        json: bool,
+        /// Checkpoint and truncate the WAL file after indexing
+        #[arg(long, conflicts_with = "dry_run")]
+        checkpoint: bool,
+    },
+    /// Remove index entries for files deleted from disk, without reindexing.
+    Prune {
+        /// Merge FTS5 segments afterwards (worthwhile after large deletions)
+        #[arg(long)]
+        optimize: bool,
+    },
+    /// Upgrade a legacy (v0.9) index in place: migrate the schema, recreate it and rebuild FTS5.
+    Rebuild,
+    /// Keep the index fresh: re-index files as they change (runs until Ctrl-C).
+    Watch {
+        /// Quiet period after the last change before indexing, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 300)]
+        debounce_ms: u64,
+        /// Time between scans of the tree when file notifications are unavailable, in milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 500, value_parser = clap::value_parser!(u64).range(10..))]
+        interval_ms: u64,
+    },
+    /// Shrink the database file with `VACUUM` (reclaims space after large deletions).
+    Compact {
+        /// Write a compacted copy here instead of compacting in place (`VACUUM INTO`)
+        #[arg(long, value_name = "PATH")]
+        into: Option<PathBuf>,
     },
     /// Run diagnostic checks on installation health.
     Doctor {
@@ -112,6 +337,12 @@ pub enum Commands {
         /// JSON output format
         #[arg(long)]
         json: bool,
+        /// Lowest severity that produces a failing exit code
+        #[arg(long, value_enum, default_value_t = FailOn::Warn, value_name = "SEVERITY")]
+        fail_on: FailOn,
+        /// Apply safe remediations (gitignore entries, orphan WAL files, invalid schema)
+        #[arg(long)]
+        fix: bool,
     },
     /// Initialize project with .gitignore and database.
     Init {
@@ -133,17 +364,207 @@ pub enum Commands {
         /// Output format
         #[arg(long, value_enum)]
         format: Option<OutputFormat>,
+        /// Maximum number of results (1-10000)
+        #[arg(
+            long,
+            visible_alias = "max-results",
+            value_name = "N",
+            default_value_t = DEFAULT_SEARCH_LIMIT,
+            value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_SEARCH_LIMIT))
+        )]
+        limit: u32,
+        /// Skip the first N results (pagination; combine with --limit)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        offset: u32,
         /// Run performance benchmark
         #[arg(long)]
         benchmark: bool,
+        /// Benchmark runs per query, used for latency percentiles
+        #[arg(
+            long,
+            default_value_t = 10,
+            value_parser = clap::value_parser!(u32).range(1..),
+            requires = "benchmark"
+        )]
+        iterations: u32,
+        /// Print benchmark latency percentiles as JSON on stdout (for CI tracking)
+        #[arg(long, requires = "benchmark")]
+        stats_json: bool,
         /// Disable auto-initialization on search (fail if no database)
         #[arg(long)]
         no_auto_init: bool,
+        /// Print the `SQLite` query plan for the search instead of running it
+        #[arg(long)]
+        explain_plan: bool,
+        /// Only check that the query is valid FTS5 syntax (exit 2 if not)
+        #[arg(long)]
+        validate_only: bool,
+        /// Line ending for result output
+        #[arg(long, value_enum, default_value_t = LineEnding::Lf)]
+        line_ending: LineEnding,
+        /// Rank recently modified files higher (weight added for older files, e.g. 1.0)
+        #[arg(long, value_name = "BOOST", value_parser = validate_recency_boost)]
+        recency: Option<f64>,
+        /// Emit 1-based result positions instead of BM25 scores as JSON `rank`
+        #[arg(long)]
+        position_ranks: bool,
+        /// Drop results whose file was deleted since the last index run
+        #[arg(long)]
+        verify_exists: bool,
+        /// Show query-term matches per line for each result (`path<TAB>matches/lines<TAB>density`)
+        #[arg(long)]
+        density: bool,
+        /// Result ordering
+        #[arg(long, value_enum, default_value_t = SortOrder::Rank)]
+        sort: SortOrder,
+        /// Show a highlighted content snippet (`<b>match</b>`) for each result
+        #[arg(long)]
+        snippets: bool,
+        /// BM25 weight for filename matches
+        #[arg(long, value_name = "WEIGHT", default_value = "100", value_parser = validate_weight)]
+        weight_filename: f64,
+        /// BM25 weight for path matches
+        #[arg(long, value_name = "WEIGHT", default_value = "50", value_parser = validate_weight)]
+        weight_path: f64,
+        /// BM25 weight for content matches
+        #[arg(long, value_name = "WEIGHT", default_value = "1", value_parser = validate_weight)]
+        weight_content: f64,
+        /// Show each result's BM25 score per column (filename/path/content); extra queries per result
+        #[arg(long)]
+        explain_ranking: bool,
+        /// How query words combine: and, or, phrase, near, or near:N (N = max token distance)
+        #[arg(long, value_name = "MODE", default_value = "and", value_parser = parse_query_mode)]
+        mode: QueryMode,
+        /// Pass the query to FTS5 as-is (operators, `"phrases"`, `prefix*`); ignores --mode
+        #[arg(long)]
+        raw_query: bool,
+        /// Treat the last word as a prefix (`con` finds config.rs, connect.rs; not reconnect.rs)
+        #[arg(long, conflicts_with = "raw_query")]
+        prefix: bool,
+        /// Show each matching content line as `path:line: text` (JSON: `match_lines`)
+        #[arg(long, short = 'n')]
+        line_numbers: bool,
+        /// Indent `--format json` output (default: compact, one line)
+        #[arg(long)]
+        json_pretty: bool,
+        /// Only search files of this language, detected from the extension (e.g. rust, python)
+        #[arg(long, value_name = "LANG", value_parser = parse_language)]
+        lang: Option<String>,
+        /// Only files modified since: a date (2024-01-31, UTC), RFC 3339 time, or age (7d, 12h, 2w)
+        #[arg(long, value_name = "WHEN", value_parser = parse_since)]
+        since: Option<i64>,
+        /// Only files modified until: a date (inclusive, UTC), RFC 3339 time, or age (7d, 12h, 2w)
+        #[arg(long, value_name = "WHEN", value_parser = parse_until)]
+        until: Option<i64>,
+        /// Only files of at least this size, in bytes or with a K/M/G suffix (e.g. 1K)
+        #[arg(long, value_name = "SIZE", value_parser = parse_file_size)]
+        min_size: Option<u64>,
+        /// Only files of at most this size, in bytes or with a K/M/G suffix (e.g. 64K)
+        #[arg(long, value_name = "SIZE", value_parser = parse_file_size)]
+        max_size: Option<u64>,
+        /// Print only the number of files matching the full-text index (ignores --limit and
+        /// --offset; filename-substring-only matches are not counted)
+        #[arg(long)]
+        count: bool,
+        /// Print only the matching file paths, once each (JSON: a `files` array)
+        #[arg(long, short = 'l', conflicts_with = "count")]
+        files_with_matches: bool,
+        /// Also match filenames with typos (up to 2 edits from the first query word, e.g. `condig`)
+        #[arg(long)]
+        fuzzy: bool,
+        /// Attach each result's content with matched tokens wrapped in markers (JSON: `highlighted`)
+        #[arg(long)]
+        highlight: bool,
+        /// Marker inserted before each highlighted token
+        #[arg(long, value_name = "TEXT", default_value = "[", requires = "highlight")]
+        highlight_open: String,
+        /// Marker inserted after each highlighted token
+        #[arg(long, value_name = "TEXT", default_value = "]", requires = "highlight")]
+        highlight_close: String,
+    },
+    /// Dump the index as JSON (path, filename, hash, mtime, size, content per file).
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+        /// One JSON object per line instead of a single array
+        #[arg(long)]
+        ndjson: bool,
+    },
+    /// Build the index from an `export` dump (JSON array or JSON Lines) instead of the files.
+    Import {
+        /// Dump to read (default: stdin)
+        #[arg(value_name = "FILE")]
+        input: Option<PathBuf>,
+        /// Replace an existing index (swapped in atomically once the import succeeds)
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print a file's content hash exactly as stored in the index.
+    Hash {
+        /// File to hash (must be valid UTF-8, as required for indexing)
+        file: PathBuf,
+    },
+    /// Print a shell completion script to stdout (e.g. `ffts-grep completions zsh > _ffts-grep`).
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    /// Render the `ffts-grep(1)` man page (roff) for packaging.
+    #[command(hide = true)]
+    Manpage {
+        /// Write to this file instead of stdout
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Show index statistics, optionally scoped to a directory and/or extension.
+    Stats {
+        /// Only count files under this directory (e.g. `src`)
+        #[arg(long = "in", value_name = "DIR")]
+        in_dir: Option<String>,
+        /// Only count files with this extension (e.g. `rs`)
+        #[arg(long, value_name = "EXT")]
+        ext: Option<String>,
+        /// JSON output format
+        #[arg(long)]
+        json: bool,
+    },
+    /// Read queries from stdin one per line, printing results after each (`:help` for commands).
+    Repl {
+        /// Maximum results per query (change in the REPL with `:limit N`)
+        #[arg(
+            long,
+            default_value_t = DEFAULT_SEARCH_LIMIT,
+            value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_SEARCH_LIMIT))
+        )]
+        limit: u32,
+        /// Start in path-only mode (toggle with `:paths`)
+        #[arg(long)]
+        paths: bool,
+    },
+    /// Serve `GET /search?q=...` and `GET /health` over HTTP until SIGTERM.
+    Serve {
+        /// Address to listen on (port 0 picks a free port; the bound address is printed)
+        #[arg(long, default_value = "127.0.0.1:7878")]
+        addr: String,
+        /// Worker threads, each with its own read-only database connection
+        #[arg(long, default_value_t = crate::server::DEFAULT_WORKERS, value_parser = clap::value_parser!(usize))]
+        workers: usize,
     },
+    /// Serve searches over a Unix socket (newline-delimited JSON) until SIGTERM.
+    #[cfg(unix)]
+    Daemon {
+        /// Socket path to listen on (a stale socket file is replaced)
+        #[arg(long, value_name = "PATH")]
+        socket: PathBuf,
+    },
+    /// Interactive fuzzy finder: live results as you type, Enter prints the selection.
+    #[cfg(feature = "tui")]
+    Interactive,
 }
 
 /// Validates `cache_size`: must be positive (`pages`) or `-1000` to `-1000000` (`KB`).
-fn validate_cache_size(s: &str) -> std::result::Result<i64, String> {
+pub(crate) fn validate_cache_size(s: &str) -> std::result::Result<i64, String> {
     let val: i64 = s.parse().map_err(|_| "invalid integer".to_string())?;
 
     match val {
@@ -154,7 +575,7 @@ fn validate_cache_size(s: &str) -> std::result::Result<i64, String> {
 }
 
 /// Validates `mmap_size`: must be between `0` and `256MB`.
-fn validate_mmap_size(s: &str) -> std::result::Result<i64, String> {
+pub(crate) fn validate_mmap_size(s: &str) -> std::result::Result<i64, String> {
     const MAX_MMAP: i64 = 256 * 1024 * 1024; // 256MB max on Linux
 
     let val: i64 = s.parse().map_err(|_| "invalid integer".to_string())?;
@@ -171,7 +592,7 @@ fn validate_mmap_size(s: &str) -> std::result::Result<i64, String> {
 }
 
 /// Validates `page_size`: must be power of 2 between `512` and `65536`.
-fn validate_page_size(s: &str) -> std::result::Result<i64, String> {
+pub(crate) fn validate_page_size(s: &str) -> std::result::Result<i64, String> {
     let val: i64 = s.parse().map_err(|_| "invalid integer".to_string())?;
 
     if !(512..=65536).contains(&val) {
@@ -186,7 +607,7 @@ fn validate_page_size(s: &str) -> std::result::Result<i64, String> {
 }
 
 /// Validates `busy_timeout`: must be non-negative.
-fn validate_busy_timeout(s: &str) -> std::result::Result<i64, String> {
+pub(crate) fn validate_busy_timeout(s: &str) -> std::result::Result<i64, String> {
     let val: i64 = s.parse().map_err(|_| "invalid integer".to_string())?;
 
     if val < 0 {
@@ -196,15 +617,225 @@ fn validate_busy_timeout(s: &str) -> std::result::Result<i64, String> {
     Ok(val)
 }
 
+/// Parses a file size: plain bytes or a binary `K`/`M`/`G` suffix (optional `B`/`iB`),
+/// case-insensitive. Must be > 0.
+fn parse_file_size(s: &str) -> std::result::Result<u64, String> {
+    let lower = s.trim().to_ascii_lowercase();
+    let unit_start = lower.find(|c: char| !c.is_ascii_digit()).unwrap_or(lower.len());
+    let (digits, unit) = lower.split_at(unit_start);
+
+    let multiplier: u64 = match unit.trim_end_matches("ib").trim_end_matches('b') {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return Err("unknown unit (use bytes or a K, M or G suffix)".to_string()),
+    };
+    let val: u64 = digits.parse().map_err(|_| "invalid size".to_string())?;
+
+    match val.checked_mul(multiplier) {
+        Some(0) => Err("must be > 0".to_string()),
+        Some(bytes) => Ok(bytes),
+        None => Err("size too large".to_string()),
+    }
+}
+
+/// Validates recency boost: must be a finite, non-negative number.
+fn validate_recency_boost(s: &str) -> std::result::Result<f64, String> {
+    let val: f64 = s.parse().map_err(|_| "invalid number".to_string())?;
+
+    if !val.is_finite() || val < 0.0 {
+        return Err("must be a finite number >= 0".to_string());
+    }
+
+    Ok(val)
+}
+
+/// Validates a BM25 column weight: must be finite and non-negative.
+pub(crate) fn validate_weight(s: &str) -> std::result::Result<f64, String> {
+    let val: f64 = s.parse().map_err(|_| "invalid number".to_string())?;
+
+    if !val.is_finite() || val < 0.0 {
+        return Err("must be a finite number >= 0".to_string());
+    }
+
+    Ok(val)
+}
+
+/// Parses a search query mode (`and`, `or`, `phrase`, `near`, `near:N`).
+fn parse_query_mode(s: &str) -> std::result::Result<QueryMode, String> {
+    s.parse()
+}
+
+/// Parses a `--lang` value: a language name known to [`detect_language`](crate::language::detect_language).
+fn parse_language(s: &str) -> std::result::Result<String, String> {
+    let lang = s.to_ascii_lowercase();
+    if is_known_language(&lang) {
+        Ok(lang)
+    } else {
+        Err(format!("unknown language '{s}' (e.g. rust, python, typescript, go, markdown)"))
+    }
+}
+
+/// Parses `--since` into Unix seconds; a bare date means the start of that day (UTC).
+fn parse_since(s: &str) -> std::result::Result<i64, String> {
+    parse_time_bound(s, false)
+}
+
+/// Parses `--until` into Unix seconds; a bare date means the end of that day (UTC).
+fn parse_until(s: &str) -> std::result::Result<i64, String> {
+    parse_time_bound(s, true)
+}
+
+/// Parses an RFC 3339 time, a `YYYY-MM-DD` date, or an age before now (`30s`, `15m`,
+/// `12h`, `7d`, `2w`) into Unix seconds.
+fn parse_time_bound(s: &str, end_of_day: bool) -> std::result::Result<i64, String> {
+    const EXPECTED: &str = "must be a date (2024-01-31), RFC 3339 time, or age like 7d, 12h, 2w";
+
+    let s = s.trim();
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(time.timestamp());
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let time =
+            if end_of_day { date.and_hms_opt(23, 59, 59) } else { date.and_hms_opt(0, 0, 0) };
+        return time.map(|t| t.and_utc().timestamp()).ok_or_else(|| EXPECTED.to_string());
+    }
+
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (digits, unit) = s.split_at(split);
+    let unit_secs: i64 = match unit.to_ascii_lowercase().as_str() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(EXPECTED.to_string()),
+    };
+    let age = digits
+        .parse::<i64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit_secs))
+        .ok_or_else(|| EXPECTED.to_string())?;
+    Ok(chrono::Utc::now().timestamp().saturating_sub(age))
+}
+
 /// Validates synchronous mode: must be OFF, NORMAL, FULL, or EXTRA.
-fn validate_synchronous(s: &str) -> std::result::Result<String, String> {
+pub(crate) fn validate_synchronous(s: &str) -> std::result::Result<String, String> {
     match s.to_uppercase().as_str() {
         "OFF" | "NORMAL" | "FULL" | "EXTRA" => Ok(s.to_uppercase()),
         _ => Err("must be OFF, NORMAL, FULL, or EXTRA".to_string()),
     }
 }
 
+/// Ids of arguments in `args` (and its subcommands) not left at their default value.
+fn explicit_ids(args: &ArgMatches) -> Vec<String> {
+    let mut ids: Vec<String> = args
+        .ids()
+        .filter(|id| args.value_source(id.as_str()).is_some_and(|s| s != ValueSource::DefaultValue))
+        .map(|id| id.as_str().to_string())
+        .collect();
+    if let Some((_, sub)) = args.subcommand() {
+        ids.extend(explicit_ids(sub));
+    }
+    ids
+}
+
+pub(crate) fn validate_journal_mode(s: &str) -> std::result::Result<String, String> {
+    match s.to_uppercase().as_str() {
+        "WAL" | "DELETE" | "TRUNCATE" => Ok(s.to_uppercase()),
+        _ => Err("must be WAL, DELETE, or TRUNCATE".to_string()),
+    }
+}
+
+/// Validate `--tokenizer`: the name of a [`TokenizerKind`].
+pub(crate) fn validate_tokenizer(s: &str) -> std::result::Result<TokenizerKind, String> {
+    match s.to_lowercase().as_str() {
+        "porter" => Ok(TokenizerKind::Porter),
+        "unicode61" => Ok(TokenizerKind::Unicode61),
+        "trigram" => Ok(TokenizerKind::Trigram),
+        _ => Err("must be porter, unicode61, or trigram".to_string()),
+    }
+}
+
 impl Cli {
+    /// Parse the process arguments like [`Parser::parse`], also recording which
+    /// options were given explicitly (see [`Cli::is_explicit`]).
+    #[must_use]
+    pub fn parse_args() -> Self {
+        Self::parse_args_from(std::env::args_os())
+    }
+
+    /// [`Cli::parse_args`] over an explicit argument list; exits on parse errors.
+    #[must_use]
+    pub fn parse_args_from<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches = Self::command().get_matches_from(args);
+        let mut cli = Self::from_arg_matches(&matches)
+            .unwrap_or_else(|e| e.format(&mut Self::command()).exit());
+        cli.explicit_args = explicit_ids(&matches);
+        cli
+    }
+
+    /// Whether the argument `id` (the field name, e.g. `pragma_cache_size`) was set on
+    /// the command line or via the environment rather than left at its default.
+    ///
+    /// Always `false` for a `Cli` not built by [`Cli::parse_args`].
+    #[must_use]
+    pub fn is_explicit(&self, id: &str) -> bool {
+        self.explicit_args.iter().any(|arg| arg == id)
+    }
+
+    /// Overwrite `config` with the PRAGMA flags that were given explicitly, so they
+    /// win over `.ffts.toml` values.
+    pub fn apply_pragma_overrides(&self, config: &mut PragmaConfig) {
+        if self.is_explicit("journal_mode") {
+            config.journal_mode.clone_from(&self.journal_mode);
+        }
+        if self.is_explicit("pragma_synchronous") {
+            config.synchronous.clone_from(&self.pragma_synchronous);
+        }
+        if self.is_explicit("pragma_cache_size") {
+            config.cache_size = self.pragma_cache_size;
+        }
+        if self.is_explicit("pragma_mmap_size") {
+            config.mmap_size = self.pragma_mmap_size;
+        }
+        if self.is_explicit("pragma_page_size") {
+            config.page_size = self.pragma_page_size;
+        }
+        if self.is_explicit("pragma_busy_timeout") {
+            config.busy_timeout_ms = self.pragma_busy_timeout;
+        }
+        if self.compress_content {
+            config.compress_content = true;
+        }
+        if self.tokenizer.is_some() {
+            config.tokenizer = self.tokenizer;
+        }
+    }
+
+    /// Overwrite `weights` with the `search --weight-*` flags that were given explicitly.
+    pub fn apply_weight_overrides(&self, weights: &mut Bm25Weights) {
+        let Some(Commands::Search { weight_filename, weight_path, weight_content, .. }) =
+            &self.command
+        else {
+            return;
+        };
+        if self.is_explicit("weight_filename") {
+            weights.filename = *weight_filename;
+        }
+        if self.is_explicit("weight_path") {
+            weights.path = *weight_path;
+        }
+        if self.is_explicit("weight_content") {
+            weights.content = *weight_content;
+        }
+    }
+
     /// Get the resolved project directory.
     ///
     /// When no explicit path is provided, uses single-pass project root detection:
@@ -247,12 +878,33 @@ impl Cli {
         Ok(path.to_path_buf())
     }
 
-    /// Get the database path for the project.
+    /// Get the database path for the project: `--db-path` if given, else
+    /// `DB_NAME` in the project directory.
     ///
     /// # Errors
     /// Returns `IndexerError::ConfigInvalid` if `project_dir()` fails.
     pub fn db_path(&self) -> Result<PathBuf> {
-        Ok(self.project_dir()?.join(DB_NAME))
+        let project_dir = self.project_dir()?;
+        let location = self.db_location(&project_dir, None)?;
+        Ok(location.unwrap_or_else(|| project_dir.join(DB_NAME)))
+    }
+
+    /// Resolve a database location override: `--db-path`, else `configured` (from
+    /// the config file). `~` is expanded and relative paths are joined onto
+    /// `project_dir`. Returns `None` when neither is set.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::ConfigInvalid` if `~` expansion fails.
+    pub fn db_location(
+        &self,
+        project_dir: &Path,
+        configured: Option<&Path>,
+    ) -> Result<Option<PathBuf>> {
+        self.db_location
+            .as_deref()
+            .or(configured)
+            .map(|path| Ok(project_dir.join(Self::expand_tilde(path)?)))
+            .transpose()
     }
 
     /// Get the search query as a single string.
@@ -279,7 +931,7 @@ impl Cli {
     #[must_use]
     pub const fn wants_reindex(&self) -> bool {
         match &self.command {
-            Some(Commands::Index { reindex }) => *reindex,
+            Some(Commands::Index { reindex, .. }) => *reindex,
             _ => false,
         }
     }
@@ -300,7 +952,6 @@ impl Cli {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::PragmaConfig;
     use serial_test::serial;
     use std::path::PathBuf;
 
@@ -330,20 +981,122 @@ mod tests {
         let cli = Cli::parse_from([BIN_NAME, "doctor"]);
         assert!(cli.wants_doctor());
         match &cli.command {
-            Some(Commands::Doctor { verbose, json }) => {
+            Some(Commands::Doctor { verbose, json, fail_on, fix }) => {
                 assert!(!*verbose);
                 assert!(!*json);
+                assert_eq!(*fail_on, FailOn::Warn);
+                assert!(!*fix);
             }
             _ => panic!("Expected Doctor subcommand"),
         }
     }
 
+    #[test]
+    fn test_subcommand_doctor_fail_on() {
+        for (arg, expected) in
+            [("warn", FailOn::Warn), ("error", FailOn::Error), ("never", FailOn::Never)]
+        {
+            let cli = Cli::parse_from([BIN_NAME, "doctor", "--fail-on", arg]);
+            match &cli.command {
+                Some(Commands::Doctor { fail_on, .. }) => assert_eq!(*fail_on, expected),
+                _ => panic!("Expected Doctor subcommand"),
+            }
+        }
+
+        assert!(Cli::try_parse_from([BIN_NAME, "doctor", "--fail-on", "info"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommand_hash() {
+        let cli = Cli::parse_from([BIN_NAME, "hash", "src/main.rs"]);
+        match &cli.command {
+            Some(Commands::Hash { file }) => assert_eq!(file, &PathBuf::from("src/main.rs")),
+            _ => panic!("Expected Hash subcommand"),
+        }
+
+        assert!(Cli::try_parse_from([BIN_NAME, "hash"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommand_completions() {
+        let cli = Cli::parse_from([BIN_NAME, "completions", "fish"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Completions { shell: clap_complete::Shell::Fish })
+        ));
+
+        assert!(Cli::try_parse_from([BIN_NAME, "completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommand_index_since() {
+        let cli = Cli::parse_from([BIN_NAME, "index", "--since", "HEAD~1"]);
+        match &cli.command {
+            Some(Commands::Index { since, .. }) => assert_eq!(since.as_deref(), Some("HEAD~1")),
+            _ => panic!("Expected Index subcommand"),
+        }
+
+        assert!(Cli::try_parse_from([BIN_NAME, "index", "--reindex", "--since", "HEAD"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommand_index_reindex_dry_run() {
+        let cli = Cli::parse_from([BIN_NAME, "index", "--reindex", "--dry-run"]);
+        match &cli.command {
+            Some(Commands::Index { reindex, dry_run, .. }) => assert!(*reindex && *dry_run),
+            _ => panic!("Expected Index subcommand"),
+        }
+
+        let cli = Cli::parse_from([BIN_NAME, "index", "--dry-run"]);
+        assert!(matches!(cli.command, Some(Commands::Index { reindex: false, dry_run: true, .. })));
+        assert!(Cli::try_parse_from([BIN_NAME, "index", "--dry-run", "--since", "HEAD"]).is_err());
+    }
+
+    #[test]
+    fn test_subcommand_index_stdin_requires_name() {
+        let cli = Cli::parse_from([BIN_NAME, "index", "--stdin", "--name", "gen/out.txt"]);
+        match &cli.command {
+            Some(Commands::Index { stdin, name, .. }) => {
+                assert!(*stdin);
+                assert_eq!(name.as_deref(), Some("gen/out.txt"));
+            }
+            _ => panic!("Expected Index subcommand"),
+        }
+
+        assert!(Cli::try_parse_from([BIN_NAME, "index", "--stdin"]).is_err());
+        assert!(Cli::try_parse_from([BIN_NAME, "index", "--name", "x.txt"]).is_err());
+        assert!(
+            Cli::try_parse_from([BIN_NAME, "index", "--stdin", "--name", "x", "--reindex"])
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_subcommand_interactive() {
+        let cli = Cli::parse_from([BIN_NAME, "interactive"]);
+        assert!(matches!(cli.command, Some(Commands::Interactive)));
+    }
+
+    #[test]
+    fn test_subcommand_stats_filters() {
+        let cli = Cli::parse_from([BIN_NAME, "stats", "--in", "src", "--ext", "rs", "--json"]);
+        match &cli.command {
+            Some(Commands::Stats { in_dir, ext, json }) => {
+                assert_eq!(in_dir.as_deref(), Some("src"));
+                assert_eq!(ext.as_deref(), Some("rs"));
+                assert!(*json);
+            }
+            _ => panic!("Expected Stats subcommand"),
+        }
+    }
+
     #[test]
     fn test_subcommand_doctor_verbose() {
         let cli = Cli::parse_from([BIN_NAME, "doctor", "-v"]);
         assert!(cli.wants_doctor());
         match &cli.command {
-            Some(Commands::Doctor { verbose, json }) => {
+            Some(Commands::Doctor { verbose, json, .. }) => {
                 assert!(*verbose);
                 assert!(!*json);
             }
@@ -407,7 +1160,7 @@ mod tests {
         let cli = Cli::parse_from([BIN_NAME, "search", "--benchmark"]);
         assert!(cli.query.is_empty());
         match &cli.command {
-            Some(Commands::Search { query, benchmark, paths, format, no_auto_init }) => {
+            Some(Commands::Search { query, benchmark, paths, format, no_auto_init, .. }) => {
                 assert!(query.is_empty());
                 assert!(*benchmark);
                 assert!(!*paths);
@@ -424,7 +1177,7 @@ mod tests {
         let cli = Cli::parse_from([BIN_NAME, "search", "test", "query"]);
         assert!(cli.query.is_empty()); // Top-level query is empty
         match &cli.command {
-            Some(Commands::Search { query, benchmark, paths, format, no_auto_init }) => {
+            Some(Commands::Search { query, benchmark, paths, format, no_auto_init, .. }) => {
                 assert_eq!(query, &vec!["test", "query"]);
                 assert!(!*benchmark);
                 assert!(!*paths);
@@ -446,6 +1199,274 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_search_subcommand_explain_plan() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--explain-plan", "test"]);
+        match &cli.command {
+            Some(Commands::Search { explain_plan, query, .. }) => {
+                assert!(*explain_plan);
+                assert_eq!(query, &vec!["test"]);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_validate_only() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--validate-only", "\"foo"]);
+        match &cli.command {
+            Some(Commands::Search { validate_only, query, .. }) => {
+                assert!(*validate_only);
+                assert_eq!(query, &vec!["\"foo".to_string()]);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_line_ending() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--line-ending", "crlf", "test"]);
+        match &cli.command {
+            Some(Commands::Search { line_ending, .. }) => {
+                assert_eq!(*line_ending, LineEnding::CrLf)
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+        assert_eq!(LineEnding::Lf.as_str(), "\n");
+        assert_eq!(LineEnding::CrLf.as_str(), "\r\n");
+    }
+
+    #[test]
+    fn test_search_subcommand_recency() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--recency", "2.5", "test"]);
+        match &cli.command {
+            Some(Commands::Search { recency, .. }) => assert_eq!(*recency, Some(2.5)),
+            _ => panic!("Expected Search subcommand"),
+        }
+
+        let result = Cli::try_parse_from([BIN_NAME, "search", "--recency", "-1", "test"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_search_subcommand_position_ranks() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--position-ranks", "test"]);
+        match &cli.command {
+            Some(Commands::Search { position_ranks, .. }) => assert!(*position_ranks),
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_limit() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "test"]);
+        assert!(matches!(cli.command, Some(Commands::Search { limit: DEFAULT_SEARCH_LIMIT, .. })));
+
+        let cli = Cli::parse_from([BIN_NAME, "search", "--limit", "5", "test"]);
+        assert!(matches!(cli.command, Some(Commands::Search { limit: 5, .. })));
+
+        let cli = Cli::parse_from([BIN_NAME, "search", "--max-results", "10000", "test"]);
+        assert!(matches!(cli.command, Some(Commands::Search { limit: 10_000, .. })));
+
+        for bad in ["0", "10001", "-3"] {
+            let arg = format!("--limit={bad}");
+            assert!(Cli::try_parse_from([BIN_NAME, "search", &arg, "test"]).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_weights() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "test"]);
+        match &cli.command {
+            Some(Commands::Search { weight_filename, weight_path, weight_content, .. }) => {
+                assert!((*weight_filename - 100.0).abs() < f64::EPSILON);
+                assert!((*weight_path - 50.0).abs() < f64::EPSILON);
+                assert!((*weight_content - 1.0).abs() < f64::EPSILON);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+
+        let cli = Cli::parse_from([BIN_NAME, "search", "--weight-content", "20.5", "test"]);
+        match &cli.command {
+            Some(Commands::Search { weight_content, .. }) => {
+                assert!((*weight_content - 20.5).abs() < f64::EPSILON);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+
+        for bad in ["-1", "NaN", "inf", "abc"] {
+            let arg = format!("--weight-path={bad}");
+            assert!(Cli::try_parse_from([BIN_NAME, "search", &arg, "test"]).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_sort_density() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--density", "--sort", "density", "test"]);
+        match &cli.command {
+            Some(Commands::Search { density, sort, .. }) => {
+                assert!(*density);
+                assert_eq!(*sort, SortOrder::Density);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_sort_file_orders() {
+        for (value, expected) in [
+            ("path", SortOrder::PathAsc),
+            ("mtime", SortOrder::MtimeDesc),
+            ("size", SortOrder::SizeDesc),
+        ] {
+            let cli = Cli::parse_from([BIN_NAME, "search", "--sort", value, "test"]);
+            match &cli.command {
+                Some(Commands::Search { sort, .. }) => assert_eq!(*sort, expected),
+                _ => panic!("Expected Search subcommand"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_query_mode() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--mode", "near:5", "main function"]);
+        match &cli.command {
+            Some(Commands::Search { mode, .. }) => assert_eq!(*mode, QueryMode::Near(5)),
+            _ => panic!("Expected Search subcommand"),
+        }
+
+        let cli = Cli::parse_from([BIN_NAME, "search", "test"]);
+        match &cli.command {
+            Some(Commands::Search { mode, .. }) => assert_eq!(*mode, QueryMode::And),
+            _ => panic!("Expected Search subcommand"),
+        }
+
+        for bad in ["xor", "near:", "near:x", "near:-1"] {
+            let arg = format!("--mode={bad}");
+            assert!(Cli::try_parse_from([BIN_NAME, "search", &arg, "test"]).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_raw_query() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--raw-query", "a OR b"]);
+        match &cli.command {
+            Some(Commands::Search { raw_query, query, .. }) => {
+                assert!(*raw_query);
+                assert_eq!(query, &["a OR b".to_string()]);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_prefix() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--prefix", "con"]);
+        match &cli.command {
+            Some(Commands::Search { prefix, .. }) => assert!(*prefix),
+            _ => panic!("Expected Search subcommand"),
+        }
+        assert!(
+            Cli::try_parse_from([BIN_NAME, "search", "--prefix", "--raw-query", "con"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_search_subcommand_json_pretty() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--format", "json", "--json-pretty", "x"]);
+        match &cli.command {
+            Some(Commands::Search { json_pretty, .. }) => assert!(*json_pretty),
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_lang() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--lang", "Rust", "x"]);
+        match &cli.command {
+            Some(Commands::Search { lang, .. }) => assert_eq!(lang.as_deref(), Some("rust")),
+            _ => panic!("Expected Search subcommand"),
+        }
+        assert!(Cli::try_parse_from([BIN_NAME, "search", "--lang", "cobol", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_bounds() {
+        assert_eq!(parse_since("2024-01-31"), Ok(1_706_659_200));
+        assert_eq!(parse_until("2024-01-31"), Ok(1_706_745_599));
+        assert_eq!(parse_since("2024-01-31T12:00:00+02:00"), Ok(1_706_695_200));
+
+        let now = chrono::Utc::now().timestamp();
+        assert!((now - 7 * 86_400 - parse_since("7d").unwrap()).abs() <= 5);
+        assert!((now - 14 * 86_400 - parse_until("2W").unwrap()).abs() <= 5);
+
+        for bad in ["", "7", "d", "7y", "-7d", "2024-13-01", "99999999999999999999d"] {
+            assert!(parse_since(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_search_subcommand_line_numbers() {
+        for flag in ["--line-numbers", "-n"] {
+            let cli = Cli::parse_from([BIN_NAME, "search", flag, "test"]);
+            match &cli.command {
+                Some(Commands::Search { line_numbers, .. }) => assert!(*line_numbers),
+                _ => panic!("Expected Search subcommand"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_watch_subcommand() {
+        let cli = Cli::parse_from([BIN_NAME, "watch"]);
+        match &cli.command {
+            Some(Commands::Watch { debounce_ms, interval_ms }) => {
+                assert_eq!((*debounce_ms, *interval_ms), (300, 500));
+            }
+            _ => panic!("Expected Watch subcommand"),
+        }
+
+        let cli =
+            Cli::parse_from([BIN_NAME, "watch", "--debounce-ms", "50", "--interval-ms", "100"]);
+        assert!(matches!(cli.command, Some(Commands::Watch { debounce_ms: 50, interval_ms: 100 })));
+        assert!(Cli::try_parse_from([BIN_NAME, "watch", "--interval-ms", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_search_subcommand_verify_exists() {
+        let cli = Cli::parse_from([BIN_NAME, "search", "--verify-exists", "test"]);
+        match &cli.command {
+            Some(Commands::Search { verify_exists, .. }) => assert!(*verify_exists),
+            _ => panic!("Expected Search subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_search_benchmark_stats_json() {
+        let cli = Cli::parse_from([
+            BIN_NAME,
+            "search",
+            "--benchmark",
+            "--stats-json",
+            "--iterations",
+            "5",
+        ]);
+        match &cli.command {
+            Some(Commands::Search { benchmark, stats_json, iterations, .. }) => {
+                assert!(*benchmark);
+                assert!(*stats_json);
+                assert_eq!(*iterations, 5);
+            }
+            _ => panic!("Expected Search subcommand"),
+        }
+
+        // Benchmark-only flags require --benchmark; zero iterations is rejected
+        assert!(Cli::try_parse_from([BIN_NAME, "search", "--stats-json"]).is_err());
+        assert!(
+            Cli::try_parse_from([BIN_NAME, "search", "--benchmark", "--iterations", "0"]).is_err()
+        );
+    }
+
     #[test]
     fn test_implicit_search_with_query() {
         // Main usage: query without subcommand triggers implicit search
@@ -571,6 +1592,12 @@ mod tests {
         assert_eq!(cli.pragma_synchronous, "NORMAL");
     }
 
+    #[test]
+    fn test_hidden_flag() {
+        assert!(!Cli::parse_from([BIN_NAME]).hidden);
+        assert!(Cli::parse_from([BIN_NAME, "--hidden", "index"]).hidden);
+    }
+
     #[test]
     fn test_follow_symlinks_flag() {
         let cli = Cli::parse_from([BIN_NAME]);
@@ -580,6 +1607,71 @@ mod tests {
         assert!(cli.follow_symlinks);
     }
 
+    #[test]
+    fn test_hash_denylist_file_flag() {
+        let cli = Cli::parse_from([BIN_NAME]);
+        assert!(cli.hash_denylist_file.is_none());
+
+        let cli = Cli::parse_from([BIN_NAME, "--hash-denylist-file", "vendored.txt", "index"]);
+        assert_eq!(cli.hash_denylist_file, Some(PathBuf::from("vendored.txt")));
+    }
+
+    #[test]
+    fn test_config_flag() {
+        let cli = Cli::parse_from([BIN_NAME]);
+        assert!(cli.config.is_none());
+
+        let cli = Cli::parse_from([BIN_NAME, "--config", "ci.toml", "index"]);
+        assert_eq!(cli.config, Some(PathBuf::from("ci.toml")));
+    }
+
+    #[test]
+    fn test_subcommand_prune() {
+        let cli = Cli::parse_from([BIN_NAME, "prune"]);
+        assert!(matches!(cli.command, Some(Commands::Prune { optimize: false })));
+
+        let cli = Cli::parse_from([BIN_NAME, "prune", "--optimize"]);
+        assert!(matches!(cli.command, Some(Commands::Prune { optimize: true })));
+    }
+
+    #[test]
+    fn test_subcommand_compact() {
+        let cli = Cli::parse_from([BIN_NAME, "compact"]);
+        assert!(matches!(cli.command, Some(Commands::Compact { into: None })));
+
+        let cli = Cli::parse_from([BIN_NAME, "compact", "--into", "copy.db"]);
+        match &cli.command {
+            Some(Commands::Compact { into }) => assert_eq!(into, &Some(PathBuf::from("copy.db"))),
+            _ => panic!("Expected Compact subcommand"),
+        }
+    }
+
+    #[test]
+    fn test_extension_filter_flags() {
+        let cli = Cli::parse_from([BIN_NAME, "index"]);
+        assert!(cli.include_extensions.is_empty());
+        assert!(cli.exclude_extensions.is_empty());
+
+        let cli = Cli::parse_from([BIN_NAME, "--ext", "rs,md", "--exclude-ext", "lock", "index"]);
+        assert_eq!(cli.include_extensions, vec!["rs", "md"]);
+        assert_eq!(cli.exclude_extensions, vec!["lock"]);
+    }
+
+    #[test]
+    fn test_index_hidden_file_repeatable() {
+        let cli = Cli::parse_from([BIN_NAME]);
+        assert!(cli.index_hidden_files.is_empty());
+
+        let cli = Cli::parse_from([
+            BIN_NAME,
+            "--index-hidden-file",
+            ".env.example",
+            "--index-hidden-file=.editorconfig",
+            "index",
+        ]);
+        assert_eq!(cli.index_hidden_files, vec![".env.example", ".editorconfig"]);
+    }
+
     #[test]
     fn test_refresh_flag() {
         let cli = Cli::parse_from([BIN_NAME]);
@@ -627,6 +1719,23 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_max_file_size_suffixes() {
+        let parse = |value: &str| {
+            Cli::try_parse_from([BIN_NAME, "--max-file-size", value]).map(|cli| cli.max_file_size)
+        };
+        assert_eq!(parse("2048").unwrap(), Some(2048));
+        assert_eq!(parse("512K").unwrap(), Some(512 * 1024));
+        assert_eq!(parse("2M").unwrap(), Some(2 * 1024 * 1024));
+        assert_eq!(parse("2mb").unwrap(), Some(2 * 1024 * 1024));
+        assert_eq!(parse("1GiB").unwrap(), Some(1024 * 1024 * 1024));
+        assert_eq!(Cli::parse_from([BIN_NAME]).max_file_size, None);
+
+        for bad in ["0", "0M", "", "M", "1.5M", "2T", "-1", "99999999999999999999"] {
+            assert!(parse(bad).is_err(), "{bad:?} should be rejected");
+        }
+    }
+
     #[test]
     fn test_pragma_mmap_size_valid() {
         let cli = Cli::parse_from([BIN_NAME, "--pragma-mmap-size=268435456"]);
@@ -666,6 +1775,40 @@ mod tests {
         assert_eq!(cli.pragma_synchronous, "EXTRA");
     }
 
+    #[test]
+    fn test_journal_mode_values() {
+        let cli = Cli::parse_from([BIN_NAME]);
+        assert_eq!(cli.journal_mode, "WAL");
+
+        let cli = Cli::parse_from([BIN_NAME, "--journal-mode=delete"]);
+        assert_eq!(cli.journal_mode, "DELETE");
+
+        assert!(Cli::try_parse_from([BIN_NAME, "--journal-mode=MEMORY"]).is_err());
+    }
+
+    #[test]
+    fn test_explicit_flags_override_file_settings() {
+        let cli = Cli::parse_args_from([
+            BIN_NAME,
+            "--pragma-cache-size=4000",
+            "search",
+            "--weight-path=7",
+            "foo",
+        ]);
+        assert!(cli.is_explicit("pragma_cache_size"));
+        assert!(!cli.is_explicit("pragma_page_size"));
+
+        let mut pragma =
+            PragmaConfig { cache_size: -64000, page_size: 8192, ..PragmaConfig::default() };
+        cli.apply_pragma_overrides(&mut pragma);
+        assert_eq!(pragma.cache_size, 4000);
+        assert_eq!(pragma.page_size, 8192);
+
+        let mut weights = Bm25Weights { filename: 10.0, path: 10.0, content: 10.0 };
+        cli.apply_weight_overrides(&mut weights);
+        assert_eq!(weights, Bm25Weights { filename: 10.0, path: 7.0, content: 10.0 });
+    }
+
     #[test]
     fn test_pragma_synchronous_invalid() {
         let result = Cli::try_parse_from([BIN_NAME, "--pragma-synchronous=INVALID"]);
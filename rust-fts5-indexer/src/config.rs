@@ -0,0 +1,341 @@
+//! Project config file (`.ffts.toml`).
+//!
+//! # Precedence
+//!
+//! 1. `--config <PATH>` (explicit file, must exist)
+//! 2. The nearest `.ffts.toml` found walking up from the project root
+//! 3. Built-in defaults
+//!
+//! Only one file is used; discovered files are not merged. CLI flags are
+//! applied on top of whichever config wins.
+//!
+//! # Example
+//!
+//! ```toml
+//! follow_symlinks = false
+//! lossy_utf8 = false
+//! index_content = true
+//! index_hidden_files = [".env.example", ".editorconfig"]
+//! max_file_size = 2097152
+//! batch_size = 1000
+//! transaction_threshold = 50
+//! auto_batch = true
+//! db_path = ".cache/ffts.db"
+//! include_extensions = ["rs", "md"]
+//! exclude_extensions = ["lock"]
+//! tokenizer = "trigram"
+//!
+//! [pragma]
+//! journal_mode = "DELETE"
+//! synchronous = "FULL"
+//! cache_size = -64000
+//!
+//! [weights]
+//! filename = 100.0
+//! path = 50.0
+//! content = 5.0
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::cli;
+use crate::db::{Bm25Weights, PragmaConfig};
+use crate::error::{IndexerError, Result};
+use crate::indexer::IndexerConfig;
+
+/// Config filename looked up by [`Config::discover`].
+pub const CONFIG_FILENAME: &str = ".ffts.toml";
+
+/// Settings read from a `.ffts.toml` file. Unset keys keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Follow symlinks while indexing
+    pub follow_symlinks: Option<bool>,
+    /// Index invalid UTF-8 with replacements (see `IndexerConfig::lossy_utf8`)
+    pub lossy_utf8: Option<bool>,
+    /// Store file content; `false` indexes paths only (see `IndexerConfig::index_content`)
+    pub index_content: Option<bool>,
+    /// Hidden filenames to index (see `IndexerConfig::hidden_allowlist`)
+    pub index_hidden_files: Option<Vec<String>>,
+    /// Maximum file size to index (in bytes)
+    pub max_file_size: Option<u64>,
+    /// Files per transaction batch
+    pub batch_size: Option<usize>,
+    /// Autocommit upserts before a transaction (see `IndexerConfig::transaction_threshold`)
+    pub transaction_threshold: Option<usize>,
+    /// Auto-tune transaction batching (see `IndexerConfig::auto_batch`)
+    pub auto_batch: Option<bool>,
+    /// Database file location, relative to the project root (see `--db-path`)
+    pub db_path: Option<PathBuf>,
+    /// Only index these extensions (see `--include-ext`)
+    pub include_extensions: Option<Vec<String>>,
+    /// Never index these extensions (see `--exclude-ext`)
+    pub exclude_extensions: Option<Vec<String>>,
+    /// FTS5 tokenizer (see `--tokenizer`)
+    pub tokenizer: Option<String>,
+    /// `SQLite` PRAGMA settings (`[pragma]` table)
+    #[serde(default)]
+    pub pragma: PragmaSettings,
+    /// BM25 column weights (`[weights]` table)
+    #[serde(default)]
+    pub weights: WeightSettings,
+}
+
+/// The `[pragma]` table; keys mirror the `--pragma-*` and `--journal-mode` flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PragmaSettings {
+    pub journal_mode: Option<String>,
+    pub synchronous: Option<String>,
+    pub cache_size: Option<i64>,
+    pub mmap_size: Option<i64>,
+    pub page_size: Option<i64>,
+    pub busy_timeout: Option<i64>,
+}
+
+/// The `[weights]` table; keys mirror the `search --weight-*` flags.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightSettings {
+    pub filename: Option<f64>,
+    pub path: Option<f64>,
+    pub content: Option<f64>,
+}
+
+impl Config {
+    /// Parse a config file.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Io` if the file cannot be read, or
+    /// `IndexerError::ConfigFile` if it is not valid TOML for this schema or a
+    /// value is out of range for its flag.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).map_err(|e| IndexerError::Io { source: e })?;
+        let config: Self = toml::from_str(&contents).map_err(|e| IndexerError::ConfigFile {
+            path: path.display().to_string(),
+            reason: e.message().to_string(),
+        })?;
+        config.validate().map_err(|reason| IndexerError::ConfigFile {
+            path: path.display().to_string(),
+            reason,
+        })?;
+        Ok(config)
+    }
+
+    /// Check values against the same rules as the matching CLI flags.
+    fn validate(&self) -> std::result::Result<(), String> {
+        fn check<T: ToString, U>(
+            key: &str,
+            value: Option<&T>,
+            parse: fn(&str) -> std::result::Result<U, String>,
+        ) -> std::result::Result<(), String> {
+            match value {
+                Some(value) => {
+                    parse(&value.to_string()).map(drop).map_err(|e| format!("{key}: {e}"))
+                }
+                None => Ok(()),
+            }
+        }
+
+        let pragma = &self.pragma;
+        check("pragma.journal_mode", pragma.journal_mode.as_ref(), cli::validate_journal_mode)?;
+        check("pragma.synchronous", pragma.synchronous.as_ref(), cli::validate_synchronous)?;
+        check("pragma.cache_size", pragma.cache_size.as_ref(), cli::validate_cache_size)?;
+        check("pragma.mmap_size", pragma.mmap_size.as_ref(), cli::validate_mmap_size)?;
+        check("pragma.page_size", pragma.page_size.as_ref(), cli::validate_page_size)?;
+        check("pragma.busy_timeout", pragma.busy_timeout.as_ref(), cli::validate_busy_timeout)?;
+        check("tokenizer", self.tokenizer.as_ref(), cli::validate_tokenizer)?;
+        check("weights.filename", self.weights.filename.as_ref(), cli::validate_weight)?;
+        check("weights.path", self.weights.path.as_ref(), cli::validate_weight)?;
+        check("weights.content", self.weights.content.as_ref(), cli::validate_weight)
+    }
+
+    /// Find the nearest [`CONFIG_FILENAME`] in `start_dir` or any ancestor.
+    #[must_use]
+    pub fn discover(start_dir: &Path) -> Option<PathBuf> {
+        start_dir.ancestors().map(|dir| dir.join(CONFIG_FILENAME)).find(|path| path.is_file())
+    }
+
+    /// Load `explicit` if given, else the discovered file, else defaults.
+    ///
+    /// Returns the config and the file it came from (if any).
+    ///
+    /// # Errors
+    /// Returns `IndexerError` if the selected file cannot be read or parsed.
+    pub fn resolve(explicit: Option<&Path>, start_dir: &Path) -> Result<(Self, Option<PathBuf>)> {
+        let path = explicit.map(Path::to_path_buf).or_else(|| Self::discover(start_dir));
+        match path {
+            Some(path) => Ok((Self::load(&path)?, Some(path))),
+            None => Ok((Self::default(), None)),
+        }
+    }
+
+    /// Overwrite `config` fields that are set in this file.
+    pub fn apply(&self, config: &mut IndexerConfig) {
+        if let Some(follow_symlinks) = self.follow_symlinks {
+            config.follow_symlinks = follow_symlinks;
+        }
+        if let Some(lossy_utf8) = self.lossy_utf8 {
+            config.lossy_utf8 = lossy_utf8;
+        }
+        if let Some(index_content) = self.index_content {
+            config.index_content = index_content;
+        }
+        if let Some(hidden) = &self.index_hidden_files {
+            config.hidden_allowlist.clone_from(hidden);
+        }
+        if let Some(max_file_size) = self.max_file_size {
+            config.max_file_size = max_file_size;
+        }
+        if let Some(batch_size) = self.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(threshold) = self.transaction_threshold {
+            config.transaction_threshold = threshold;
+        }
+        if let Some(auto_batch) = self.auto_batch {
+            config.auto_batch = auto_batch;
+        }
+        if let Some(include) = &self.include_extensions {
+            config.include_extensions = Some(include.clone());
+        }
+        if let Some(exclude) = &self.exclude_extensions {
+            config.exclude_extensions.clone_from(exclude);
+        }
+    }
+
+    /// Overwrite `config` PRAGMA settings that are set in the `[pragma]` table, and
+    /// the tokenizer if `tokenizer` is set.
+    pub fn apply_pragma(&self, config: &mut PragmaConfig) {
+        // Validated by `load`
+        if let Some(Ok(tokenizer)) = self.tokenizer.as_deref().map(cli::validate_tokenizer) {
+            config.tokenizer = Some(tokenizer);
+        }
+        let pragma = &self.pragma;
+        if let Some(journal_mode) = &pragma.journal_mode {
+            config.journal_mode = journal_mode.to_uppercase();
+        }
+        if let Some(synchronous) = &pragma.synchronous {
+            config.synchronous = synchronous.to_uppercase();
+        }
+        if let Some(cache_size) = pragma.cache_size {
+            config.cache_size = cache_size;
+        }
+        if let Some(mmap_size) = pragma.mmap_size {
+            config.mmap_size = mmap_size;
+        }
+        if let Some(page_size) = pragma.page_size {
+            config.page_size = page_size;
+        }
+        if let Some(busy_timeout) = pragma.busy_timeout {
+            config.busy_timeout_ms = busy_timeout;
+        }
+    }
+
+    /// Overwrite `weights` columns that are set in the `[weights]` table.
+    pub fn apply_weights(&self, weights: &mut Bm25Weights) {
+        if let Some(filename) = self.weights.filename {
+            weights.filename = filename;
+        }
+        if let Some(path) = self.weights.path {
+            weights.path = path;
+        }
+        if let Some(content) = self.weights.content {
+            weights.content = content;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TokenizerKind;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_discover_finds_parent_config_from_nested_start() {
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("packages/app/src");
+        fs::create_dir_all(&nested).unwrap();
+        let config_path = dir.path().join(CONFIG_FILENAME);
+        fs::write(&config_path, "batch_size = 42\n").unwrap();
+
+        assert_eq!(Config::discover(&nested), Some(config_path.clone()));
+
+        let (config, source) = Config::resolve(None, &nested).unwrap();
+        assert_eq!(config.batch_size, Some(42));
+        assert_eq!(source, Some(config_path));
+    }
+
+    #[test]
+    fn test_explicit_config_overrides_discovered() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join(CONFIG_FILENAME), "batch_size = 42\n").unwrap();
+        let explicit = dir.path().join("custom.toml");
+        fs::write(&explicit, "batch_size = 7\nfollow_symlinks = true\n").unwrap();
+
+        let (config, source) = Config::resolve(Some(&explicit), dir.path()).unwrap();
+        assert_eq!(source, Some(explicit));
+
+        let mut indexer_config = IndexerConfig::default();
+        config.apply(&mut indexer_config);
+        assert_eq!(indexer_config.batch_size, 7);
+        assert!(indexer_config.follow_symlinks);
+        assert_eq!(indexer_config.max_file_size, IndexerConfig::default().max_file_size);
+    }
+
+    #[test]
+    fn test_unknown_key_is_rejected() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILENAME);
+        fs::write(&path, "batch_sise = 7\n").unwrap();
+
+        assert!(matches!(Config::load(&path), Err(IndexerError::ConfigFile { .. })));
+    }
+
+    #[test]
+    fn test_pragma_and_weight_tables() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILENAME);
+        fs::write(
+            &path,
+            "exclude_extensions = [\"lock\"]\ntokenizer = \"trigram\"\n\n[pragma]\njournal_mode = \"delete\"\ncache_size = -64000\n\n[weights]\ncontent = 5.0\n",
+        )
+        .unwrap();
+        let config = Config::load(&path).unwrap();
+
+        let mut pragma = PragmaConfig::default();
+        config.apply_pragma(&mut pragma);
+        assert_eq!(pragma.journal_mode, "DELETE");
+        assert_eq!(pragma.cache_size, -64000);
+        assert_eq!(pragma.page_size, PragmaConfig::default().page_size);
+        assert_eq!(pragma.tokenizer, Some(TokenizerKind::Trigram));
+
+        let mut weights = Bm25Weights::default();
+        config.apply_weights(&mut weights);
+        assert_eq!(weights, Bm25Weights { content: 5.0, ..Bm25Weights::default() });
+
+        let mut indexer_config = IndexerConfig::default();
+        config.apply(&mut indexer_config);
+        assert_eq!(indexer_config.exclude_extensions, vec!["lock"]);
+        assert_eq!(indexer_config.include_extensions, None);
+    }
+
+    #[test]
+    fn test_out_of_range_value_is_rejected() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILENAME);
+        fs::write(&path, "[pragma]\npage_size = 1000\n").unwrap();
+
+        match Config::load(&path) {
+            Err(IndexerError::ConfigFile { reason, .. }) => {
+                assert!(reason.starts_with("pragma.page_size"), "{reason}");
+            }
+            other => panic!("expected ConfigFile error, got {other:?}"),
+        }
+    }
+}
@@ -11,6 +11,13 @@ pub const EXPECTED_APPLICATION_ID: u32 = 0xA17E_6D42;
 /// `application_id` stored as i32 with the same bit pattern.
 pub const APPLICATION_ID_I32: i32 = i32::from_ne_bytes(EXPECTED_APPLICATION_ID.to_ne_bytes());
 
+/// Schema version stamped into `PRAGMA user_version` by `Database::init_schema`.
+///
+/// Version 0 is an unstamped database: either the v0.9 layout or a current layout
+/// written before versions were recorded. Bump this together with a new step in
+/// `Database::migrate_schema`.
+pub const SCHEMA_VERSION: i32 = 1;
+
 #[cfg(test)]
 mod tests {
     use super::*;
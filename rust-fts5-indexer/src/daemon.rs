@@ -0,0 +1,287 @@
+//! Persistent search daemon on a Unix domain socket (`ffts-grep daemon`).
+//!
+//! Per-query process startup and PRAGMA setup dominate latency for small
+//! indexes; the daemon opens the database once and answers queries over a
+//! socket instead.
+//!
+//! # Protocol
+//!
+//! Newline-delimited JSON. Each request line is an object:
+//!
+//! ```json
+//! {"query": "main", "limit": 10, "paths": false}
+//! ```
+//!
+//! `limit` and `paths` are optional. Each request gets exactly one response line:
+//! the same object `search --format json` prints (`query`, `count`, `results`),
+//! or `{"error": "..."}`. A connection may send any number of requests.
+//!
+//! # Design
+//!
+//! - **One connection**: every client thread shares the database behind a mutex
+//! - **Stale sockets**: a leftover socket file nobody listens on is removed at
+//!   startup; a live daemon or a non-socket file at the path is an error
+//! - **Graceful shutdown**: the accept loop polls a flag (set from SIGTERM/SIGINT
+//!   by [`install_shutdown_handler`]) and removes the socket file on exit
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{MAX_SEARCH_LIMIT, OutputFormat};
+use crate::db::Database;
+use crate::error::{IndexerError, Result};
+use crate::search::{SearchConfig, Searcher};
+
+/// How often the accept loop checks for shutdown while idle.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set by the signal handler installed with [`install_shutdown_handler`].
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// One request line.
+#[derive(Debug, Deserialize)]
+struct Request {
+    query: String,
+    limit: Option<u32>,
+    paths: Option<bool>,
+}
+
+/// Response line for a request that could not be answered.
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Search daemon bound to a socket.
+pub struct Daemon {
+    db: Arc<Mutex<Database>>,
+    listener: UnixListener,
+    socket: PathBuf,
+    config: SearchConfig,
+}
+
+impl Daemon {
+    /// Bind `socket`, removing a stale socket file left by a previous run.
+    ///
+    /// `config` is the base for every request; its format is forced to JSON.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Io` with `AddrInUse` if another daemon is
+    /// listening on `socket` or a non-socket file is in the way, or if binding fails.
+    pub fn bind(db: Database, socket: &Path, config: SearchConfig) -> Result<Self> {
+        remove_stale_socket(socket)?;
+        let listener = UnixListener::bind(socket)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            db: Arc::new(Mutex::new(db)),
+            listener,
+            socket: socket.to_path_buf(),
+            config: SearchConfig { format: OutputFormat::Json, pretty: false, ..config },
+        })
+    }
+
+    /// Socket path this daemon listens on.
+    #[must_use]
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Accept and serve connections until `shutdown` becomes true.
+    ///
+    /// Each connection is served on its own thread; in-flight connections are
+    /// not waited for.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Io` if accepting a connection fails.
+    pub fn serve(&self, shutdown: &AtomicBool) -> Result<()> {
+        while !shutdown.load(Ordering::SeqCst) {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let db = Arc::clone(&self.db);
+                    let config = self.config.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = serve_connection(stream, &db, &config) {
+                            tracing::debug!(error = %e, "Daemon connection closed with error");
+                        }
+                    });
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        // Best effort: a leftover file is cleaned up by the next bind anyway
+        let _ = std::fs::remove_file(&self.socket);
+    }
+}
+
+/// Make SIGTERM and SIGINT request a graceful shutdown; returns the flag they set.
+pub fn install_shutdown_handler() -> &'static AtomicBool {
+    extern "C" fn on_signal(_: libc::c_int) {
+        SHUTDOWN.store(true, Ordering::SeqCst);
+    }
+
+    let handler = on_signal as extern "C" fn(libc::c_int);
+    // The handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGTERM, handler as libc::sighandler_t);
+        libc::signal(libc::SIGINT, handler as libc::sighandler_t);
+    }
+    &SHUTDOWN
+}
+
+/// Remove `socket` if it is a socket file no process is listening on.
+fn remove_stale_socket(socket: &Path) -> Result<()> {
+    let Ok(metadata) = std::fs::symlink_metadata(socket) else {
+        return Ok(());
+    };
+    let in_use = |reason: &str| IndexerError::Io {
+        source: io::Error::new(io::ErrorKind::AddrInUse, format!("{}: {reason}", socket.display())),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(in_use("exists and is not a socket"));
+    }
+    if UnixStream::connect(socket).is_ok() {
+        return Err(in_use("another daemon is listening"));
+    }
+    tracing::info!(path = %socket.display(), "Removing stale daemon socket");
+    std::fs::remove_file(socket)?;
+    Ok(())
+}
+
+/// Answer request lines on one connection until the client disconnects.
+fn serve_connection(stream: UnixStream, db: &Mutex<Database>, config: &SearchConfig) -> Result<()> {
+    // Accepted sockets inherit non-blocking mode from the listener on some platforms
+    stream.set_nonblocking(false)?;
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut response = Vec::new();
+        if let Err(e) = answer(&line, db, config, &mut response) {
+            response.clear();
+            serde_json::to_writer(&mut response, &ErrorResponse { error: e.to_string() })?;
+            response.push(b'\n');
+        }
+        writer.write_all(&response)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Run one request line and write its JSON response line to `output`.
+fn answer(
+    line: &str,
+    db: &Mutex<Database>,
+    config: &SearchConfig,
+    output: &mut Vec<u8>,
+) -> Result<()> {
+    let request: Request = serde_json::from_str(line)?;
+    let mut config = config.clone();
+    if let Some(limit) = request.limit {
+        if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+            return Err(IndexerError::ConfigInvalid {
+                field: "limit".to_string(),
+                value: limit.to_string(),
+                reason: format!("must be between 1 and {MAX_SEARCH_LIMIT}"),
+            });
+        }
+        config.max_results = limit;
+    }
+    if let Some(paths) = request.paths {
+        config.paths_only = paths;
+    }
+
+    // A search that panicked left no transaction open, so the connection is reusable
+    let mut db = db.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    let mut searcher = Searcher::new(&mut db, config);
+    let results = searcher.search(&request.query)?;
+    searcher.format_results(&results, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::PragmaConfig;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_query_over_socket_returns_json() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db"), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/main.rs", "fn main() { daemon_needle(); }", 0, 30).unwrap();
+        let socket = dir.path().join("ffts.sock");
+        // A stale socket from a crashed run is replaced
+        drop(UnixListener::bind(&socket).unwrap());
+
+        let daemon = Daemon::bind(db, &socket, SearchConfig::default()).unwrap();
+        let shutdown = AtomicBool::new(false);
+        thread::scope(|scope| {
+            scope.spawn(|| daemon.serve(&shutdown).unwrap());
+
+            let stream = UnixStream::connect(&socket).unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            writer
+                .write_all(b"{\"query\": \"daemon_needle\", \"limit\": 5}\n{\"query\": 1}\n")
+                .unwrap();
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert_eq!(response["query"], "daemon_needle");
+            assert_eq!(response["count"], 1);
+            assert_eq!(response["results"][0]["path"], "src/main.rs");
+
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+            let response: serde_json::Value = serde_json::from_str(&line).unwrap();
+            assert!(response["error"].is_string(), "{line}");
+
+            shutdown.store(true, Ordering::SeqCst);
+        });
+
+        assert!(
+            Daemon::bind(
+                Database::open_readonly(&dir.path().join("test.db")).unwrap(),
+                &socket,
+                SearchConfig::default()
+            )
+            .is_err_and(|e| e.to_string().contains("another daemon"))
+        );
+        drop(daemon);
+        assert!(!socket.exists());
+    }
+
+    #[test]
+    fn test_refuses_to_replace_regular_file() {
+        let dir = tempdir().unwrap();
+        let socket = dir.path().join("not-a-socket");
+        std::fs::write(&socket, "keep me").unwrap();
+
+        assert!(matches!(remove_stale_socket(&socket), Err(IndexerError::Io { .. })));
+        assert_eq!(std::fs::read_to_string(&socket).unwrap(), "keep me");
+    }
+}
@@ -1,16 +1,440 @@
 use chrono::Utc;
-use rusqlite::ToSql;
-use std::path::Path;
+use rusqlite::functions::{Context, FunctionFlags};
+use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+use rusqlite::{OptionalExtension, ToSql};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use crate::constants::APPLICATION_ID_I32;
+use crate::ROOT_LABEL_PREFIX;
+use crate::cli::SortOrder;
+use crate::constants::{APPLICATION_ID_I32, SCHEMA_VERSION};
 use crate::error::{IndexerError, Result};
+use crate::language::detect_language;
+use crate::search::sanitize_fts_query;
+
+/// Rows per chunk when rebuilding the FTS5 index.
+const FTS_REBUILD_CHUNK_SIZE: usize = 10_000;
+
+/// Schema migration steps; entry `n` upgrades version `n` to `n + 1`
+/// (see [`Database::migrate_schema`]).
+const MIGRATIONS: &[fn(&Database) -> Result<usize>] = &[Database::migrate_v0_to_v1];
+
+/// zstd level for [`PragmaConfig::compress_content`] (zstd's own default).
+pub const CONTENT_ZSTD_LEVEL: i32 = 3;
+
+/// SQL function returning the plaintext of a `files.content` value.
+///
+/// Compressed content is stored as a zstd BLOB and plaintext as TEXT, so the type
+/// alone tells them apart. The Rust getters always read `files.content` through
+/// this function; the schema only calls it once compression is enabled (see
+/// [`COMPRESSED_CONTENT_SCHEMA`]), so a plain index stays usable from any `SQLite` client.
+const CONTENT_FN: &str = "ffts_content";
+
+/// [`CONTENT_FN`]: TEXT and NULL pass through, a BLOB is zstd-decompressed.
+fn content_plaintext(ctx: &Context<'_>) -> rusqlite::Result<Value> {
+    match ctx.get_raw(0) {
+        ValueRef::Blob(compressed) => zstd::decode_all(compressed)
+            .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+            .and_then(|bytes| {
+                String::from_utf8(bytes).map_err(|e| rusqlite::Error::UserFunctionError(e.into()))
+            })
+            .map(Value::Text),
+        ValueRef::Text(text) => Ok(Value::Text(String::from_utf8_lossy(text).into_owned())),
+        ValueRef::Null => Ok(Value::Null),
+        ValueRef::Integer(n) => Ok(Value::Text(n.to_string())),
+        ValueRef::Real(x) => Ok(Value::Text(x.to_string())),
+    }
+}
+
+/// SQL function `ffts_age_factor(mtime, now)`: [`age_factor`] for the recency blend
+/// of [`SearchFilter::recency_boost`].
+const AGE_FACTOR_FN: &str = "ffts_age_factor";
+
+/// Age (in days) at which a file receives half of the full recency penalty.
+pub const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Recency penalty of a file modified at `mtime`, as of `now` (Unix seconds).
+///
+/// Decays from 0 (modified now) towards 1 (old), reaching 0.5 at
+/// [`RECENCY_HALF_LIFE_DAYS`]; future mtimes count as now.
+#[must_use]
+pub fn age_factor(mtime: i64, now: i64) -> f64 {
+    // Precision loss is irrelevant at day granularity
+    #[allow(clippy::cast_precision_loss)]
+    let age_days = now.saturating_sub(mtime).max(0) as f64 / 86_400.0;
+    1.0 - 0.5_f64.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+}
+
+/// [`AGE_FACTOR_FN`]: a file without an mtime gets no penalty.
+fn age_factor_sql(ctx: &Context<'_>) -> rusqlite::Result<f64> {
+    let mtime: Option<i64> = ctx.get(0)?;
+    let now: i64 = ctx.get(1)?;
+    Ok(mtime.map_or(0.0, |mtime| age_factor(mtime, now)))
+}
+
+/// Set of FTS5 columns a search is restricted to (bit set over filename/path/content).
+///
+/// Combine with `|`, e.g. `ColumnSet::FILENAME | ColumnSet::CONTENT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColumnSet(u8);
+
+impl ColumnSet {
+    /// The `filename` column (file name only, e.g. `CLAUDE.md`)
+    pub const FILENAME: Self = Self(0b001);
+    /// The `path` column (project-relative path)
+    pub const PATH: Self = Self(0b010);
+    /// The `content` column (file contents)
+    pub const CONTENT: Self = Self(0b100);
+    /// All indexed columns (default)
+    pub const ALL: Self = Self(0b111);
+
+    /// FTS5 column names in schema order.
+    const NAMES: [(Self, &'static str); 3] =
+        [(Self::FILENAME, "filename"), (Self::PATH, "path"), (Self::CONTENT, "content")];
+
+    /// Returns true if every column in `other` is also in `self`.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns true if `self` and `other` share at least one column.
+    #[must_use]
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns true if no column is selected.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Restrict an FTS5 expression to these columns: `{filename content} : (query)`.
+    ///
+    /// `ALL` (and the empty set) leave the expression unfiltered.
+    #[must_use]
+    pub fn filter_expression(self, query: &str) -> String {
+        if self.contains(Self::ALL) || self.is_empty() {
+            return query.to_string();
+        }
+
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(column, _)| self.contains(*column))
+            .map(|(_, name)| *name)
+            .collect();
+        format!("{{{}}} : ({query})", names.join(" "))
+    }
+}
+
+impl Default for ColumnSet {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl std::ops::BitOr for ColumnSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// BM25 column weights for ranking (higher = matches in that column count more).
+///
+/// The default (filename=100, path=50, content=1) favors file lookups; documentation
+/// trees may want more weight on content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25Weights {
+    pub filename: f64,
+    pub path: f64,
+    pub content: f64,
+}
+
+impl Default for Bm25Weights {
+    fn default() -> Self {
+        Self { filename: 100.0, path: 50.0, content: 1.0 }
+    }
+}
+
+/// Restrictions and ordering for [`Database::search_filtered`].
+///
+/// The default filters nothing and orders by rank.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SearchFilter<'a> {
+    /// Only files whose detected language is this (see [`detect_language`])
+    pub language: Option<&'a str>,
+    /// Only files modified at or after this time (Unix seconds)
+    pub modified_since: Option<i64>,
+    /// Only files modified at or before this time (Unix seconds)
+    pub modified_until: Option<i64>,
+    /// Only files of at least this many bytes
+    pub min_size: Option<i64>,
+    /// Only files of at most this many bytes
+    pub max_size: Option<i64>,
+    /// Result order (see [`Database::search_sorted`])
+    pub sort: SortOrder,
+    /// Add `boost * age_factor(mtime)` to each rank before ordering and the limit,
+    /// so recent files can outrank older, slightly better matches (see [`age_factor`])
+    pub recency_boost: Option<f64>,
+}
+
+impl SearchFilter<'_> {
+    /// Whether a file with this path, mtime and size passes every filter.
+    #[must_use]
+    pub fn accepts(&self, path: &str, mtime: i64, size: i64) -> bool {
+        self.language.is_none_or(|language| detect_language(path) == Some(language))
+            && self.modified_since.is_none_or(|since| mtime >= since)
+            && self.modified_until.is_none_or(|until| mtime <= until)
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+    }
+
+    /// Whether any row restriction is set (ordering aside).
+    #[must_use]
+    pub const fn restricts(&self) -> bool {
+        self.language.is_some()
+            || self.modified_since.is_some()
+            || self.modified_until.is_some()
+            || self.min_size.is_some()
+            || self.max_size.is_some()
+    }
+}
 
 /// Search result returned by FTS5 queries.
 #[derive(Debug, Clone)]
 pub struct SearchResult {
     pub path: String,
     pub rank: f64,
+    /// Content excerpt around the match (only populated when requested)
+    pub snippet: Option<String>,
+    /// Stored `files.filename` (only populated when requested)
+    pub filename: Option<String>,
+    /// Query-term matches per line (only populated when requested)
+    pub density: Option<MatchDensity>,
+    /// Per-column BM25 breakdown (only populated when requested)
+    pub ranking: Option<RankBreakdown>,
+    /// Content lines containing a query term (only populated when requested)
+    pub line_matches: Option<Vec<LineMatch>>,
+    /// Full content with matched tokens wrapped in markers (only populated when requested)
+    pub highlighted: Option<String>,
+}
+
+/// A content line containing at least one query term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch {
+    /// 1-based line number
+    pub line: u32,
+    /// The line itself, without its line terminator
+    pub text: String,
+}
+
+/// Per-file metadata recorded at index time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub path: String,
+    pub filename: String,
+    /// Hash of the stored content (see [`Database::content_hash`])
+    pub content_hash: Option<String>,
+    /// File modification time (Unix seconds) when indexed
+    pub mtime: i64,
+    /// File size in bytes when indexed
+    pub size: i64,
+    /// When the row was last written (Unix seconds)
+    pub indexed_at: i64,
+}
+
+/// One indexed file with its content, as written by `ffts-grep export`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub path: String,
+    pub filename: String,
+    /// Hash of `content` (see [`Database::content_hash`])
+    pub content_hash: Option<String>,
+    /// File modification time (Unix seconds) when indexed
+    pub mtime: i64,
+    /// File size in bytes when indexed
+    pub size: i64,
+    pub content: String,
+}
+
+/// Lowercase content terms of a query, for substring matching against stored text.
+///
+/// FTS5 operators and quotes are dropped; a prefix `*` is implied by substring
+/// matching, so it is stripped too.
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .filter(|term| !matches!(*term, "AND" | "OR" | "NOT" | "NEAR"))
+        .map(|term| term.trim_matches('"').trim_end_matches('*').to_lowercase())
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// BM25 score of a result with each column's weight applied in isolation.
+///
+/// FTS5 saturates term frequency across the weighted sum of all columns, so the
+/// parts add up to the combined rank exactly only when a single column matched;
+/// otherwise they show which column drives the score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankBreakdown {
+    /// Score with only the filename weight (others zero)
+    pub filename: f64,
+    /// Score with only the path weight
+    pub path: f64,
+    /// Score with only the content weight
+    pub content: f64,
+}
+
+impl RankBreakdown {
+    /// Sum of the per-column scores (lower = better, like BM25).
+    #[must_use]
+    pub fn total(&self) -> f64 {
+        self.filename + self.path + self.content
+    }
+}
+
+/// Markup and length of a [`Database::snippet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnippetStyle<'a> {
+    /// Inserted before each matched token (empty = no highlighting)
+    pub open: &'a str,
+    /// Inserted after each matched token
+    pub close: &'a str,
+    /// Marks elided text at either end
+    pub ellipsis: &'a str,
+    /// Maximum tokens in the snippet (FTS5 caps this at 64)
+    pub max_tokens: u32,
+}
+
+impl SnippetStyle<'static> {
+    /// Unmarked text, up to 16 tokens, `...` for elisions.
+    pub const PLAIN: Self = Self { open: "", close: "", ellipsis: "...", max_tokens: 16 };
+
+    /// Matches wrapped in `<b>`/`</b>`, up to 32 tokens, `…` for elisions (as in
+    /// [`Database::search_with_snippets`]).
+    pub const HIGHLIGHTED: Self =
+        Self { open: "<b>", close: "</b>", ellipsis: "…", max_tokens: 32 };
+}
+
+/// Normalize a highlighted FTS5 snippet of the content column.
+///
+/// When only the filename or path matched, `snippet()` falls back to the start of
+/// the content with nothing highlighted; that is noise, so it becomes `""`.
+fn content_snippet(snippet: String) -> String {
+    if snippet.contains("<b>") { snippet } else { String::new() }
+}
+
+/// How densely a file matches the query: term occurrences relative to line count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchDensity {
+    /// Case-insensitive occurrences of the query terms in the content
+    pub matches: usize,
+    /// Lines in the content
+    pub lines: usize,
+}
+
+impl MatchDensity {
+    /// Count occurrences of each (lowercase) term in `content`.
+    ///
+    /// Matching is substring-based and ignores stemming, so counts can differ
+    /// slightly from what FTS5 considers a match.
+    #[must_use]
+    pub fn measure(content: &str, terms: &[String]) -> Self {
+        let lower = content.to_lowercase();
+        let matches = terms.iter().map(|term| lower.matches(term.as_str()).count()).sum();
+        Self { matches, lines: content.lines().count() }
+    }
+
+    /// `matches / lines` (0 for an empty file).
+    #[must_use]
+    pub fn ratio(self) -> f64 {
+        if self.lines == 0 {
+            return 0.0;
+        }
+        // Precision loss only matters beyond 2^52 lines/matches
+        #[allow(clippy::cast_precision_loss)]
+        let ratio = self.matches as f64 / self.lines as f64;
+        ratio
+    }
+}
+
+/// FTS5 tokenizer used to build `files_fts`.
+///
+/// The choice is recorded in the `tokenize=` clause of the stored `files_fts` DDL
+/// and read back on open (see [`Database::tokenizer`]). Explicitly requesting a
+/// different one rebuilds `files_fts` (see [`Database::migrate_tokenizer`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenizerKind {
+    /// Unicode words with English stemming (`porter unicode61`)
+    #[default]
+    Porter,
+    /// Unicode words without stemming
+    Unicode61,
+    /// Overlapping 3-character sequences, so substrings inside identifiers match
+    /// (e.g. `UserBy` in `getUserById`). Larger index; terms need 3+ characters.
+    Trigram,
+}
+
+impl TokenizerKind {
+    /// Value of the FTS5 `tokenize=` option.
+    #[must_use]
+    pub const fn tokenize_clause(self) -> &'static str {
+        match self {
+            Self::Porter => "porter unicode61",
+            Self::Unicode61 => "unicode61",
+            Self::Trigram => "trigram",
+        }
+    }
+
+    /// Tokenizer whose [`tokenize_clause`](Self::tokenize_clause) is `clause`.
+    #[must_use]
+    pub fn from_tokenize_clause(clause: &str) -> Option<Self> {
+        [Self::Porter, Self::Unicode61, Self::Trigram]
+            .into_iter()
+            .find(|kind| kind.tokenize_clause() == clause)
+    }
+
+    /// `CREATE VIRTUAL TABLE` statement for `files_fts` with this tokenizer, reading
+    /// content from `files` or, for compressed content, the `files_text` view.
+    const fn fts_table_sql(self, compressed: bool) -> &'static str {
+        match (self, compressed) {
+            (Self::Porter, false) => FILES_FTS_PORTER_SQL,
+            (Self::Unicode61, false) => FILES_FTS_UNICODE61_SQL,
+            (Self::Trigram, false) => FILES_FTS_TRIGRAM_SQL,
+            (Self::Porter, true) => FILES_FTS_PORTER_COMPRESSED_SQL,
+            (Self::Unicode61, true) => FILES_FTS_UNICODE61_COMPRESSED_SQL,
+            (Self::Trigram, true) => FILES_FTS_TRIGRAM_COMPRESSED_SQL,
+        }
+    }
+}
+
+/// Mode of a WAL checkpoint (see [`Database::checkpoint`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckpointMode {
+    /// Copy as many frames as possible without waiting on readers or writers
+    #[default]
+    Passive,
+    /// Wait for writers, then copy every frame
+    Full,
+    /// Like `Full`, then truncate the WAL file to zero bytes
+    Truncate,
+}
+
+impl CheckpointMode {
+    /// Argument of `PRAGMA wal_checkpoint(...)`.
+    #[must_use]
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Passive => "PASSIVE",
+            Self::Full => "FULL",
+            Self::Truncate => "TRUNCATE",
+        }
+    }
 }
 
 /// Database configuration for PRAGMA settings.
@@ -23,6 +447,21 @@ pub struct PragmaConfig {
     pub mmap_size: i64,
     pub page_size: i64,
     pub busy_timeout_ms: i64,
+    /// Tokenizer for `files_fts` (applied by [`Database::init_schema`]).
+    ///
+    /// `None` keeps the tokenizer an existing index was built with (porter for a
+    /// new index); `Some` of a different one rebuilds the FTS index.
+    pub tokenizer: Option<TokenizerKind>,
+    /// Store file content zstd-compressed (see [`Database::upsert_file`]).
+    ///
+    /// Shrinks the database for large text corpora at the cost of a decompression
+    /// per snippet, highlight or content read. Search is unaffected: FTS5 indexes
+    /// the plaintext. [`Database::init_schema`] switches the schema to read content
+    /// through [`CONTENT_FN`], which other `SQLite` clients lack; the choice is kept
+    /// in the schema, so later handles keep compressing without this flag (see
+    /// [`Database::compresses_content`]). Rows written before it was enabled stay
+    /// plaintext until `index --reindex`.
+    pub compress_content: bool,
 }
 
 impl Default for PragmaConfig {
@@ -35,11 +474,19 @@ impl Default for PragmaConfig {
             mmap_size: Self::default_mmap_size(),
             page_size: 4096,
             busy_timeout_ms: 5000,
+            tokenizer: None,
+            compress_content: false,
         }
     }
 }
 
 impl PragmaConfig {
+    /// Whether `journal_mode` is WAL (and so needs checkpointing before a rename).
+    #[must_use]
+    pub fn uses_wal(&self) -> bool {
+        self.journal_mode.eq_ignore_ascii_case("wal")
+    }
+
     /// Platform-aware `mmap_size` default.
     ///
     /// - **macOS**: Returns 0 (mmap unreliable on HFS+/APFS with `SQLite`)
@@ -91,6 +538,10 @@ impl PragmaConfig {
 /// synchronous pooling or `deadpool-sqlite` for async pooling.
 pub struct Database {
     conn: rusqlite::Connection,
+    /// Requested by [`PragmaConfig::tokenizer`], else the one `files_fts` was built with
+    tokenizer: TokenizerKind,
+    /// Requested by [`PragmaConfig::compress_content`] or already in the schema
+    compress_content: bool,
 }
 
 impl Database {
@@ -98,6 +549,19 @@ impl Database {
         conn.pragma_update(None, name, value).map_err(|e| IndexerError::Database { source: e })
     }
 
+    /// Register [`CONTENT_FN`], which the compressed schema's triggers and view call,
+    /// and [`AGE_FACTOR_FN`] for recency-boosted searches.
+    ///
+    /// Innocuous so they may run from the schema with `trusted_schema=OFF`.
+    fn register_functions(conn: &rusqlite::Connection) -> Result<()> {
+        let flags = FunctionFlags::SQLITE_UTF8
+            | FunctionFlags::SQLITE_DETERMINISTIC
+            | FunctionFlags::SQLITE_INNOCUOUS;
+        conn.create_scalar_function(CONTENT_FN, 1, flags, content_plaintext)
+            .and_then(|()| conn.create_scalar_function(AGE_FACTOR_FN, 2, flags, age_factor_sql))
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
     /// Open database at path, creating if needed.
     ///
     /// # Errors
@@ -114,6 +578,7 @@ impl Database {
         }
 
         let conn = rusqlite::Connection::open(db_path)?;
+        Self::register_functions(&conn)?;
 
         // Apply PRAGMAs with error context
         Self::apply_pragma(&conn, "journal_mode", &config.journal_mode)?;
@@ -136,34 +601,115 @@ impl Database {
         let busy_timeout = Duration::from_millis(config.busy_timeout_ms as u64);
         conn.busy_timeout(busy_timeout).map_err(|e| IndexerError::Database { source: e })?;
 
-        Ok(Self { conn })
+        let stored_fts = stored_fts_sql(&conn)?;
+        let tokenizer = config
+            .tokenizer
+            .or_else(|| stored_fts.as_deref().and_then(stored_tokenizer))
+            .unwrap_or_default();
+        let compress_content =
+            config.compress_content || stored_fts.as_deref().is_some_and(reads_content_view);
+        Ok(Self { conn, tokenizer, compress_content })
     }
 
-    /// Migrate legacy database schema (2-column FTS5) to current schema (3-column FTS5 with filename).
+    /// Switch this handle to the database at `db_path` (e.g. after
+    /// [`atomic_reindex`](crate::indexer::atomic_reindex) swapped in a new index).
     ///
-    /// This migration:
-    /// 1. Adds `filename` column to `files` table if missing
-    /// 2. Populates filename from existing paths using Rust (`SQLite` lacks string functions)
-    /// 3. Drops old FTS5 table and triggers (will be recreated by `init_schema`)
+    /// A long-running server keeps searching the old index while the reindex builds
+    /// the replacement, then calls this to pick it up. Until then, results reflect
+    /// the old index.
     ///
-    /// Safe to call multiple times - only runs if migration is needed.
+    /// The new database is opened once to validate it, so a bad path leaves this
+    /// handle on the old index. The old connection is then closed *before* the new
+    /// one opens: closing a WAL connection removes `-wal`/`-shm` by name, which must
+    /// not hit files the new connection already uses.
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if migration DDL fails.
-    pub fn migrate_schema(&self) -> Result<()> {
-        // Check if filename column already exists
-        let has_filename: bool = self
-            .conn
-            .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('files') WHERE name = 'filename'",
-                [],
-                |row| row.get::<_, i64>(0).map(|n| n > 0),
-            )
-            .unwrap_or(false);
+    /// Returns `IndexerError::Database` if the new database cannot be opened. If that
+    /// happens after validation, the handle is left on an empty in-memory database.
+    pub fn reopen(&mut self, db_path: &Path, config: &PragmaConfig) -> Result<()> {
+        drop(Self::open(db_path, config)?);
+
+        let placeholder = rusqlite::Connection::open_in_memory()
+            .map_err(|e| IndexerError::Database { source: e })?;
+        drop(std::mem::replace(&mut self.conn, placeholder));
+
+        *self = Self::open(db_path, config)?;
+        Ok(())
+    }
+
+    /// Schema version stored in `PRAGMA user_version` (0 = never stamped).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the PRAGMA cannot be read.
+    pub fn current_schema_version(&self) -> Result<i32> {
+        self.conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| IndexerError::Database { source: e })
+    }
 
-        if has_filename {
+    /// Record `version` in `PRAGMA user_version`.
+    fn set_schema_version(&self, version: i32) -> Result<()> {
+        Self::apply_pragma(&self.conn, "user_version", version)
+    }
+
+    /// Column names of the `files` table (empty if it does not exist).
+    fn files_columns(&self) -> Vec<String> {
+        self.conn
+            .prepare("SELECT name FROM pragma_table_info('files')")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .unwrap_or_default()
+    }
+
+    /// True if a `files` table exists with a schema version older than
+    /// [`SCHEMA_VERSION`], i.e. [`Self::migrate_schema`] has work to do.
+    #[must_use]
+    pub fn needs_migration(&self) -> bool {
+        !self.files_columns().is_empty()
+            && self.current_schema_version().is_ok_and(|version| version < SCHEMA_VERSION)
+    }
+
+    /// Bring an existing schema up to [`SCHEMA_VERSION`].
+    ///
+    /// Runs every step in `MIGRATIONS` from the stored version onward, stamping
+    /// the version after each, so an interrupted migration resumes where it stopped.
+    /// Follow with [`Self::init_schema`] and [`Self::rebuild_fts_index`], since steps
+    /// may drop the FTS5 table and triggers.
+    ///
+    /// Safe to call multiple times - only runs if migration is needed. Returns the
+    /// number of rows migrated (0 when the schema is already current).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if migration DDL fails.
+    pub fn migrate_schema(&self) -> Result<usize> {
+        if !self.needs_migration() {
             // Already migrated, nothing to do
-            return Ok(());
+            return Ok(0);
+        }
+
+        let mut version = self.current_schema_version()?;
+        let mut migrated = 0;
+        while let Some(step) = usize::try_from(version).ok().and_then(|v| MIGRATIONS.get(v)) {
+            tracing::info!(from = version, to = version + 1, "Migrating database schema");
+            migrated += step(self)?;
+            version += 1;
+            self.set_schema_version(version)?;
+        }
+        Ok(migrated)
+    }
+
+    /// Schema v0 -> v1: legacy (2-column FTS5) to 3-column FTS5 with filename.
+    ///
+    /// 1. Adds `filename` column to `files` table if missing
+    /// 2. Populates filename from existing paths using Rust (`SQLite` lacks string functions)
+    /// 3. Drops old FTS5 table and triggers (will be recreated by `init_schema`)
+    ///
+    /// A v0 database that already has the column (created before versions were
+    /// stamped) only gets the stamp.
+    fn migrate_v0_to_v1(&self) -> Result<usize> {
+        if self.files_columns().iter().any(|name| name == "filename") {
+            return Ok(0);
         }
 
         tracing::info!("Migrating database schema (adding filename column for FTS5 ranking)");
@@ -187,6 +733,7 @@ impl Database {
         };
 
         // Update each row with extracted filename
+        let migrated = paths.len();
         for (id, path) in paths {
             let filename = Path::new(&path).file_name().and_then(|n| n.to_str()).unwrap_or(&path);
             self.conn
@@ -215,30 +762,104 @@ impl Database {
 
         tracing::info!("Schema migration complete - call init_schema() then rebuild_fts_index()");
 
-        Ok(())
+        Ok(migrated)
+    }
+
+    /// Tokenizer of `files_fts`: the one requested by [`PragmaConfig::tokenizer`],
+    /// else the one recorded in the existing index, else porter.
+    #[must_use]
+    pub const fn tokenizer(&self) -> TokenizerKind {
+        self.tokenizer
+    }
+
+    /// Whether file content is stored zstd-compressed.
+    ///
+    /// True once the schema reads content through the `files_text` view (set up by
+    /// [`Self::init_schema`] with [`PragmaConfig::compress_content`]), or if this
+    /// handle was opened with that option.
+    #[must_use]
+    pub const fn compresses_content(&self) -> bool {
+        self.compress_content
     }
 
     /// Rebuild FTS5 index from existing files table data.
     ///
     /// Call this after `migrate_schema()` and `init_schema()` to repopulate the
     /// FTS5 index with existing data. This is necessary because the migration
-    /// drops and recreates the FTS5 table.
+    /// drops and recreates the FTS5 table. Returns the number of rows indexed.
     ///
     /// # Errors
     /// Returns `IndexerError::Database` if the INSERT query fails.
-    pub fn rebuild_fts_index(&self) -> Result<()> {
-        // Manually populate FTS5 from existing files table
-        // This bypasses triggers to do a bulk rebuild
+    pub fn rebuild_fts_index(&self) -> Result<usize> {
+        self.rebuild_fts_index_chunked(FTS_REBUILD_CHUNK_SIZE, |done, total| {
+            tracing::debug!(done, total, "FTS5 rebuild progress");
+        })
+    }
+
+    /// Rebuild the FTS5 index in rowid-ordered chunks, reporting progress.
+    ///
+    /// Each chunk is a single `INSERT ... SELECT` over the next `chunk_size` ids, run
+    /// in autocommit mode so it commits on its own. This bounds transaction length
+    /// and memory on large tables. Keyset pagination on `id` visits every row exactly
+    /// once, so no FTS rows are duplicated or missed.
+    ///
+    /// Existing index entries are cleared first (FTS5 `delete-all`), so rebuilding an
+    /// index that is already populated does not duplicate rows.
+    ///
+    /// `progress(done, total)` is called after every chunk. Returns the number of rows
+    /// inserted into the FTS index.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if counting or inserting fails; chunks
+    /// committed before the failure remain in place.
+    pub fn rebuild_fts_index_chunked(
+        &self,
+        chunk_size: usize,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<usize> {
         self.conn
-            .execute(
-                "INSERT INTO files_fts(rowid, filename, path, content)
-                 SELECT id, filename, path, content FROM files",
-                [],
-            )
+            .execute("INSERT INTO files_fts(files_fts) VALUES('delete-all')", [])
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        tracing::info!("FTS5 index rebuilt from existing data");
-        Ok(())
+        let chunk_size = i64::try_from(chunk_size.max(1)).unwrap_or(i64::MAX);
+        let total: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+            .map_err(|e| IndexerError::Database { source: e })?;
+        let total = usize::try_from(total).unwrap_or(0);
+
+        let mut done = 0usize;
+        let mut last_id = i64::MIN;
+        loop {
+            // Upper id bound of the next chunk (NULL once all rows are processed)
+            let upper: Option<i64> = self
+                .conn
+                .query_row(
+                    "SELECT MAX(id) FROM (SELECT id FROM files WHERE id > ?1 ORDER BY id LIMIT ?2)",
+                    rusqlite::params![last_id, chunk_size],
+                    |row| row.get(0),
+                )
+                .map_err(|e| IndexerError::Database { source: e })?;
+            let Some(upper) = upper else { break };
+
+            // Bypasses triggers to do a bulk rebuild
+            let inserted = self
+                .conn
+                .execute(
+                    "INSERT INTO files_fts(rowid, filename, path, content)
+                     SELECT id, filename, path, ffts_content(content)
+                     FROM files WHERE id > ?1 AND id <= ?2",
+                    rusqlite::params![last_id, upper],
+                )
+                .map_err(|e| IndexerError::Database { source: e })?;
+
+            done += inserted;
+            last_id = upper;
+            progress(done, total);
+        }
+
+        tracing::info!(rows = done, "FTS5 index rebuilt from existing data");
+        Ok(done)
     }
 
     /// Initialize schema (idempotent - safe to call multiple times).
@@ -246,91 +867,303 @@ impl Database {
     /// # Errors
     /// Returns `IndexerError::Database` if any CREATE TABLE, CREATE TRIGGER, or CREATE INDEX statement fails.
     pub fn init_schema(&self) -> Result<()> {
-        // Main files table
-        // The `filename` column stores just the file name (e.g., "CLAUDE.md" from "docs/CLAUDE.md")
-        // This enables precise BM25 weighting where filename matches rank higher than path matches
-        self.conn
-            .execute(
-                "CREATE TABLE IF NOT EXISTS files (
-                id INTEGER PRIMARY KEY,
-                path TEXT UNIQUE NOT NULL,
-                filename TEXT,
-                content_hash TEXT,
-                mtime INTEGER,
-                size INTEGER,
-                indexed_at INTEGER,
-                content TEXT
-            )",
-                [],
-            )
-            .map_err(|e| IndexerError::Database { source: e })?;
+        for object in self.schema_objects() {
+            self.conn.execute(object.sql, []).map_err(|e| IndexerError::Database { source: e })?;
+        }
 
-        // FTS5 virtual table with external content
-        // Column order: filename, path, content (for BM25 weight arguments)
-        // BM25 weights: filename=100, path=50, content=1
-        // This ensures "CLAUDE.md" ranks higher than "docs/MASTRA-VS-CLAUDE-SDK.md" for query "claude"
-        // columnsize=0: saves 10-15% storage (BM25 ranking still works)
-        self.conn
-            .execute(
-                "CREATE VIRTUAL TABLE IF NOT EXISTS files_fts USING fts5(
-                filename, path, content,
-                content='files',
-                content_rowid='id',
-                tokenize='porter unicode61',
-                columnsize=0
-            )",
-                [],
-            )
-            .map_err(|e| IndexerError::Database { source: e })?;
+        self.migrate_path_lower()?;
+        self.migrate_language()?;
+        self.migrate_content_compression()?;
+        self.migrate_tokenizer()?;
 
-        // Auto-sync triggers (include filename for FTS5 indexing)
-        self.conn
-            .execute(
-                "CREATE TRIGGER IF NOT EXISTS files_ai AFTER INSERT ON files BEGIN
-                INSERT INTO files_fts(rowid, filename, path, content)
-                VALUES (new.id, new.filename, new.path, new.content);
-            END",
-                [],
-            )
-            .map_err(|e| IndexerError::Database { source: e })?;
+        // Only a schema that really is current gets the stamp: a v0.9 file still
+        // needs `migrate_schema`, and a newer version is never downgraded
+        if self.current_schema_version()? < SCHEMA_VERSION
+            && self.files_columns().iter().any(|name| name == "filename")
+        {
+            self.set_schema_version(SCHEMA_VERSION)?;
+        }
 
-        self.conn
-            .execute(
-                "CREATE TRIGGER IF NOT EXISTS files_au AFTER UPDATE ON files BEGIN
-                INSERT INTO files_fts(files_fts, rowid, filename, path, content)
-                VALUES('delete', old.id, old.filename, old.path, old.content);
-                INSERT INTO files_fts(rowid, filename, path, content)
-                VALUES (new.id, new.filename, new.path, new.content);
-            END",
-                [],
-            )
-            .map_err(|e| IndexerError::Database { source: e })?;
+        Ok(())
+    }
 
-        self.conn
-            .execute(
-                "CREATE TRIGGER IF NOT EXISTS files_ad AFTER DELETE ON files BEGIN
-                INSERT INTO files_fts(files_fts, rowid, filename, path, content)
-                VALUES('delete', old.id, old.filename, old.path, old.content);
-            END",
-                [],
+    /// Schema objects this handle creates, in creation order.
+    ///
+    /// [`EXPECTED_SCHEMA`] with `files_fts` for this handle's tokenizer and, when
+    /// content is compressed, the view and triggers of [`COMPRESSED_CONTENT_SCHEMA`].
+    fn schema_objects(&self) -> Vec<SchemaObject> {
+        let mut objects = Vec::with_capacity(EXPECTED_SCHEMA.len() + 1);
+        for object in EXPECTED_SCHEMA {
+            if object.name == "files_fts" {
+                if self.compress_content {
+                    objects.push(COMPRESSED_CONTENT_SCHEMA[0]);
+                }
+                objects.push(SchemaObject { sql: self.fts_table_sql(), ..object });
+            } else {
+                objects.push(self.compressed_variant(object).unwrap_or(object));
+            }
+        }
+        objects
+    }
+
+    /// [`COMPRESSED_CONTENT_SCHEMA`] replacement for `object`, if content is compressed.
+    fn compressed_variant(&self, object: SchemaObject) -> Option<SchemaObject> {
+        if !self.compress_content {
+            return None;
+        }
+        COMPRESSED_CONTENT_SCHEMA.iter().copied().find(|compressed| compressed.name == object.name)
+    }
+
+    /// `files_fts` DDL for this handle's tokenizer and content storage.
+    const fn fts_table_sql(&self) -> &'static str {
+        self.tokenizer.fts_table_sql(self.compress_content)
+    }
+
+    /// DDL of the `files_au` trigger for this handle's content storage.
+    const fn update_trigger_sql(&self) -> &'static str {
+        if self.compress_content { FILES_AU_COMPRESSED_TRIGGER_SQL } else { FILES_AU_TRIGGER_SQL }
+    }
+
+    /// Switch a plain index to compressed content storage if this handle compresses.
+    ///
+    /// `files_fts` and its triggers are recreated to read content through the
+    /// `files_text` view (so compressed rows are indexed as plaintext) and the index
+    /// is repopulated. Existing rows are not recompressed. Gated on the stored DDL,
+    /// so it is a single cheap check when nothing changed.
+    ///
+    /// Returns `true` if the schema was switched.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if reading the schema, the DDL, or the
+    /// rebuild fails.
+    pub fn migrate_content_compression(&self) -> Result<bool> {
+        if !self.compress_content
+            || stored_fts_sql(&self.conn)?.is_none_or(|sql| reads_content_view(&sql))
+            || self.files_columns().is_empty()
+        {
+            return Ok(false);
+        }
+
+        tracing::info!("Switching FTS5 index to compressed content storage");
+        for statement in [
+            "DROP TRIGGER IF EXISTS files_ai",
+            "DROP TRIGGER IF EXISTS files_au",
+            "DROP TRIGGER IF EXISTS files_ad",
+            "DROP TABLE IF EXISTS files_fts",
+        ] {
+            self.conn.execute(statement, []).map_err(|e| IndexerError::Database { source: e })?;
+        }
+        for object in self.schema_objects() {
+            self.conn.execute(object.sql, []).map_err(|e| IndexerError::Database { source: e })?;
+        }
+        self.rebuild_fts_index()?;
+
+        Ok(true)
+    }
+
+    /// Rebuild `files_fts` if it was built with a different tokenizer than this handle's.
+    ///
+    /// Only happens when [`PragmaConfig::tokenizer`] explicitly asks for another
+    /// tokenizer; otherwise the handle adopts the stored one on open. The tokenizer is
+    /// fixed when an FTS5 table is created, so the table is dropped, recreated with the
+    /// requested `tokenize=` clause and repopulated from `files`. Gated on the stored
+    /// DDL, so it is a single cheap check when nothing changed.
+    ///
+    /// Returns `true` if the index was rebuilt.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if reading the schema, the DDL, or the
+    /// rebuild fails.
+    pub fn migrate_tokenizer(&self) -> Result<bool> {
+        let Some(stored) = stored_fts_sql(&self.conn)? else {
+            return Ok(false);
+        };
+
+        let wanted = self.tokenizer.tokenize_clause();
+        match stored_tokenize_clause(&stored) {
+            Some(current) if current != wanted => {
+                tracing::info!(from = %current, to = wanted, "Rebuilding FTS5 index for new tokenizer");
+            }
+            _ => return Ok(false),
+        }
+
+        self.conn
+            .execute("DROP TABLE files_fts", [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+        self.conn
+            .execute(self.fts_table_sql(), [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+        self.rebuild_fts_index()?;
+
+        Ok(true)
+    }
+
+    /// Add and backfill the `path_lower` column on databases created before it existed.
+    ///
+    /// Gated on `pragma_table_info`, so it is a single cheap check once migrated.
+    /// The backfill lowercases in Rust because `SQLite`'s `lower()` is ASCII-only, and
+    /// runs with the update trigger dropped: `path_lower` is not indexed by FTS5, and
+    /// re-indexing every row would fail if FTS5 is out of sync (e.g. mid-migration).
+    ///
+    /// Returns `true` if the column was added.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the ALTER or backfill fails.
+    pub fn migrate_path_lower(&self) -> Result<bool> {
+        let has_column: bool = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('files') WHERE name = 'path_lower'",
+                [],
+                |row| row.get::<_, i64>(0).map(|n| n > 0),
             )
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        // Indexes for efficient queries
-        self.conn
-            .execute("CREATE INDEX IF NOT EXISTS idx_files_mtime ON files(mtime)", [])
+        if has_column {
+            return Ok(false);
+        }
+
+        tracing::info!("Migrating database schema (adding path_lower column)");
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("ALTER TABLE files ADD COLUMN path_lower TEXT", [])
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        self.conn
-            .execute("CREATE INDEX IF NOT EXISTS idx_files_path ON files(path)", [])
+        let paths: Vec<(i64, String)> = {
+            let mut stmt = tx
+                .prepare("SELECT id, path FROM files")
+                .map_err(|e| IndexerError::Database { source: e })?;
+            stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| IndexerError::Database { source: e })?
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|e| IndexerError::Database { source: e })?
+        };
+
+        tx.execute("DROP TRIGGER IF EXISTS files_au", [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+        {
+            let mut update = tx
+                .prepare("UPDATE files SET path_lower = ?1 WHERE id = ?2")
+                .map_err(|e| IndexerError::Database { source: e })?;
+            for (id, path) in paths {
+                update
+                    .execute(rusqlite::params![path.to_lowercase(), id])
+                    .map_err(|e| IndexerError::Database { source: e })?;
+            }
+        }
+        tx.execute(self.update_trigger_sql(), [])
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        // Index on content_hash for faster lazy invalidation subquery
-        self.conn
-            .execute("CREATE INDEX IF NOT EXISTS idx_files_hash ON files(content_hash)", [])
+        tx.commit().map_err(|e| IndexerError::Database { source: e })?;
+        Ok(true)
+    }
+
+    /// Add and backfill the `language` column on databases created before it existed.
+    ///
+    /// Same approach as [`migrate_path_lower`](Self::migrate_path_lower): the language
+    /// is detected in Rust from each stored path, with the update trigger dropped
+    /// because `language` is not indexed by FTS5.
+    ///
+    /// Returns `true` if the column was added.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the ALTER or backfill fails.
+    pub fn migrate_language(&self) -> Result<bool> {
+        let has_column: bool = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('files') WHERE name = 'language'",
+                [],
+                |row| row.get::<_, i64>(0).map(|n| n > 0),
+            )
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        Ok(())
+        if has_column {
+            return Ok(false);
+        }
+
+        tracing::info!("Migrating database schema (adding language column)");
+
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("ALTER TABLE files ADD COLUMN language TEXT", [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let paths: Vec<(i64, String)> = {
+            let mut stmt = tx
+                .prepare("SELECT id, path FROM files")
+                .map_err(|e| IndexerError::Database { source: e })?;
+            stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| IndexerError::Database { source: e })?
+                .collect::<std::result::Result<_, _>>()
+                .map_err(|e| IndexerError::Database { source: e })?
+        };
+
+        tx.execute("DROP TRIGGER IF EXISTS files_au", [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+        {
+            let mut update = tx
+                .prepare("UPDATE files SET language = ?1 WHERE id = ?2")
+                .map_err(|e| IndexerError::Database { source: e })?;
+            for (id, path) in paths {
+                let Some(language) = detect_language(&path) else { continue };
+                update
+                    .execute(rusqlite::params![language, id])
+                    .map_err(|e| IndexerError::Database { source: e })?;
+            }
+        }
+        tx.execute(self.update_trigger_sql(), [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        tx.commit().map_err(|e| IndexerError::Database { source: e })?;
+        Ok(true)
+    }
+
+    /// Compare the stored DDL of each schema object against [`init_schema`](Self::init_schema).
+    ///
+    /// [`check_schema`](Self::check_schema) only checks that objects exist; this also
+    /// catches objects whose definition changed (e.g. an FTS table from an older
+    /// release with fewer columns). Missing objects are not reported here.
+    ///
+    /// SQL is compared after normalizing case, whitespace, and `IF NOT EXISTS`.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if `sqlite_master` cannot be read.
+    pub fn schema_matches_expected(&self) -> Result<SchemaDriftReport> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT sql FROM sqlite_master WHERE type = ?1 AND name = ?2")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let mut report = SchemaDriftReport::default();
+        for object in self.schema_objects() {
+            let expected = object.sql;
+            let actual: Option<Option<String>> = stmt
+                .query_row([object.kind, object.name], |row| row.get(0))
+                .optional()
+                .map_err(|e| IndexerError::Database { source: e })?;
+            let Some(actual) = actual.flatten() else {
+                continue;
+            };
+
+            if normalize_ddl(&actual) != normalize_ddl(expected) {
+                report.mismatched.push(SchemaDrift {
+                    object: format!("{}: {}", object.kind, object.name),
+                    expected: expected.to_string(),
+                    actual,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Hash of file content exactly as stored in `files.content_hash`.
+    ///
+    /// Delegates to [`wyhash`], so the value matches the Zig implementation and can be
+    /// used to debug lazy-invalidation mismatches across the Rust/Zig boundary.
+    #[must_use]
+    pub fn content_hash(content: &str) -> String {
+        wyhash(content.as_bytes())
     }
 
     /// Insert or update a file (lazy invalidation via `content_hash`).
@@ -340,11 +1173,56 @@ impl Database {
     ///
     /// The `filename` is automatically extracted from `path` for FTS5 ranking.
     /// This enables BM25 to weight filename matches higher than path matches.
+    /// The `language` is detected from the extension (see [`detect_language`]).
+    ///
+    /// With [`PragmaConfig::compress_content`], `content` is stored as a zstd BLOB;
+    /// the hash and the FTS5 index are always computed from the plaintext.
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if the INSERT or UPDATE query fails.
+    /// Returns `IndexerError::Database` if the INSERT or UPDATE query fails, or
+    /// `IndexerError::Io` if compression fails.
     pub fn upsert_file(&self, path: &str, content: &str, mtime: i64, size: i64) -> Result<()> {
-        let hash = wyhash(content.as_bytes());
+        let hash = Self::content_hash(content);
+        let stored = if self.compress_content {
+            ToSqlOutput::Owned(Value::Blob(zstd::encode_all(
+                content.as_bytes(),
+                CONTENT_ZSTD_LEVEL,
+            )?))
+        } else {
+            ToSqlOutput::Borrowed(ValueRef::Text(content.as_bytes()))
+        };
+        self.upsert_row(path, &hash, stored, mtime, size)
+    }
+
+    /// Insert or update a file without its content (metadata-only indexing).
+    ///
+    /// Stores an empty `content`, so only `filename` and `path` are searchable.
+    /// `content_hash` is still the hash of the real content, which keeps lazy
+    /// invalidation and [`Self::get_content_hashes`] comparisons working; as a
+    /// consequence, an unchanged file keeps whatever content it was stored with
+    /// until a full reindex.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the INSERT or UPDATE query fails.
+    pub fn upsert_file_metadata(
+        &self,
+        path: &str,
+        content_hash: &str,
+        mtime: i64,
+        size: i64,
+    ) -> Result<()> {
+        self.upsert_row(path, content_hash, ToSqlOutput::Borrowed(ValueRef::Text(b"")), mtime, size)
+    }
+
+    /// Shared upsert of [`Self::upsert_file`] and [`Self::upsert_file_metadata`].
+    fn upsert_row(
+        &self,
+        path: &str,
+        hash: &str,
+        stored: ToSqlOutput<'_>,
+        mtime: i64,
+        size: i64,
+    ) -> Result<()> {
         let now = Utc::now().timestamp();
 
         // Extract filename from path for FTS5 ranking boost
@@ -354,17 +1232,29 @@ impl Database {
         // Lazy invalidation: only update if content changed
         // The ON CONFLICT handles the case where path exists
         self.conn.execute(
-            "INSERT INTO files (path, filename, content_hash, mtime, size, indexed_at, content)
-             VALUES (?, ?, ?, ?, ?, ?, ?)
+            "INSERT INTO files (path, filename, content_hash, mtime, size, indexed_at, content, path_lower, language)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
              ON CONFLICT(path) DO UPDATE SET
                  filename = excluded.filename,
+                 path_lower = excluded.path_lower,
+                 language = excluded.language,
                  content_hash = excluded.content_hash,
                  mtime = excluded.mtime,
                  size = excluded.size,
                  indexed_at = excluded.indexed_at,
                  content = excluded.content
              WHERE excluded.content_hash != (SELECT content_hash FROM files WHERE path = excluded.path)",
-            rusqlite::params![path, filename, hash, mtime, size, now, content],
+            rusqlite::params![
+                path,
+                filename,
+                hash,
+                mtime,
+                size,
+                now,
+                stored,
+                path.to_lowercase(),
+                detect_language(path)
+            ],
         )
         .map_err(|e| IndexerError::Database { source: e })?;
 
@@ -391,333 +1281,1510 @@ impl Database {
     ///
     /// This ensures "CLAUDE.md" ranks higher than "docs/MASTRA-VS-CLAUDE-SDK.md" for query "claude".
     ///
+    /// `query` is plain user input: every term is quoted by
+    /// [`sanitize_fts_query`](crate::search::sanitize_fts_query), so hyphens, dots and
+    /// parentheses never cause FTS5 syntax errors. Use [`search_raw`](Self::search_raw)
+    /// for FTS5 operators.
+    ///
     /// # Errors
-    /// Returns `IndexerError::Database` if:
-    /// - The FTS5 MATCH query fails (e.g., invalid FTS5 syntax)
-    /// - Query preparation or execution fails
+    /// Returns `IndexerError::Database` if query preparation or execution fails.
     pub fn search(&self, query: &str, paths_only: bool, limit: u32) -> Result<Vec<SearchResult>> {
-        // Handle empty queries gracefully
-        if query.trim().is_empty() {
-            return Ok(vec![]);
-        }
-
-        // BM25 weights: filename=100, path=50, content=1
-        // Column order in FTS5: filename, path, content
-        let sql = if paths_only {
-            "SELECT path, bm25(files_fts, 100.0, 50.0, 1.0) AS rank FROM files_fts
-             WHERE path MATCH ?1 ORDER BY rank LIMIT ?2"
-        } else {
-            "SELECT path, bm25(files_fts, 100.0, 50.0, 1.0) AS rank FROM files_fts
-             WHERE files_fts MATCH ?1 ORDER BY rank LIMIT ?2"
-        };
-
-        let mut stmt =
-            self.conn.prepare_cached(sql).map_err(|e| IndexerError::Database { source: e })?;
-
-        // Pre-allocate Vec to avoid reallocation in hot path
-        let mut results = Vec::with_capacity(limit as usize);
-        let rows = stmt
-            .query_map(rusqlite::params![query, limit], |row| {
-                Ok(SearchResult { path: row.get::<_, String>(0)?, rank: row.get::<_, f64>(1)? })
-            })
-            .map_err(|e| IndexerError::Database { source: e })?;
+        self.search_paged(query, paths_only, limit, 0)
+    }
 
-        for row in rows {
-            results.push(row.map_err(|e| IndexerError::Database { source: e })?);
-        }
+    /// One page of [`search`](Self::search) results: skip `offset`, return up to `limit`.
+    ///
+    /// Ties in rank are broken by row id, so the order is total and consecutive
+    /// pages neither overlap nor skip results (as long as the index is unchanged).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if query preparation or execution fails.
+    pub fn search_paged(
+        &self,
+        query: &str,
+        paths_only: bool,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_raw(&sanitize_fts_query(query), paths_only, limit, offset)
+    }
 
-        Ok(results)
+    /// Like [`search_paged`](Self::search_paged), but `query` is passed to FTS5
+    /// `MATCH` unchanged, so operators (`OR`, `NOT`, `NEAR`, `"phrases"`, `prefix*`) work.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails (e.g., invalid
+    /// FTS5 syntax).
+    pub fn search_raw(
+        &self,
+        query: &str,
+        paths_only: bool,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.run_search(
+            Self::SEARCH_SQL,
+            query,
+            Self::paths_only_columns(paths_only),
+            Bm25Weights::default(),
+            limit,
+            offset,
+        )
     }
 
-    /// Search for files where filename CONTAINS the query substring (case-insensitive).
+    /// Total number of files [`search`](Self::search) would match, ignoring `limit`.
     ///
-    /// This bypasses FTS5 token matching to enable substring searches.
-    /// FTS5 only matches whole tokens, so "intro" doesn't match "introduction".
-    /// SQL LIKE '%intro%' finds "01-introduction.md" correctly.
+    /// The query is sanitized the same way as `search`, and `paths_only` targets the
+    /// same columns. Only `COUNT(*)` is computed; no rows or ranks are fetched.
     ///
-    /// Results are ordered by:
-    /// 1. Exact filename match (highest priority)
-    /// 2. Filename starts with query (prefix match)
-    /// 3. Filename length (shorter = more relevant)
+    /// # Errors
+    /// Returns `IndexerError::Database` if the count query fails.
+    pub fn search_count(&self, query: &str, paths_only: bool) -> Result<usize> {
+        self.count_columns(&sanitize_fts_query(query), Self::paths_only_columns(paths_only))
+    }
+
+    /// Number of files the FTS5 MATCH expression `query` matches in `columns`.
     ///
-    /// # Arguments
-    /// * `query` - Search term (wildcards stripped if present)
-    /// * `limit` - Maximum results to return
+    /// `query` is used verbatim, so a caller that built the expression for
+    /// [`search_columns`](Self::search_columns) counts exactly what it searches.
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if the query fails.
-    pub fn search_filename_contains(&self, query: &str, limit: u32) -> Result<Vec<String>> {
-        let query = query.trim();
-        if query.is_empty() {
-            return Ok(vec![]);
+    /// Returns `IndexerError::Database` if the count query fails.
+    pub fn count_columns(&self, query: &str, columns: ColumnSet) -> Result<usize> {
+        if query.trim().is_empty() {
+            return Ok(0);
         }
 
-        // Strip wildcard if present (from auto-prefix like "01-" → "01*")
-        let search_term = query.trim_end_matches('*');
-        if search_term.is_empty() {
-            return Ok(vec![]);
-        }
+        let match_expr = columns.filter_expression(query);
+        let count: i64 = self
+            .conn
+            .prepare_cached("SELECT COUNT(*) FROM files_fts WHERE files_fts MATCH ?1")
+            .and_then(|mut stmt| stmt.query_row([match_expr], |row| row.get(0)))
+            .map_err(|e| IndexerError::Database { source: e })?;
 
-        let like_term = escape_like_pattern(search_term);
+        // COUNT(*) is never negative
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        Ok(count as usize)
+    }
 
-        // CONTAINS match with intelligent ordering:
-        // - CASE 0: exact filename match
-        // - CASE 1: filename starts with query (prefix)
-        // - CASE 2: filename contains query anywhere
-        // Secondary sort by filename length (shorter = more specific match)
-        let sql = "SELECT path FROM files
-                   WHERE filename LIKE '%' || ?1 || '%' ESCAPE '\\' COLLATE NOCASE
-                   ORDER BY
-                       CASE WHEN filename = ?2 COLLATE NOCASE THEN 0
-                            WHEN filename LIKE ?1 || '%' ESCAPE '\\' COLLATE NOCASE THEN 1
-                            ELSE 2 END,
-                       length(filename)
-                   LIMIT ?3";
+    /// FTS5 search restricted to a subset of columns.
+    ///
+    /// The query is wrapped in an FTS5 column filter (`{path content} : (query)`), so
+    /// any combination of filename/path/content can be searched with one statement.
+    /// Ranking is the same weighted BM25 as [`search`](Self::search).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_columns(
+        &self,
+        query: &str,
+        columns: ColumnSet,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_columns_weighted(query, columns, Bm25Weights::default(), limit)
+    }
 
-        let mut stmt =
-            self.conn.prepare_cached(sql).map_err(|e| IndexerError::Database { source: e })?;
+    /// Like [`search_columns`](Self::search_columns), ranked with custom BM25 weights.
+    ///
+    /// Weights are bound as statement parameters, never formatted into the SQL.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_columns_weighted(
+        &self,
+        query: &str,
+        columns: ColumnSet,
+        weights: Bm25Weights,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.run_search(Self::SEARCH_SQL, query, columns, weights, limit, 0)
+    }
 
-        let paths: Vec<String> = stmt
-            .query_map(rusqlite::params![like_term, search_term, limit], |row| {
-                row.get::<_, String>(0)
-            })
-            .map_err(|e| IndexerError::Database { source: e })?
-            .filter_map(std::result::Result::ok)
-            .collect();
+    /// Like [`search_columns_weighted`](Self::search_columns_weighted), but also returns
+    /// the stored `filename` of each result (joins `files`, so slightly slower).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_columns_with_filename(
+        &self,
+        query: &str,
+        columns: ColumnSet,
+        weights: Bm25Weights,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.run_search(Self::SEARCH_WITH_FILENAME_SQL, query, columns, weights, limit, 0)
+    }
 
-        Ok(paths)
+    /// Like [`search_columns`](Self::search_columns), but each result carries a
+    /// highlighted content snippet (see [`SnippetStyle::HIGHLIGHTED`]).
+    ///
+    /// Results that matched only the filename or path get an empty snippet.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_with_snippets(
+        &self,
+        query: &str,
+        columns: ColumnSet,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.run_search(
+            Self::SEARCH_WITH_SNIPPETS_SQL,
+            query,
+            columns,
+            Bm25Weights::default(),
+            limit,
+            0,
+        )
     }
 
-    /// Get all indexed file paths.
+    /// Like [`search`](Self::search), but each result carries its whole content with
+    /// every matched token wrapped in `open`/`close` (FTS5 `highlight()` on the
+    /// content column), e.g. `fn [main]()` for `main` with `[`/`]`.
+    ///
+    /// Results that matched only the filename or path carry their content unmarked.
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if the SELECT query fails.
-    #[must_use = "returns file paths that should be used"]
-    pub fn get_all_files(&self, limit: u32) -> Result<Vec<String>> {
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_with_highlight(
+        &self,
+        query: &str,
+        limit: u32,
+        open: &str,
+        close: &str,
+    ) -> Result<Vec<SearchResult>> {
+        let query = sanitize_fts_query(query);
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let weights = Bm25Weights::default();
         let mut stmt = self
             .conn
-            .prepare("SELECT path FROM files LIMIT ?")
+            .prepare_cached(
+                "SELECT path, bm25(files_fts, ?3, ?4, ?5) AS rank,
+                 highlight(files_fts, 2, ?6, ?7)
+                 FROM files_fts WHERE files_fts MATCH ?1 ORDER BY rank, rowid LIMIT ?2",
+            )
             .map_err(|e| IndexerError::Database { source: e })?;
-
-        let paths: Vec<String> = stmt
-            .query_map([limit], |row| row.get::<_, String>(0))
-            .map_err(|e| IndexerError::Database { source: e })?
-            .collect::<std::result::Result<Vec<_>, _>>()
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    query,
+                    limit,
+                    weights.filename,
+                    weights.path,
+                    weights.content,
+                    open,
+                    close
+                ],
+                |row| {
+                    Ok(SearchResult {
+                        path: row.get::<_, String>(0)?,
+                        rank: row.get::<_, f64>(1)?,
+                        snippet: None,
+                        filename: None,
+                        density: None,
+                        ranking: None,
+                        line_matches: None,
+                        highlighted: row.get::<_, Option<String>>(2)?,
+                    })
+                },
+            )
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        Ok(paths)
+        let mut results = Vec::with_capacity(result_capacity(limit));
+        for row in rows {
+            results.push(row.map_err(|e| IndexerError::Database { source: e })?);
+        }
+        Ok(results)
     }
 
-    /// Remove database entries for files that no longer exist on disk.
-    ///
-    /// Returns the number of pruned rows.
+    /// Like [`search`](Self::search), restricted to files whose detected language is
+    /// `language` (e.g. `rust`, see [`detect_language`]).
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if any query or deletion fails.
-    pub fn prune_missing_files(&mut self, root: &Path) -> Result<usize> {
+    /// Returns `IndexerError::Database` if query preparation or execution fails.
+    pub fn search_in_language(
+        &self,
+        query: &str,
+        language: &str,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_filtered(
+            query,
+            &SearchFilter { language: Some(language), ..Default::default() },
+            limit,
+        )
+    }
+
+    /// Like [`search`](Self::search), ordered by `sort` instead of rank.
+    ///
+    /// The order applies to all matches, not just the best-ranked ones: with
+    /// [`SortOrder::MtimeDesc`] the first result is the newest matching file.
+    /// Equal keys fall back to rank order. [`SortOrder::Density`] needs file content
+    /// and is applied by [`Searcher`](crate::Searcher); here it means rank order.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if query preparation or execution fails.
+    pub fn search_sorted(
+        &self,
+        query: &str,
+        sort: SortOrder,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_filtered(query, &SearchFilter { sort, ..Default::default() }, limit)
+    }
+
+    /// Like [`search`](Self::search), restricted and ordered by `filter`.
+    ///
+    /// The modification-time and size ranges are inclusive; either end may be open.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if query preparation or execution fails.
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter<'_>,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_columns_filtered(
+            &sanitize_fts_query(query),
+            ColumnSet::ALL,
+            Bm25Weights::default(),
+            filter,
+            limit,
+        )
+    }
+
+    /// Like [`search_columns_weighted`](Self::search_columns_weighted), restricted and
+    /// ordered by `filter` (see [`search_filtered`](Self::search_filtered)).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_columns_filtered(
+        &self,
+        query: &str,
+        columns: ColumnSet,
+        weights: Bm25Weights,
+        filter: &SearchFilter<'_>,
+        limit: u32,
+    ) -> Result<Vec<SearchResult>> {
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
         let mut stmt = self
             .conn
-            .prepare("SELECT path FROM files")
+            .prepare_cached(filtered_search_sql(filter.sort))
             .map_err(|e| IndexerError::Database { source: e })?;
-
         let rows = stmt
-            .query_map([], |row| row.get::<_, String>(0))
+            .query_map(
+                rusqlite::params![
+                    columns.filter_expression(query),
+                    limit,
+                    weights.filename,
+                    weights.path,
+                    weights.content,
+                    filter.language,
+                    // Open ends become the full range so `BETWEEN` can use idx_files_mtime
+                    filter.modified_since.unwrap_or(i64::MIN),
+                    filter.modified_until.unwrap_or(i64::MAX),
+                    filter.min_size.unwrap_or(i64::MIN),
+                    filter.max_size.unwrap_or(i64::MAX),
+                    filter.recency_boost,
+                    chrono::Utc::now().timestamp()
+                ],
+                |row| {
+                    Ok(SearchResult {
+                        path: row.get::<_, String>(0)?,
+                        rank: row.get::<_, f64>(1)?,
+                        snippet: None,
+                        filename: None,
+                        density: None,
+                        ranking: None,
+                        line_matches: None,
+                        highlighted: None,
+                    })
+                },
+            )
             .map_err(|e| IndexerError::Database { source: e })?;
 
-        let mut missing = Vec::new();
+        let mut results = Vec::with_capacity(result_capacity(limit));
         for row in rows {
-            let rel_path = row.map_err(|e| IndexerError::Database { source: e })?;
-            if !root.join(&rel_path).exists() {
-                missing.push(rel_path);
-            }
+            results.push(row.map_err(|e| IndexerError::Database { source: e })?);
         }
+        Ok(results)
+    }
 
-        if missing.is_empty() {
-            return Ok(0);
+    /// Path and highlighted snippet for the best content matches of `query`.
+    ///
+    /// A focused, read-only API for preview panes and context extraction: only the
+    /// content column is searched, so every snippet contains at least one
+    /// `<b>…</b>`-wrapped match. At most `limit` pairs are returned, best first.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 MATCH query fails.
+    pub fn search_snippets(&self, query: &str, limit: u32) -> Result<Vec<(String, String)>> {
+        let results = self.search_with_snippets(query, ColumnSet::CONTENT, limit)?;
+        Ok(results.into_iter().map(|r| (r.path, r.snippet.unwrap_or_default())).collect())
+    }
+
+    /// Run a search statement whose columns are `path, rank` plus optional
+    /// `filename` and `snippet` columns (detected by name).
+    fn run_search(
+        &self,
+        sql: &str,
+        query: &str,
+        columns: ColumnSet,
+        weights: Bm25Weights,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<SearchResult>> {
+        // Handle empty queries gracefully
+        if query.trim().is_empty() {
+            return Ok(vec![]);
         }
 
-        drop(stmt);
+        let mut stmt =
+            self.conn.prepare_cached(sql).map_err(|e| IndexerError::Database { source: e })?;
+        let filename_idx = stmt.column_index("filename").ok();
+        let snippet_idx = stmt.column_index("snippet").ok();
 
-        let tx = self.conn.transaction().map_err(|e| IndexerError::Database { source: e })?;
-        {
-            let mut delete_stmt = tx
-                .prepare("DELETE FROM files WHERE path = ?")
-                .map_err(|e| IndexerError::Database { source: e })?;
-            for rel_path in &missing {
-                delete_stmt
-                    .execute([rel_path])
-                    .map_err(|e| IndexerError::Database { source: e })?;
-            }
+        // Pre-allocate Vec to avoid reallocation in hot path
+        let mut results = Vec::with_capacity(result_capacity(limit));
+        let match_expr = columns.filter_expression(query);
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    match_expr,
+                    limit,
+                    weights.filename,
+                    weights.path,
+                    weights.content,
+                    offset
+                ],
+                |row| {
+                    Ok(SearchResult {
+                        path: row.get::<_, String>(0)?,
+                        rank: row.get::<_, f64>(1)?,
+                        snippet: match snippet_idx {
+                            Some(idx) => Some(content_snippet(row.get::<_, String>(idx)?)),
+                            None => None,
+                        },
+                        filename: match filename_idx {
+                            Some(idx) => Some(row.get::<_, String>(idx)?),
+                            None => None,
+                        },
+                        density: None,
+                        ranking: None,
+                        line_matches: None,
+                        highlighted: None,
+                    })
+                },
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        for row in rows {
+            results.push(row.map_err(|e| IndexerError::Database { source: e })?);
         }
-        tx.commit().map_err(|e| IndexerError::Database { source: e })?;
 
-        Ok(missing.len())
+        Ok(results)
     }
 
-    /// Get total number of indexed files.
+    /// SQL used by [`search_columns`](Self::search_columns).
     ///
-    /// # Errors
-    /// Returns `IndexerError::Database` if the COUNT query fails.
-    #[must_use = "returns count that should be used"]
-    pub fn get_file_count(&self) -> Result<usize> {
-        let count: i64 = self
-            .conn
-            .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
-            .map_err(|e| IndexerError::Database { source: e })?;
-        // Safety: File count will never exceed usize::MAX (limited by available memory)
-        // SQLite COUNT returns i64, but practical file counts fit in usize on all platforms
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        Ok(count as usize)
+    /// BM25 weights are parameters `?3..?5` ([`Bm25Weights`], default filename=100,
+    /// path=50, content=1). Column order in FTS5: filename, path, content.
+    /// `rowid` breaks rank ties so `LIMIT ?2 OFFSET ?6` pages are stable.
+    const SEARCH_SQL: &'static str = "SELECT path, bm25(files_fts, ?3, ?4, ?5) AS rank
+         FROM files_fts WHERE files_fts MATCH ?1 ORDER BY rank, rowid LIMIT ?2 OFFSET ?6";
+
+    /// [`SEARCH_SQL`](Self::SEARCH_SQL) plus the stored filename via a join on `files`.
+    const SEARCH_WITH_FILENAME_SQL: &'static str =
+        "SELECT files.path, bm25(files_fts, ?3, ?4, ?5) AS rank, files.filename
+         FROM files_fts JOIN files ON files.id = files_fts.rowid
+         WHERE files_fts MATCH ?1 ORDER BY rank, files_fts.rowid LIMIT ?2 OFFSET ?6";
+
+    /// [`SEARCH_SQL`](Self::SEARCH_SQL) plus a highlighted content snippet.
+    const SEARCH_WITH_SNIPPETS_SQL: &'static str =
+        "SELECT path, bm25(files_fts, ?3, ?4, ?5) AS rank,
+         snippet(files_fts, 2, '<b>', '</b>', '…', 32) AS snippet
+         FROM files_fts WHERE files_fts MATCH ?1 ORDER BY rank, rowid LIMIT ?2 OFFSET ?6";
+
+    /// Compatibility mapping for the `paths_only` flag.
+    const fn paths_only_columns(paths_only: bool) -> ColumnSet {
+        if paths_only { ColumnSet::PATH } else { ColumnSet::ALL }
     }
 
-    /// Optimize FTS5 index by merging b-trees.
+    /// Check that `query` is a valid FTS5 MATCH expression without running a search.
+    ///
+    /// FTS5 parses the expression lazily when the statement first steps, so preparing
+    /// alone cannot catch syntax errors. The statement therefore steps once with
+    /// `LIMIT 1`, reading at most one row. The query is checked verbatim (no
+    /// sanitization), which is what tooling that builds FTS5 queries needs.
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if the FTS5 optimize command fails.
-    pub fn optimize_fts(&self) -> Result<()> {
-        // 'optimize' command merges FTS5 segment b-trees
-        self.conn
-            .execute("INSERT INTO files_fts(files_fts) VALUES('optimize')", [])
+    /// - `IndexerError::EmptyQuery` if the query is blank
+    /// - `IndexerError::QueryParse` with the FTS5 message if the expression is invalid
+    /// - `IndexerError::Database` if the statement cannot be prepared (e.g. missing schema)
+    pub fn validate_query(&self, query: &str) -> Result<()> {
+        if query.trim().is_empty() {
+            return Err(IndexerError::EmptyQuery);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT 1 FROM files_fts WHERE files_fts MATCH ?1 LIMIT 1")
             .map_err(|e| IndexerError::Database { source: e })?;
 
+        // Only the MATCH argument varies, so step errors are query errors
+        let mut rows = stmt.query([query]).map_err(|e| IndexerError::QueryParse(e.to_string()))?;
+        rows.next().map_err(|e| IndexerError::QueryParse(e.to_string()))?;
+
         Ok(())
     }
 
-    /// Optimize `SQLite` query planner statistics (2025+ best practice).
+    /// Extract a content snippet for `path` around the tokens matched by `query`.
     ///
-    /// Should be called after bulk indexing operations to update statistics
-    /// that `SQLite` uses for query planning. This improves query performance
-    /// by ensuring the query planner has accurate table/index statistics.
+    /// Uses FTS5 `snippet()` on the content column, marked up per `style`. Returns
+    /// `None` if the path is not indexed or does not match, and, with highlighting,
+    /// if only the filename or path matched (nothing in the content to mark).
     ///
     /// # Errors
-    /// Returns `IndexerError::Database` if the PRAGMA optimize command fails.
-    pub fn optimize(&self) -> Result<()> {
-        self.conn
-            .execute("PRAGMA optimize", [])
+    /// Returns `IndexerError::Database` if the snippet query fails.
+    pub fn snippet(
+        &self,
+        query: &str,
+        path: &str,
+        style: SnippetStyle<'_>,
+    ) -> Result<Option<String>> {
+        if query.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT snippet(files_fts, 2, ?3, ?4, ?5, ?6)
+                 FROM files_fts JOIN files ON files.id = files_fts.rowid
+                 WHERE files_fts MATCH ?1 AND files.path = ?2",
+            )
             .map_err(|e| IndexerError::Database { source: e })?;
-        Ok(())
+
+        let snippet: Option<String> = stmt
+            .query_row(
+                rusqlite::params![
+                    query,
+                    path,
+                    style.open,
+                    style.close,
+                    style.ellipsis,
+                    style.max_tokens
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| IndexerError::Database { source: e })?;
+        Ok(snippet.filter(|s| !s.is_empty() && (style.open.is_empty() || s.contains(style.open))))
     }
 
-    /// Check FTS5 integrity.
+    /// Break down the BM25 rank of `path` for `query` by column.
     ///
-    /// Returns true if integrity check passes.
-    #[must_use = "returns integrity status that should be checked"]
-    pub fn check_fts_integrity(&self) -> bool {
-        // FTS5 integrity check command
-        self.conn.execute("INSERT INTO files_fts(files_fts) VALUES('integrity-check')", []).is_ok()
-    }
+    /// Runs `bm25()` three times, each with one column's weight from `weights` and
+    /// the others zeroed. `query` is an FTS5 MATCH expression (apply any column
+    /// filter first). Returns `None` if the path is not indexed or does not match.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the query fails.
+    pub fn explain_rank(
+        &self,
+        query: &str,
+        path: &str,
+        weights: Bm25Weights,
+    ) -> Result<Option<RankBreakdown>> {
+        if query.trim().is_empty() {
+            return Ok(None);
+        }
 
-    /// Get database connection (for transactions).
-    pub const fn conn(&self) -> &rusqlite::Connection {
-        &self.conn
-    }
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT bm25(files_fts, ?3, 0.0, 0.0), bm25(files_fts, 0.0, ?4, 0.0),
+                        bm25(files_fts, 0.0, 0.0, ?5)
+                 FROM files_fts JOIN files ON files.id = files_fts.rowid
+                 WHERE files_fts MATCH ?1 AND files.path = ?2",
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
 
-    /// Get mut database connection.
-    pub const fn conn_mut(&mut self) -> &mut rusqlite::Connection {
-        &mut self.conn
+        stmt.query_row(
+            rusqlite::params![query, path, weights.filename, weights.path, weights.content],
+            |row| {
+                Ok(RankBreakdown { filename: row.get(0)?, path: row.get(1)?, content: row.get(2)? })
+            },
+        )
+        .optional()
+        .map_err(|e| IndexerError::Database { source: e })
     }
 
-    /// Open database in read-only mode (for --doctor diagnostics).
+    /// Content of `path` with the tokens matched by `query` wrapped in `open`/`close`.
     ///
-    /// CRITICAL: Uses `SQLITE_OPEN_READ_ONLY` to ensure no WAL modifications.
-    /// This is essential for `--doctor` to be truly non-destructive.
+    /// Per-result form of [`search_with_highlight`](Self::search_with_highlight);
+    /// `query` is an FTS5 MATCH expression. Returns `None` if the path is not indexed
+    /// or does not match.
     ///
     /// # Errors
-    ///
-    /// Returns error if database file doesn't exist or can't be opened.
-    pub fn open_readonly(db_path: &Path) -> Result<Self> {
-        use rusqlite::OpenFlags;
-
-        let conn = rusqlite::Connection::open_with_flags(
-            db_path,
-            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
-        )
-        .map_err(|e| IndexerError::Database { source: e })?;
+    /// Returns `IndexerError::Database` if the highlight query fails.
+    pub fn highlight(
+        &self,
+        query: &str,
+        path: &str,
+        open: &str,
+        close: &str,
+    ) -> Result<Option<String>> {
+        if query.trim().is_empty() {
+            return Ok(None);
+        }
 
-        // Skip PRAGMA writes - just query for read-only access
-        Ok(Self { conn })
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT highlight(files_fts, 2, ?3, ?4)
+                 FROM files_fts JOIN files ON files.id = files_fts.rowid
+                 WHERE files_fts MATCH ?1 AND files.path = ?2",
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
+        stmt.query_row(rusqlite::params![query, path, open, close], |row| row.get(0))
+            .optional()
+            .map(Option::flatten)
+            .map_err(|e| IndexerError::Database { source: e })
     }
 
-    /// Check if all required schema objects exist.
+    /// Explain how `SQLite` would execute [`search`](Self::search) for `query`.
     ///
-    /// Returns a `SchemaCheck` struct with details about what's present/missing.
-    #[must_use]
-    pub fn check_schema(&self) -> SchemaCheck {
-        let mut check = SchemaCheck::default();
+    /// Runs `EXPLAIN QUERY PLAN` on the search SQL and returns one plan step per
+    /// line, indented by nesting depth. Nothing is executed, so this is safe on a
+    /// read-only connection.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the plan query cannot be prepared or run.
+    pub fn explain_query(&self, query: &str, paths_only: bool) -> Result<String> {
+        let sql = format!("EXPLAIN QUERY PLAN {}", Self::SEARCH_SQL);
+        let mut stmt = self.conn.prepare(&sql).map_err(|e| IndexerError::Database { source: e })?;
+
+        // Columns: id, parent, notused, detail
+        let match_expr = Self::paths_only_columns(paths_only).filter_expression(query);
+        let weights = Bm25Weights::default();
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    match_expr,
+                    1,
+                    weights.filename,
+                    weights.path,
+                    weights.content,
+                    0
+                ],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(3)?)),
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
 
-        // Query sqlite_master for all expected objects
-        let query = r"
-            SELECT
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='files') AS has_files_table,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='files_fts') AS has_fts_table,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='trigger' AND name='files_ai') AS has_insert_trigger,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='trigger' AND name='files_au') AS has_update_trigger,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='trigger' AND name='files_ad') AS has_delete_trigger,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_files_mtime') AS has_mtime_idx,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_files_path') AS has_path_idx,
-                (SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_files_hash') AS has_hash_idx
-        ";
+        let mut depths: Vec<(i64, usize)> = Vec::new();
+        let mut plan = String::new();
+        for row in rows {
+            let (id, parent, detail) = row.map_err(|e| IndexerError::Database { source: e })?;
+            let depth = depths.iter().find(|(pid, _)| *pid == parent).map_or(0, |(_, d)| d + 1);
+            depths.push((id, depth));
 
-        if let Ok(row) = self.conn.query_row(query, [], |row| {
-            Ok((
-                row.get::<_, i64>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, i64>(2)?,
-                row.get::<_, i64>(3)?,
-                row.get::<_, i64>(4)?,
-                row.get::<_, i64>(5)?,
-                row.get::<_, i64>(6)?,
-                row.get::<_, i64>(7)?,
-            ))
-        }) {
-            check.has_files_table = row.0 > 0;
-            check.has_fts_table = row.1 > 0;
-            check.has_insert_trigger = row.2 > 0;
-            check.has_update_trigger = row.3 > 0;
-            check.has_delete_trigger = row.4 > 0;
-            check.has_mtime_index = row.5 > 0;
-            check.has_path_index = row.6 > 0;
-            check.has_hash_index = row.7 > 0;
+            if !plan.is_empty() {
+                plan.push('\n');
+            }
+            plan.push_str(&"  ".repeat(depth));
+            plan.push_str(&detail);
         }
 
-        check
+        Ok(plan)
     }
 
-    /// Get `application_id` pragma value.
+    /// Search for files where a word of the filename starts with `term` (case-insensitive).
     ///
-    /// Expected value for cc-fts5-indexer: `0xA17E_6D42`
-    #[must_use]
-    pub fn get_application_id(&self) -> Option<u32> {
-        self.conn
-            .query_row("PRAGMA application_id", [], |row| {
-                // Safety: SQLite application_id is stored as i32 but semantically unsigned
-                // Reinterpreting the bit pattern as u32 (intended usage)
-                #[allow(clippy::cast_sign_loss)]
-                row.get::<_, i32>(0).map(|v| v as u32)
+    /// Words start at the beginning of the filename or after `-`, `_`, `.` or a space,
+    /// so `intro` finds `01-introduction.md` and `con` finds `config.rs` but not
+    /// `reconnect.rs`. Ordering is the same as
+    /// [`search_filename_contains`](Self::search_filename_contains).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the query fails.
+    pub fn search_filename_prefix(&self, term: &str, limit: u32) -> Result<Vec<String>> {
+        let search_term = term.trim().trim_end_matches('*');
+        if search_term.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let like_term = escape_like_pattern(search_term);
+        let sql = "SELECT path FROM files
+                   WHERE filename LIKE ?1 || '%' ESCAPE '\\' COLLATE NOCASE
+                      OR filename LIKE '%-' || ?1 || '%' ESCAPE '\\' COLLATE NOCASE
+                      OR filename LIKE '%\\_' || ?1 || '%' ESCAPE '\\' COLLATE NOCASE
+                      OR filename LIKE '%.' || ?1 || '%' ESCAPE '\\' COLLATE NOCASE
+                      OR filename LIKE '% ' || ?1 || '%' ESCAPE '\\' COLLATE NOCASE
+                   ORDER BY
+                       CASE WHEN filename = ?2 COLLATE NOCASE THEN 0
+                            WHEN filename LIKE ?1 || '%' ESCAPE '\\' COLLATE NOCASE THEN 1
+                            ELSE 2 END,
+                       length(filename)
+                   LIMIT ?3";
+
+        let mut stmt =
+            self.conn.prepare_cached(sql).map_err(|e| IndexerError::Database { source: e })?;
+
+        let paths: Vec<String> = stmt
+            .query_map(rusqlite::params![like_term, search_term, limit], |row| {
+                row.get::<_, String>(0)
             })
-            .ok()
-    }
+            .map_err(|e| IndexerError::Database { source: e })?
+            .filter_map(std::result::Result::ok)
+            .collect();
 
-    /// Get `journal_mode` pragma value.
-    #[must_use]
-    pub fn get_journal_mode(&self) -> Option<String> {
-        self.conn.query_row("PRAGMA journal_mode", [], |row| row.get::<_, String>(0)).ok()
+        Ok(paths)
     }
 
-    /// Get database file size in bytes.
+    /// Prefix search: files with a filename word, path token or content token
+    /// starting with `term`.
     ///
-    /// Uses `page_count * page_size` for accurate size including all pages.
-    #[must_use]
-    pub fn get_db_size_bytes(&self) -> Option<u64> {
-        let page_count: i64 =
-            self.conn.query_row("PRAGMA page_count", [], |row| row.get(0)).ok()?;
-        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0)).ok()?;
-        // Safety: page_count and page_size are always non-negative
-        // Result is database size in bytes, semantically unsigned
-        #[allow(clippy::cast_sign_loss)]
-        Some((page_count * page_size) as u64)
-    }
-}
+    /// The `porter` tokenizer stems indexed words, so a bare FTS5 `term*` misses
+    /// whenever the prefix is longer than the stem. Precedence therefore is:
+    /// 1. Filename-word prefix matches ([`search_filename_prefix`](Self::search_filename_prefix)),
+    ///    ranked [`FILENAME_MATCH_RANK`], in filename order
+    /// 2. FTS5 `"term"*` matches on filename/path/content, by BM25
+    ///
+    /// A trailing `*` on `term` is optional; punctuation is quoted, never parsed.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if either query fails.
+    pub fn search_prefix(&self, term: &str, limit: u32) -> Result<Vec<SearchResult>> {
+        let term = term.trim().trim_end_matches('*');
+        if term.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut results: Vec<SearchResult> = self
+            .search_filename_prefix(term, limit)?
+            .into_iter()
+            .map(|path| SearchResult {
+                path,
+                rank: FILENAME_MATCH_RANK,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            })
+            .collect();
+        let mut seen: HashSet<String> = results.iter().map(|r| r.path.clone()).collect();
+
+        let max = usize::try_from(limit).unwrap_or(usize::MAX);
+        if results.len() < max {
+            let fts_query = format!("{}*", sanitize_fts_query(term));
+            let fts_limit = limit.saturating_add(u32::try_from(seen.len()).unwrap_or(u32::MAX));
+            for result in self.search_raw(&fts_query, false, fts_limit, 0)? {
+                if results.len() >= max {
+                    break;
+                }
+                if seen.insert(result.path.clone()) {
+                    results.push(result);
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Search for files where filename CONTAINS the query substring (case-insensitive).
+    ///
+    /// This bypasses FTS5 token matching to enable substring searches.
+    /// FTS5 only matches whole tokens, so "intro" doesn't match "introduction".
+    /// SQL LIKE '%intro%' finds "01-introduction.md" correctly.
+    ///
+    /// Results are ordered by:
+    /// 1. Exact filename match (highest priority)
+    /// 2. Filename starts with query (prefix match)
+    /// 3. Filename length (shorter = more relevant)
+    ///
+    /// # Arguments
+    /// * `query` - Search term (wildcards stripped if present)
+    /// * `limit` - Maximum results to return
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the query fails.
+    pub fn search_filename_contains(&self, query: &str, limit: u32) -> Result<Vec<String>> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Strip wildcard if present (from auto-prefix like "01-" → "01*")
+        let search_term = query.trim_end_matches('*');
+        if search_term.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let like_term = escape_like_pattern(search_term);
+
+        // CONTAINS match with intelligent ordering:
+        // - CASE 0: exact filename match
+        // - CASE 1: filename starts with query (prefix)
+        // - CASE 2: filename contains query anywhere
+        // Secondary sort by filename length (shorter = more specific match)
+        let sql = "SELECT path FROM files
+                   WHERE filename LIKE '%' || ?1 || '%' ESCAPE '\\' COLLATE NOCASE
+                   ORDER BY
+                       CASE WHEN filename = ?2 COLLATE NOCASE THEN 0
+                            WHEN filename LIKE ?1 || '%' ESCAPE '\\' COLLATE NOCASE THEN 1
+                            ELSE 2 END,
+                       length(filename)
+                   LIMIT ?3";
+
+        let mut stmt =
+            self.conn.prepare_cached(sql).map_err(|e| IndexerError::Database { source: e })?;
+
+        let paths: Vec<String> = stmt
+            .query_map(rusqlite::params![like_term, search_term, limit], |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(|e| IndexerError::Database { source: e })?
+            .filter_map(std::result::Result::ok)
+            .collect();
+
+        Ok(paths)
+    }
+
+    /// Search for files whose name is within `max_distance` edits of `query`
+    /// (case-insensitive Levenshtein distance), so `condig` finds `config.rs`.
+    ///
+    /// The distance is taken against both the filename and its stem (the name
+    /// without its last extension). Candidates are narrowed in SQL first: the query
+    /// is cut into `max_distance + 1` pieces, and a name within `max_distance` edits
+    /// must contain at least one of them unchanged, so the `LIKE` prefilter loses no
+    /// matches. Queries no longer than `max_distance` would match almost anything
+    /// and return nothing.
+    ///
+    /// Results are ordered by distance, then filename length, then path.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the query fails.
+    pub fn search_filename_fuzzy(
+        &self,
+        query: &str,
+        limit: u32,
+        max_distance: usize,
+    ) -> Result<Vec<String>> {
+        let query = query.trim().trim_end_matches('*').to_lowercase();
+        let chars: Vec<char> = query.chars().collect();
+        if chars.len() <= max_distance {
+            return Ok(vec![]);
+        }
+
+        let pieces: Vec<String> = chars
+            .chunks(chars.len().div_ceil(max_distance + 1))
+            .map(|piece| escape_like_pattern(&piece.iter().collect::<String>()))
+            .collect();
+        let clauses: Vec<String> = (1..=pieces.len())
+            .map(|i| format!("filename LIKE '%' || ?{i} || '%' ESCAPE '\\' COLLATE NOCASE"))
+            .collect();
+        let sql = format!("SELECT path, filename FROM files WHERE {}", clauses.join(" OR "));
+
+        let mut stmt =
+            self.conn.prepare_cached(&sql).map_err(|e| IndexerError::Database { source: e })?;
+        let mut ranked: Vec<(usize, usize, String)> = stmt
+            .query_map(rusqlite::params_from_iter(&pieces), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .map_err(|e| IndexerError::Database { source: e })?
+            .filter_map(std::result::Result::ok)
+            .filter_map(|(path, filename)| {
+                let filename = filename?.to_lowercase();
+                let stem = filename.rsplit_once('.').map_or(filename.as_str(), |(stem, _)| stem);
+                let distance = levenshtein(&query, &filename).min(levenshtein(&query, stem));
+                (distance <= max_distance).then_some((distance, filename.len(), path))
+            })
+            .collect();
+
+        ranked.sort_unstable();
+        ranked.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        Ok(ranked.into_iter().map(|(_, _, path)| path).collect())
+    }
+
+    /// Lines of an indexed file's stored content that contain any query term.
+    ///
+    /// Matching is case-insensitive substring matching (like [`MatchDensity`]), so
+    /// it ignores stemming. Lines are split on `\n` (a trailing `\r` is dropped) and
+    /// numbered from 1; multi-byte UTF-8 never shifts the count. Returns an empty list
+    /// if the path is not indexed or nothing in its content matches (e.g. a filename-only
+    /// match).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the content cannot be read.
+    pub fn locate_matches(&self, path: &str, query: &str) -> Result<Vec<LineMatch>> {
+        let terms = query_terms(query);
+        let Some(content) = self.get_file_content(path)? else {
+            return Ok(Vec::new());
+        };
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(content
+            .lines()
+            .zip(1u32..)
+            .filter(|(text, _)| {
+                let lower = text.to_lowercase();
+                terms.iter().any(|term| lower.contains(term.as_str()))
+            })
+            .map(|(text, line)| LineMatch { line, text: text.to_string() })
+            .collect())
+    }
+
+    /// Get the stored content for an indexed path, without touching the filesystem.
+    ///
+    /// Returns `None` if the path is not indexed. The content is what was indexed, so
+    /// it is still available after the file is deleted (until the next prune).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    pub fn get_file_content(&self, path: &str) -> Result<Option<String>> {
+        self.conn
+            .prepare_cached("SELECT ffts_content(content) FROM files WHERE path = ?1")
+            .and_then(|mut stmt| stmt.query_row([path], |row| row.get(0)).optional())
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Get the stored filename for an indexed path.
+    ///
+    /// Returns `None` if the path is not indexed.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    pub fn get_filename(&self, path: &str) -> Result<Option<String>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT filename FROM files WHERE path = ?1")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        stmt.query_row([path], |row| row.get(0))
+            .optional()
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Get the stored modification time (Unix seconds) for an indexed path.
+    ///
+    /// Returns `None` if the path is not indexed.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    pub fn get_mtime(&self, path: &str) -> Result<Option<i64>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached("SELECT mtime FROM files WHERE path = ?1")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let mut rows = stmt.query([path]).map_err(|e| IndexerError::Database { source: e })?;
+        match rows.next().map_err(|e| IndexerError::Database { source: e })? {
+            Some(row) => row.get(0).map_err(|e| IndexerError::Database { source: e }),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the recorded metadata for an indexed path.
+    ///
+    /// Returns `None` if the path is not indexed. Compare `mtime` and `size` against
+    /// the file on disk to detect changes made outside the indexer.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    pub fn get_file_metadata(&self, path: &str) -> Result<Option<FileMetadata>> {
+        let mut stmt = self
+            .conn
+            .prepare_cached(
+                "SELECT path, filename, content_hash, mtime, size, indexed_at
+                 FROM files WHERE path = ?1",
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        stmt.query_row([path], |row| {
+            Ok(FileMetadata {
+                path: row.get(0)?,
+                filename: row.get(1)?,
+                content_hash: row.get(2)?,
+                mtime: row.get(3)?,
+                size: row.get(4)?,
+                indexed_at: row.get(5)?,
+            })
+        })
+        .optional()
+        .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Call `f` with every indexed file, streaming rows from the statement so the
+    /// index is never loaded into memory at once.
+    ///
+    /// Files come in row-id (insertion) order, so re-inserting them in this order
+    /// reproduces how search breaks rank ties.
+    ///
+    /// Returns the number of files visited; stops at the first error from `f`.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT fails, or the first error of `f`.
+    pub fn for_each_file(&self, mut f: impl FnMut(FileRecord) -> Result<()>) -> Result<usize> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT path, filename, content_hash, mtime, size, ffts_content(content)
+                 FROM files ORDER BY id",
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
+        let mut rows = stmt.query([]).map_err(|e| IndexerError::Database { source: e })?;
+
+        let mut count = 0;
+        while let Some(row) = rows.next().map_err(|e| IndexerError::Database { source: e })? {
+            let record = FileRecord {
+                path: row.get(0)?,
+                filename: row.get(1)?,
+                content_hash: row.get(2)?,
+                mtime: row.get(3)?,
+                size: row.get(4)?,
+                content: row.get(5)?,
+            };
+            f(record)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Get all indexed file paths.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    #[must_use = "returns file paths that should be used"]
+    pub fn get_all_files(&self, limit: u32) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path FROM files LIMIT ?")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let paths: Vec<String> = stmt
+            .query_map([limit], |row| row.get::<_, String>(0))
+            .map_err(|e| IndexerError::Database { source: e })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        Ok(paths)
+    }
+
+    /// Get up to `limit` indexed paths with their stored mtimes.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    pub fn get_file_mtimes(&self, limit: u32) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, mtime FROM files LIMIT ?")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        stmt.query_map([limit], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+            .map_err(|e| IndexerError::Database { source: e })?
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Map of every indexed path to its stored content hash.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SELECT query fails.
+    pub fn get_content_hashes(&self) -> Result<HashMap<String, Option<String>>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, content_hash FROM files")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))
+            .map_err(|e| IndexerError::Database { source: e })?
+            .collect::<std::result::Result<HashMap<_, _>, _>>()
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Remove database entries for files that no longer exist on disk.
+    ///
+    /// Only paths of the main root are checked; entries stored from extra index
+    /// roots (see [`Self::prune_missing_files_under`]) are kept.
+    /// Returns the number of pruned rows.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if any query or deletion fails.
+    pub fn prune_missing_files(&mut self, root: &Path) -> Result<usize> {
+        self.prune_missing_files_under(&[("", root)])
+    }
+
+    /// Remove database entries for missing files under the given roots.
+    ///
+    /// Each root is a `(prefix, dir)` pair: a stored path `prefix + rel` is checked
+    /// at `dir/rel`. The main root has an empty prefix and does not own paths
+    /// starting with [`ROOT_LABEL_PREFIX`], so entries of roots that are not listed
+    /// are never pruned. Returns the number of pruned rows.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if any query or deletion fails.
+    pub fn prune_missing_files_under(&mut self, roots: &[(&str, &Path)]) -> Result<usize> {
+        let missing = self.missing_files_under(roots)?;
+        if missing.is_empty() {
+            return Ok(0);
+        }
+
+        let tx = self.conn.transaction().map_err(|e| IndexerError::Database { source: e })?;
+        {
+            let mut delete_stmt = tx
+                .prepare("DELETE FROM files WHERE path = ?")
+                .map_err(|e| IndexerError::Database { source: e })?;
+            for rel_path in &missing {
+                delete_stmt
+                    .execute([rel_path])
+                    .map_err(|e| IndexerError::Database { source: e })?;
+            }
+        }
+        tx.commit().map_err(|e| IndexerError::Database { source: e })?;
+
+        Ok(missing.len())
+    }
+
+    /// Stored paths under the given roots whose files no longer exist on disk.
+    ///
+    /// Same ownership rules as [`Self::prune_missing_files_under`], without deleting.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the query fails.
+    pub fn missing_files_under(&self, roots: &[(&str, &Path)]) -> Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path FROM files")
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let mut missing = Vec::new();
+        for row in rows {
+            let stored = row.map_err(|e| IndexerError::Database { source: e })?;
+            if resolve_stored_path(roots, &stored).is_some_and(|path| !path.exists()) {
+                missing.push(stored);
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Get total number of indexed files.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the COUNT query fails.
+    #[must_use = "returns count that should be used"]
+    pub fn get_file_count(&self) -> Result<usize> {
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+            .map_err(|e| IndexerError::Database { source: e })?;
+        // Safety: File count will never exceed usize::MAX (limited by available memory)
+        // SQLite COUNT returns i64, but practical file counts fit in usize on all platforms
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(count as usize)
+    }
+
+    /// Copy auxiliary tables (schema and rows) from another database file into this one.
+    ///
+    /// Used by atomic reindex to carry user-facing tables (history, settings) over
+    /// to the freshly built database. Tables missing from `source` are skipped; an
+    /// existing table of the same name here is replaced. Indexes and triggers on the
+    /// copied tables are not carried over.
+    ///
+    /// Returns the names of the tables that were copied.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if attaching `source` or copying a table fails.
+    pub fn copy_tables_from(&self, source: &Path, tables: &[&str]) -> Result<Vec<String>> {
+        self.conn
+            .execute("ATTACH DATABASE ?1 AS preserved_src", [source.to_string_lossy()])
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        let result = self.copy_attached_tables(tables);
+
+        let detach = self
+            .conn
+            .execute("DETACH DATABASE preserved_src", [])
+            .map_err(|e| IndexerError::Database { source: e });
+
+        let copied = result?;
+        detach?;
+        Ok(copied)
+    }
+
+    fn copy_attached_tables(&self, tables: &[&str]) -> Result<Vec<String>> {
+        let mut copied = Vec::new();
+
+        for table in tables {
+            let create_sql: Option<String> = self
+                .conn
+                .query_row(
+                    "SELECT sql FROM preserved_src.sqlite_master WHERE type = 'table' AND name = ?1",
+                    [table],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| IndexerError::Database { source: e })?;
+            let Some(create_sql) = create_sql else { continue };
+
+            let quoted = format!("\"{}\"", table.replace('"', "\"\""));
+            let tx = self
+                .conn
+                .unchecked_transaction()
+                .map_err(|e| IndexerError::Database { source: e })?;
+            tx.execute_batch(&format!(
+                "DROP TABLE IF EXISTS main.{quoted};
+                 {create_sql};
+                 INSERT INTO main.{quoted} SELECT * FROM preserved_src.{quoted};"
+            ))
+            .map_err(|e| IndexerError::Database { source: e })?;
+            tx.commit().map_err(|e| IndexerError::Database { source: e })?;
+
+            copied.push((*table).to_string());
+        }
+
+        Ok(copied)
+    }
+
+    /// Sum of the stored `size` of every indexed file, in bytes.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the SUM query fails.
+    pub fn get_total_indexed_bytes(&self) -> Result<u64> {
+        let total: i64 = self
+            .conn
+            .query_row("SELECT COALESCE(SUM(size), 0) FROM files", [], |row| row.get(0))
+            .map_err(|e| IndexerError::Database { source: e })?;
+        // Sizes are stored from u64 file lengths and never negative
+        Ok(u64::try_from(total).unwrap_or(0))
+    }
+
+    /// Path and stored size of the largest indexed file (`None` for an empty index).
+    ///
+    /// Ties are broken by path, so the answer is stable.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the query fails.
+    pub fn get_largest_file(&self) -> Result<Option<(String, u64)>> {
+        self.conn
+            .query_row(
+                "SELECT path, COALESCE(size, 0) FROM files ORDER BY size DESC, path LIMIT 1",
+                [],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+            )
+            .optional()
+            .map(|largest| largest.map(|(path, size)| (path, u64::try_from(size).unwrap_or(0))))
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Count indexed files under a directory prefix and/or with an extension.
+    ///
+    /// Runs a single `COUNT(*)`; `None` (or an empty value) leaves that filter off.
+    /// `prefix` matches whole directory names (`src` counts `src/a.rs`, not
+    /// `srcfoo/a.rs`); `ext` may be given with or without the leading dot. LIKE
+    /// wildcards in either value are escaped.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the COUNT query fails.
+    pub fn get_file_count_filtered(
+        &self,
+        prefix: Option<&str>,
+        ext: Option<&str>,
+    ) -> Result<usize> {
+        // Matched against `path_lower`, so filters are case-insensitive (incl. non-ASCII)
+        let prefix = prefix
+            .map(|p| p.trim_start_matches("./").trim_end_matches('/'))
+            .filter(|p| !p.is_empty() && *p != ".")
+            .map(|p| escape_like_pattern(&p.to_lowercase()));
+        let ext = ext
+            .map(|e| e.trim_start_matches('.'))
+            .filter(|e| !e.is_empty())
+            .map(|e| escape_like_pattern(&e.to_lowercase()));
+
+        let count: i64 = self
+            .conn
+            .prepare_cached(
+                "SELECT COUNT(*) FROM files
+                 WHERE (?1 IS NULL OR path_lower LIKE ?1 || '/%' ESCAPE '\\')
+                   AND (?2 IS NULL OR path_lower LIKE '%.' || ?2 ESCAPE '\\')",
+            )
+            .and_then(|mut stmt| stmt.query_row(rusqlite::params![prefix, ext], |row| row.get(0)))
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        // COUNT(*) is never negative
+        Ok(usize::try_from(count).unwrap_or(0))
+    }
+
+    /// Optimize FTS5 index by merging b-trees.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 optimize command fails.
+    pub fn optimize_fts(&self) -> Result<()> {
+        // 'optimize' command merges FTS5 segment b-trees
+        self.conn
+            .execute("INSERT INTO files_fts(files_fts) VALUES('optimize')", [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        Ok(())
+    }
+
+    /// Optimize `SQLite` query planner statistics (2025+ best practice).
+    ///
+    /// Should be called after bulk indexing operations to update statistics
+    /// that `SQLite` uses for query planning. This improves query performance
+    /// by ensuring the query planner has accurate table/index statistics.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the PRAGMA optimize command fails.
+    pub fn optimize(&self) -> Result<()> {
+        self.conn
+            .execute("PRAGMA optimize", [])
+            .map_err(|e| IndexerError::Database { source: e })?;
+        Ok(())
+    }
+
+    /// Run `PRAGMA wal_checkpoint(mode)`.
+    ///
+    /// Returns `(busy, log, checkpointed)`: `busy` is non-zero if the checkpoint was
+    /// blocked, `log` is the number of frames in the WAL and `checkpointed` the number
+    /// moved into the database file. Outside WAL mode `SQLite` reports `(0, -1, -1)`.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the PRAGMA fails.
+    pub fn checkpoint(&self, mode: CheckpointMode) -> Result<(i64, i64, i64)> {
+        let sql = format!("PRAGMA wal_checkpoint({})", mode.as_sql());
+        self.conn
+            .query_row(&sql, [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Compare `files_fts` with the `files` table it indexes.
+    ///
+    /// Runs the FTS5 `integrity-check` with `rank = 1`, which also verifies the index
+    /// against the external content table, and counts the rowids the index covers
+    /// through a temporary `fts5vocab` table. Missing trigger runs (e.g. rows written
+    /// before a migration) show up as `indexed < files` or `consistent == false`.
+    ///
+    /// Needs a writable connection (both probes write to the `temp` schema or the FTS
+    /// control column).
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the counts cannot be read.
+    pub fn check_fts_sync(&self) -> Result<FtsSync> {
+        let consistent = self
+            .conn
+            .execute("INSERT INTO files_fts(files_fts, rank) VALUES('integrity-check', 1)", [])
+            .is_ok();
+
+        let files: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM files", [], |row| row.get(0))
+            .map_err(|e| IndexerError::Database { source: e })?;
+
+        self.conn
+            .execute_batch(
+                "CREATE VIRTUAL TABLE IF NOT EXISTS temp.files_fts_instances
+                 USING fts5vocab(main, files_fts, instance)",
+            )
+            .map_err(|e| IndexerError::Database { source: e })?;
+        let indexed = self
+            .conn
+            .query_row("SELECT COUNT(DISTINCT doc) FROM temp.files_fts_instances", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map_err(|e| IndexerError::Database { source: e });
+        let _ = self.conn.execute_batch("DROP TABLE IF EXISTS temp.files_fts_instances");
+
+        Ok(FtsSync {
+            files: u64::try_from(files).unwrap_or_default(),
+            indexed: u64::try_from(indexed?).unwrap_or_default(),
+            consistent,
+        })
+    }
+
+    /// Check FTS5 integrity.
+    ///
+    /// Returns true if integrity check passes.
+    #[must_use = "returns integrity status that should be checked"]
+    pub fn check_fts_integrity(&self) -> bool {
+        // FTS5 integrity check command
+        self.conn.execute("INSERT INTO files_fts(files_fts) VALUES('integrity-check')", []).is_ok()
+    }
+
+    /// Get database connection (for transactions).
+    pub const fn conn(&self) -> &rusqlite::Connection {
+        &self.conn
+    }
+
+    /// Get mut database connection.
+    pub const fn conn_mut(&mut self) -> &mut rusqlite::Connection {
+        &mut self.conn
+    }
+
+    /// Open database in read-only mode (for --doctor diagnostics).
+    ///
+    /// CRITICAL: Uses `SQLITE_OPEN_READ_ONLY` to ensure no WAL modifications.
+    /// This is essential for `--doctor` to be truly non-destructive.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if database file doesn't exist or can't be opened.
+    pub fn open_readonly(db_path: &Path) -> Result<Self> {
+        use rusqlite::OpenFlags;
+
+        let conn = rusqlite::Connection::open_with_flags(
+            db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(|e| IndexerError::Database { source: e })?;
+        Self::register_functions(&conn)?;
+
+        // Skip PRAGMA writes - just query for read-only access
+        let stored_fts = stored_fts_sql(&conn).unwrap_or_default();
+        let tokenizer = stored_fts.as_deref().and_then(stored_tokenizer).unwrap_or_default();
+        let compress_content = stored_fts.as_deref().is_some_and(reads_content_view);
+        Ok(Self { conn, tokenizer, compress_content })
+    }
+
+    /// Check if all required schema objects exist.
+    ///
+    /// Returns a `SchemaCheck` struct with details about what's present/missing.
+    #[must_use]
+    pub fn check_schema(&self) -> SchemaCheck {
+        let mut check = SchemaCheck::default();
+
+        // Query sqlite_master for all expected objects
+        let query = r"
+            SELECT
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='files') AS has_files_table,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='files_fts') AS has_fts_table,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='trigger' AND name='files_ai') AS has_insert_trigger,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='trigger' AND name='files_au') AS has_update_trigger,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='trigger' AND name='files_ad') AS has_delete_trigger,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_files_mtime') AS has_mtime_idx,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_files_path') AS has_path_idx,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name='idx_files_hash') AS has_hash_idx,
+                (SELECT COUNT(*) FROM sqlite_master WHERE type='view' AND name='files_text')
+                    OR NOT EXISTS (SELECT 1 FROM sqlite_master WHERE type='table' AND name='files_fts'
+                                   AND sql LIKE '%files_text%') AS has_content_view
+        ";
+
+        if let Ok(row) = self.conn.query_row(query, [], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, i64>(6)?,
+                row.get::<_, i64>(7)?,
+                row.get::<_, i64>(8)?,
+            ))
+        }) {
+            check.has_files_table = row.0 > 0;
+            check.has_fts_table = row.1 > 0;
+            check.has_insert_trigger = row.2 > 0;
+            check.has_update_trigger = row.3 > 0;
+            check.has_delete_trigger = row.4 > 0;
+            check.has_mtime_index = row.5 > 0;
+            check.has_path_index = row.6 > 0;
+            check.has_hash_index = row.7 > 0;
+            check.has_content_view = row.8 > 0;
+        }
+
+        check
+    }
+
+    /// Get `application_id` pragma value.
+    ///
+    /// Expected value for cc-fts5-indexer: `0xA17E_6D42`
+    #[must_use]
+    pub fn get_application_id(&self) -> Option<u32> {
+        self.conn
+            .query_row("PRAGMA application_id", [], |row| {
+                // Safety: SQLite application_id is stored as i32 but semantically unsigned
+                // Reinterpreting the bit pattern as u32 (intended usage)
+                #[allow(clippy::cast_sign_loss)]
+                row.get::<_, i32>(0).map(|v| v as u32)
+            })
+            .ok()
+    }
+
+    /// Get `journal_mode` pragma value.
+    #[must_use]
+    pub fn get_journal_mode(&self) -> Option<String> {
+        self.conn.query_row("PRAGMA journal_mode", [], |row| row.get::<_, String>(0)).ok()
+    }
+
+    /// Rebuild the database file to reclaim space left by deleted rows.
+    ///
+    /// `SQLite` never shrinks the file on its own. `VACUUM` cannot run inside a
+    /// transaction, so an open batching transaction is committed first.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the commit or `VACUUM` fails.
+    pub fn vacuum(&self) -> Result<()> {
+        self.commit_pending()?;
+        self.conn.execute_batch("VACUUM").map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Write a compacted copy of the database to `dest` (`VACUUM INTO`).
+    ///
+    /// The live database is left untouched; `dest` must not already exist.
+    ///
+    /// # Errors
+    /// - `IndexerError::InvalidUtf8` if `dest` is not valid UTF-8
+    /// - `IndexerError::Database` if the commit or `VACUUM INTO` fails
+    pub fn vacuum_into(&self, dest: &Path) -> Result<()> {
+        let dest_str = dest
+            .to_str()
+            .ok_or_else(|| IndexerError::InvalidUtf8 { path: dest.display().to_string() })?;
+        self.commit_pending()?;
+        self.conn
+            .execute("VACUUM INTO ?1", [dest_str])
+            .map(|_| ())
+            .map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Commit an open transaction (e.g. an indexer batch) so `VACUUM` can run.
+    fn commit_pending(&self) -> Result<()> {
+        if self.conn.is_autocommit() {
+            return Ok(());
+        }
+        tracing::debug!("Committing open transaction before VACUUM");
+        self.conn.execute_batch("COMMIT").map_err(|e| IndexerError::Database { source: e })
+    }
+
+    /// Get database file size in bytes.
+    ///
+    /// Uses `page_count * page_size` for accurate size including all pages.
+    #[must_use]
+    pub fn get_db_size_bytes(&self) -> Option<u64> {
+        let page_count: i64 =
+            self.conn.query_row("PRAGMA page_count", [], |row| row.get(0)).ok()?;
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |row| row.get(0)).ok()?;
+        // Safety: page_count and page_size are always non-negative
+        // Result is database size in bytes, semantically unsigned
+        #[allow(clippy::cast_sign_loss)]
+        Some((page_count * page_size) as u64)
+    }
+}
 
 /// Escape LIKE wildcard characters in user input.
 fn escape_like_pattern(input: &str) -> String {
@@ -733,6 +2800,41 @@ fn escape_like_pattern(input: &str) -> String {
     escaped
 }
 
+/// Levenshtein edit distance between `a` and `b`, by `char`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitute.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Result of [`Database::check_fts_sync`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FtsSync {
+    /// Rows in `files`
+    pub files: u64,
+    /// Distinct rowids with at least one token in `files_fts`
+    pub indexed: u64,
+    /// The content-aware FTS5 integrity check passed
+    pub consistent: bool,
+}
+
+impl FtsSync {
+    /// True if every file is indexed and the index matches its content.
+    #[must_use]
+    pub const fn in_sync(&self) -> bool {
+        self.consistent && self.files == self.indexed
+    }
+}
+
 /// Result of schema completeness check.
 ///
 /// Each boolean flag represents an independent schema component.
@@ -748,6 +2850,9 @@ pub struct SchemaCheck {
     pub has_mtime_index: bool,
     pub has_path_index: bool,
     pub has_hash_index: bool,
+    /// `files_text`, the plaintext view FTS5 reads compressed content from (also
+    /// true when `files_fts` reads `files` directly and needs no view)
+    pub has_content_view: bool,
 }
 
 impl SchemaCheck {
@@ -762,6 +2867,7 @@ impl SchemaCheck {
             && self.has_mtime_index
             && self.has_path_index
             && self.has_hash_index
+            && self.has_content_view
     }
 
     /// Count of tables (expected: 2 - files + `files_fts`).
@@ -809,35 +2915,339 @@ impl SchemaCheck {
         count
     }
 
-    /// Get list of missing objects.
+    /// Get list of missing objects.
+    #[must_use]
+    pub fn missing_objects(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if !self.has_files_table {
+            missing.push("table: files");
+        }
+        if !self.has_fts_table {
+            missing.push("table: files_fts");
+        }
+        if !self.has_insert_trigger {
+            missing.push("trigger: files_ai");
+        }
+        if !self.has_update_trigger {
+            missing.push("trigger: files_au");
+        }
+        if !self.has_delete_trigger {
+            missing.push("trigger: files_ad");
+        }
+        if !self.has_mtime_index {
+            missing.push("index: idx_files_mtime");
+        }
+        if !self.has_path_index {
+            missing.push("index: idx_files_path");
+        }
+        if !self.has_hash_index {
+            missing.push("index: idx_files_hash");
+        }
+        if !self.has_content_view {
+            missing.push("view: files_text");
+        }
+        missing
+    }
+}
+
+/// On-disk location of the stored path `stored`, given the `(prefix, dir)` of each
+/// index root (see [`root_prefixes`](crate::indexer::root_prefixes)).
+///
+/// The main root has an empty prefix and does not own paths starting with
+/// [`ROOT_LABEL_PREFIX`]; a path whose root is not listed resolves to `None`.
+#[must_use]
+pub fn resolve_stored_path<P: AsRef<str>, D: AsRef<Path>>(
+    roots: &[(P, D)],
+    stored: &str,
+) -> Option<PathBuf> {
+    roots.iter().find_map(|(prefix, dir)| {
+        let prefix = prefix.as_ref();
+        let rel_path = if prefix.is_empty() {
+            (!stored.starts_with(ROOT_LABEL_PREFIX)).then_some(stored)
+        } else {
+            stored.strip_prefix(prefix)
+        };
+        rel_path.map(|rel_path| dir.as_ref().join(rel_path))
+    })
+}
+
+/// Synthetic rank given to filename matches so they sort before any BM25 score.
+pub const FILENAME_MATCH_RANK: f64 = -1000.0;
+
+/// Upper bound on result slots pre-allocated for a single query.
+///
+/// The SQL `LIMIT` is still honored in full; this only caps the up-front
+/// allocation so a bad caller (e.g. `u32::MAX`) cannot trigger a huge one.
+pub const MAX_PREALLOCATED_RESULTS: usize = 100_000;
+
+/// Capacity to pre-allocate for a query returning at most `limit` rows.
+pub(crate) fn result_capacity(limit: u32) -> usize {
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    if limit > MAX_PREALLOCATED_RESULTS {
+        tracing::debug!(
+            limit,
+            capacity = MAX_PREALLOCATED_RESULTS,
+            "Clamping result pre-allocation"
+        );
+    }
+    limit.min(MAX_PREALLOCATED_RESULTS)
+}
+
+/// One object created by [`Database::init_schema`].
+#[derive(Clone, Copy)]
+struct SchemaObject {
+    /// `sqlite_master.type`
+    kind: &'static str,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Update trigger, also recreated by [`Database::migrate_path_lower`].
+const FILES_AU_TRIGGER_SQL: &str =
+    "CREATE TRIGGER IF NOT EXISTS files_au AFTER UPDATE ON files BEGIN
+                INSERT INTO files_fts(files_fts, rowid, filename, path, content)
+                VALUES('delete', old.id, old.filename, old.path, old.content);
+                INSERT INTO files_fts(rowid, filename, path, content)
+                VALUES (new.id, new.filename, new.path, new.content);
+            END";
+
+/// [`FILES_AU_TRIGGER_SQL`] for compressed content.
+const FILES_AU_COMPRESSED_TRIGGER_SQL: &str =
+    "CREATE TRIGGER IF NOT EXISTS files_au AFTER UPDATE ON files BEGIN
+                INSERT INTO files_fts(files_fts, rowid, filename, path, content)
+                VALUES('delete', old.id, old.filename, old.path, ffts_content(old.content));
+                INSERT INTO files_fts(rowid, filename, path, content)
+                VALUES (new.id, new.filename, new.path, ffts_content(new.content));
+            END";
+
+/// `files_fts` DDL with the given external content table and `tokenize=` value.
+macro_rules! files_fts_sql {
+    ($content:literal, $tokenize:literal) => {
+        concat!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS files_fts USING fts5(
+                filename, path, content,
+                content='",
+            $content,
+            "',
+                content_rowid='id',
+                tokenize='",
+            $tokenize,
+            "',
+                columnsize=0
+            )"
+        )
+    };
+}
+
+const FILES_FTS_PORTER_SQL: &str = files_fts_sql!("files", "porter unicode61");
+const FILES_FTS_UNICODE61_SQL: &str = files_fts_sql!("files", "unicode61");
+const FILES_FTS_TRIGRAM_SQL: &str = files_fts_sql!("files", "trigram");
+const FILES_FTS_PORTER_COMPRESSED_SQL: &str = files_fts_sql!("files_text", "porter unicode61");
+const FILES_FTS_UNICODE61_COMPRESSED_SQL: &str = files_fts_sql!("files_text", "unicode61");
+const FILES_FTS_TRIGRAM_COMPRESSED_SQL: &str = files_fts_sql!("files_text", "trigram");
+
+/// Stored `files_fts` DDL, which records the index's tokenizer and content storage
+/// (`None` if the table does not exist).
+fn stored_fts_sql(conn: &rusqlite::Connection) -> Result<Option<String>> {
+    let stored: Option<Option<String>> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'files_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| IndexerError::Database { source: e })?;
+    Ok(stored.flatten())
+}
+
+/// True if a stored `files_fts` DDL statement reads content through the
+/// `files_text` view, i.e. the index stores compressed content.
+fn reads_content_view(sql: &str) -> bool {
+    normalize_ddl(sql).contains("content='files_text'")
+}
+
+/// `tokenize=` value from a stored `files_fts` DDL statement.
+fn stored_tokenize_clause(sql: &str) -> Option<String> {
+    let normalized = normalize_ddl(sql);
+    let rest = &normalized[normalized.find("tokenize='")? + "tokenize='".len()..];
+    Some(rest[..rest.find('\'')?].to_string())
+}
+
+/// Tokenizer a stored `files_fts` DDL statement was built with, if it is one of ours.
+fn stored_tokenizer(sql: &str) -> Option<TokenizerKind> {
+    stored_tokenize_clause(sql).as_deref().and_then(TokenizerKind::from_tokenize_clause)
+}
+
+/// [`Database::search_columns_filtered`] SQL: `SEARCH_SQL` joined on `files`, with an
+/// optional `language = ?6` filter, an `mtime` window `?7..=?8`, a `size` range
+/// `?9..=?10` and the given `ORDER BY`.
+macro_rules! filtered_search_sql {
+    ($order_by:literal) => {
+        concat!(
+            "SELECT files.path, bm25(files_fts, ?3, ?4, ?5)
+                    + CASE WHEN ?11 IS NULL THEN 0.0
+                           ELSE ?11 * ffts_age_factor(files.mtime, ?12) END AS rank
+             FROM files_fts JOIN files ON files.id = files_fts.rowid
+             WHERE files_fts MATCH ?1 AND (?6 IS NULL OR files.language = ?6)
+               AND files.mtime BETWEEN ?7 AND ?8
+               AND files.size BETWEEN ?9 AND ?10
+             ORDER BY ",
+            $order_by,
+            " LIMIT ?2"
+        )
+    };
+}
+
+/// Statement for [`Database::search_columns_filtered`] with the given order.
+const fn filtered_search_sql(sort: SortOrder) -> &'static str {
+    match sort {
+        SortOrder::Rank | SortOrder::Density => filtered_search_sql!("rank, files_fts.rowid"),
+        SortOrder::PathAsc => filtered_search_sql!("files.path, files_fts.rowid"),
+        SortOrder::MtimeDesc => filtered_search_sql!("files.mtime DESC, rank, files_fts.rowid"),
+        SortOrder::SizeDesc => filtered_search_sql!("files.size DESC, rank, files_fts.rowid"),
+    }
+}
+
+/// Schema DDL in creation order (the source of truth for drift detection).
+///
+/// `files_fts` is listed with the default tokenizer and plain content; see
+/// [`Database::schema_objects`].
+const EXPECTED_SCHEMA: [SchemaObject; 8] = [
+    // Main files table
+    // The `filename` column stores just the file name (e.g., "CLAUDE.md" from "docs/CLAUDE.md")
+    // This enables precise BM25 weighting where filename matches rank higher than path matches
+    // `path_lower` is the Unicode-lowercased path for case-insensitive path filters;
+    // `path` keeps the original case for display (see `migrate_path_lower`)
+    // `language` is detected from the extension (see `language::detect_language`)
+    SchemaObject {
+        kind: "table",
+        name: "files",
+        sql: "CREATE TABLE IF NOT EXISTS files (
+                id INTEGER PRIMARY KEY,
+                path TEXT UNIQUE NOT NULL,
+                filename TEXT,
+                content_hash TEXT,
+                mtime INTEGER,
+                size INTEGER,
+                indexed_at INTEGER,
+                content TEXT,
+                path_lower TEXT,
+                language TEXT
+            )",
+    },
+    // FTS5 virtual table with external content
+    // Column order: filename, path, content (for BM25 weight arguments)
+    // BM25 weights: filename=100, path=50, content=1
+    // This ensures "CLAUDE.md" ranks higher than "docs/MASTRA-VS-CLAUDE-SDK.md" for query "claude"
+    // columnsize=0: saves 10-15% storage (BM25 ranking still works)
+    SchemaObject { kind: "table", name: "files_fts", sql: FILES_FTS_PORTER_SQL },
+    // Auto-sync triggers (include filename for FTS5 indexing)
+    SchemaObject {
+        kind: "trigger",
+        name: "files_ai",
+        sql: "CREATE TRIGGER IF NOT EXISTS files_ai AFTER INSERT ON files BEGIN
+                INSERT INTO files_fts(rowid, filename, path, content)
+                VALUES (new.id, new.filename, new.path, new.content);
+            END",
+    },
+    SchemaObject { kind: "trigger", name: "files_au", sql: FILES_AU_TRIGGER_SQL },
+    SchemaObject {
+        kind: "trigger",
+        name: "files_ad",
+        sql: "CREATE TRIGGER IF NOT EXISTS files_ad AFTER DELETE ON files BEGIN
+                INSERT INTO files_fts(files_fts, rowid, filename, path, content)
+                VALUES('delete', old.id, old.filename, old.path, old.content);
+            END",
+    },
+    // Indexes for efficient queries
+    SchemaObject {
+        kind: "index",
+        name: "idx_files_mtime",
+        sql: "CREATE INDEX IF NOT EXISTS idx_files_mtime ON files(mtime)",
+    },
+    SchemaObject {
+        kind: "index",
+        name: "idx_files_path",
+        sql: "CREATE INDEX IF NOT EXISTS idx_files_path ON files(path)",
+    },
+    // Index on content_hash for faster lazy invalidation subquery
+    SchemaObject {
+        kind: "index",
+        name: "idx_files_hash",
+        sql: "CREATE INDEX IF NOT EXISTS idx_files_hash ON files(content_hash)",
+    },
+];
+
+/// Schema objects that differ when content is compressed
+/// ([`PragmaConfig::compress_content`]): FTS5 and its triggers read `files.content`
+/// through [`CONTENT_FN`], so compressed rows are indexed as plaintext.
+///
+/// The view comes first; it is created just before `files_fts`.
+const COMPRESSED_CONTENT_SCHEMA: [SchemaObject; 4] = [
+    // Plaintext view of `files`; FTS5 reads snippet and highlight text from it
+    SchemaObject {
+        kind: "view",
+        name: "files_text",
+        sql: "CREATE VIEW IF NOT EXISTS files_text AS
+                SELECT id, filename, path, ffts_content(content) AS content FROM files",
+    },
+    SchemaObject {
+        kind: "trigger",
+        name: "files_ai",
+        sql: "CREATE TRIGGER IF NOT EXISTS files_ai AFTER INSERT ON files BEGIN
+                INSERT INTO files_fts(rowid, filename, path, content)
+                VALUES (new.id, new.filename, new.path, ffts_content(new.content));
+            END",
+    },
+    SchemaObject { kind: "trigger", name: "files_au", sql: FILES_AU_COMPRESSED_TRIGGER_SQL },
+    SchemaObject {
+        kind: "trigger",
+        name: "files_ad",
+        sql: "CREATE TRIGGER IF NOT EXISTS files_ad AFTER DELETE ON files BEGIN
+                INSERT INTO files_fts(files_fts, rowid, filename, path, content)
+                VALUES('delete', old.id, old.filename, old.path, ffts_content(old.content));
+            END",
+    },
+];
+
+/// Canonical form of a CREATE statement for comparison: lowercase, single
+/// spaces, no `IF NOT EXISTS` (`SQLite` drops it from `sqlite_master`).
+fn normalize_ddl(sql: &str) -> String {
+    let lower = sql.to_ascii_lowercase();
+    let collapsed = lower.split_whitespace().collect::<Vec<_>>().join(" ");
+    collapsed.replace(" if not exists", "").replace("( ", "(").replace(" )", ")")
+}
+
+/// A schema object whose stored definition differs from the expected DDL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaDrift {
+    /// Object label, e.g. `table: files_fts`
+    pub object: String,
+    /// DDL this version creates
+    pub expected: String,
+    /// DDL stored in `sqlite_master`
+    pub actual: String,
+}
+
+/// Result of [`Database::schema_matches_expected`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDriftReport {
+    pub mismatched: Vec<SchemaDrift>,
+}
+
+impl SchemaDriftReport {
+    /// Returns true if every existing object matches the expected DDL.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.mismatched.is_empty()
+    }
+
+    /// Suggested fix when drift was found.
     #[must_use]
-    pub fn missing_objects(&self) -> Vec<&'static str> {
-        let mut missing = Vec::new();
-        if !self.has_files_table {
-            missing.push("table: files");
-        }
-        if !self.has_fts_table {
-            missing.push("table: files_fts");
-        }
-        if !self.has_insert_trigger {
-            missing.push("trigger: files_ai");
-        }
-        if !self.has_update_trigger {
-            missing.push("trigger: files_au");
-        }
-        if !self.has_delete_trigger {
-            missing.push("trigger: files_ad");
-        }
-        if !self.has_mtime_index {
-            missing.push("index: idx_files_mtime");
-        }
-        if !self.has_path_index {
-            missing.push("index: idx_files_path");
-        }
-        if !self.has_hash_index {
-            missing.push("index: idx_files_hash");
-        }
-        missing
+    pub fn recommendation(&self) -> Option<&'static str> {
+        (!self.is_clean()).then_some(
+            "schema is from an older version; run `ffts-grep index --reindex` to migrate",
+        )
     }
 }
 
@@ -865,6 +3275,7 @@ pub fn wyhash(content: &[u8]) -> String {
 mod tests {
     use super::*;
     use crate::DB_NAME;
+    use std::collections::HashSet;
     use tempfile::tempdir;
 
     fn create_test_db() -> (tempfile::TempDir, Database) {
@@ -877,135 +3288,609 @@ mod tests {
     }
 
     #[test]
-    fn test_wyhash_empty_string() {
-        // Empty string produces valid hash
-        let hash = wyhash(b"");
-        assert_eq!(hash.len(), 16);
-        // Verify hex characters only
-        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    fn test_wyhash_empty_string() {
+        // Empty string produces valid hash
+        let hash = wyhash(b"");
+        assert_eq!(hash.len(), 16);
+        // Verify hex characters only
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_wyhash_hello() {
+        // "hello" hash for verification
+        let hash = wyhash(b"hello");
+        assert_eq!(hash.len(), 16);
+        // Verify hex characters only
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_content_hash_matches_stored_hash() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("a.rs", "fn a() {}", 0, 9).unwrap();
+
+        let stored: String = db
+            .conn()
+            .query_row("SELECT content_hash FROM files WHERE path = 'a.rs'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(Database::content_hash("fn a() {}"), stored);
+    }
+
+    #[test]
+    fn test_open_creates_db() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let config = PragmaConfig::default();
+
+        // File shouldn't exist yet
+        assert!(!db_path.exists());
+
+        let db = Database::open(&db_path, &config).unwrap();
+        db.init_schema().unwrap();
+
+        // Now file exists
+        assert!(db_path.exists());
+    }
+
+    #[test]
+    fn test_init_schema_idempotent() {
+        let (_dir, db) = create_test_db();
+
+        // Running init_schema twice should not error
+        db.init_schema().unwrap();
+        db.init_schema().unwrap();
+    }
+
+    #[test]
+    fn test_upsert_new_file() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("test.rs", "fn main() {}", 0, 12).unwrap();
+
+        let files = db.get_all_files(100).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0], "test.rs");
+    }
+
+    #[test]
+    fn test_upsert_updates_existing() {
+        let (_dir, db) = create_test_db();
+
+        // Insert file
+        db.upsert_file("test.rs", "v1", 0, 2).unwrap();
+
+        // Update with new content
+        db.upsert_file("test.rs", "v2", 1, 2).unwrap();
+
+        // Should still be one file
+        let files = db.get_all_files(100).unwrap();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_search_path_match() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("src/main.rs", "some content", 0, 12).unwrap();
+        db.upsert_file("README.md", "documentation", 0, 12).unwrap();
+
+        let results = db.search("main", false, 10).unwrap();
+        assert!(!results.is_empty());
+        assert_eq!(results[0].path, "src/main.rs");
+    }
+
+    #[test]
+    fn test_search_content_match() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("foo.rs", "fn calculate_total()", 0, 20).unwrap();
+        db.upsert_file("bar.rs", "other content", 0, 12).unwrap();
+
+        let results = db.search("calculate", false, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "foo.rs");
+    }
+
+    #[test]
+    fn test_search_bm25_path_boost() {
+        let (_dir, db) = create_test_db();
+
+        // Path contains "test" but content doesn't
+        db.upsert_file("test.rs", "unrelated content here", 0, 25).unwrap();
+        // Content contains "test" but path doesn't
+        db.upsert_file("other.rs", "test keyword in content", 0, 26).unwrap();
+
+        let results = db.search("test", false, 10).unwrap();
+        assert_eq!(results.len(), 2);
+        // Path match should rank higher (lower BM25 score)
+        assert!(results[0].rank <= results[1].rank);
+    }
+
+    #[test]
+    fn test_search_columns_weighted_reorders() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("weighty.rs", "unrelated content here", 0, 22).unwrap();
+        db.upsert_file("other.rs", "weighty weighty weighty", 0, 23).unwrap();
+
+        // Default weights: filename match wins and matches the unweighted API
+        let default =
+            db.search_columns_weighted("weighty", ColumnSet::ALL, Bm25Weights::default(), 10);
+        let default = default.unwrap();
+        assert_eq!(default[0].path, "weighty.rs");
+        let plain = db.search("weighty", false, 10).unwrap();
+        assert_eq!(
+            default.iter().map(|r| (&r.path, r.rank)).collect::<Vec<_>>(),
+            plain.iter().map(|r| (&r.path, r.rank)).collect::<Vec<_>>()
+        );
+
+        // Content-heavy weights flip the order
+        let content_first = Bm25Weights { filename: 0.0, path: 0.0, content: 10.0 };
+        let results =
+            db.search_columns_weighted("weighty", ColumnSet::ALL, content_first, 10).unwrap();
+        assert_eq!(results[0].path, "other.rs");
+    }
+
+    #[test]
+    fn test_explain_rank_breakdown_matches_combined_rank() {
+        let (_dir, db) = create_test_db();
+        // Each file matches in exactly one column, so the parts sum to the rank
+        db.upsert_file("notes/a.md", "rankterm rankterm rankterm", 0, 26).unwrap();
+        db.upsert_file("notes/b.md", "rankterm and a lot of other words here", 0, 38).unwrap();
+        db.upsert_file("rankterm/c.md", "nothing relevant", 0, 16).unwrap();
+
+        let weights = Bm25Weights::default();
+        let results = db.search_columns_weighted("rankterm", ColumnSet::ALL, weights, 10).unwrap();
+        assert_eq!(results.len(), 3);
+
+        let mut by_total = Vec::new();
+        for result in &results {
+            let breakdown = db.explain_rank("rankterm", &result.path, weights).unwrap().unwrap();
+            assert!((breakdown.total() - result.rank).abs() < 1e-9, "{}", result.path);
+            assert_eq!(breakdown.filename, 0.0);
+            by_total.push((breakdown.total(), result.path.clone()));
+        }
+        by_total.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let ordered: Vec<&String> = by_total.iter().map(|(_, path)| path).collect();
+        assert_eq!(ordered, results.iter().map(|r| &r.path).collect::<Vec<_>>());
+
+        // Path-only match is explained entirely by the path column
+        let path_only = db.explain_rank("rankterm", "rankterm/c.md", weights).unwrap().unwrap();
+        assert!(path_only.path < 0.0);
+        assert_eq!(path_only.content, 0.0);
+
+        assert_eq!(db.explain_rank("rankterm", "missing.md", weights).unwrap(), None);
+    }
+
+    #[test]
+    fn test_search_paged_pages_do_not_overlap() {
+        let (_dir, db) = create_test_db();
+        // Identical content gives tied ranks: paging must still be stable
+        for i in 0..12 {
+            db.upsert_file(&format!("src/file_{i:02}.rs"), "fn pageterm() {}", 0, 16).unwrap();
+        }
+
+        let page1 = db.search_paged("pageterm", false, 5, 0).unwrap();
+        let page2 = db.search_paged("pageterm", false, 5, 5).unwrap();
+        let page3 = db.search_paged("pageterm", false, 5, 10).unwrap();
+        assert_eq!((page1.len(), page2.len(), page3.len()), (5, 5, 2));
+
+        let paths: HashSet<&str> = page1.iter().chain(&page2).map(|r| r.path.as_str()).collect();
+        assert_eq!(paths.len(), 10);
+
+        // search is page one
+        let first: Vec<String> =
+            db.search("pageterm", false, 5).unwrap().into_iter().map(|r| r.path).collect();
+        assert_eq!(first, page1.iter().map(|r| r.path.clone()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_search_paths_only() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("src/main.rs", "hello world", 0, 11).unwrap();
+
+        // Paths-only search for "main" (in path)
+        let results = db.search("main", true, 10).unwrap();
+        assert!(!results.is_empty());
+
+        // Full search for "world" (in content but not path)
+        let results_full = db.search("world", false, 10).unwrap();
+        assert!(!results_full.is_empty()); // "world" IS in content
+    }
+
+    #[test]
+    fn test_search_empty_query() {
+        let (_dir, db) = create_test_db();
+
+        // Empty-like query
+        let results = db.search("", false, 10).unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_get_mtime() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("a.rs", "a", 1_700_000_000, 1).unwrap();
+
+        assert_eq!(db.get_mtime("a.rs").unwrap(), Some(1_700_000_000));
+        assert_eq!(db.get_mtime("missing.rs").unwrap(), None);
+    }
+
+    #[test]
+    fn test_snippet_extracts_matching_content() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("a.rs", "first line\nfn snippetterm() {}\nlast line", 0, 40).unwrap();
+
+        let snippet = db.snippet("snippetterm", "a.rs", SnippetStyle::PLAIN).unwrap().unwrap();
+        assert!(snippet.contains("snippetterm"));
+        assert_eq!(db.snippet("snippetterm", "missing.rs", SnippetStyle::PLAIN).unwrap(), None);
+        assert_eq!(db.snippet("absentterm", "a.rs", SnippetStyle::PLAIN).unwrap(), None);
+
+        let style = SnippetStyle { open: "[", close: "]", ellipsis: "~", max_tokens: 2 };
+        let snippet = db.snippet("snippetterm", "a.rs", style).unwrap().unwrap();
+        assert!(snippet.starts_with("~[snippetterm]"), "{snippet}");
+        assert!(!snippet.contains("first"), "{snippet}");
+    }
+
+    #[test]
+    fn test_search_snippets_returns_marked_matches() {
+        let (_dir, db) = create_test_db();
+        for i in 0..3 {
+            let content = format!("line {i}\nlet previewterm = {i};\nend");
+            db.upsert_file(&format!("src/file_{i}.rs"), &content, 0, 30).unwrap();
+        }
+        // Path-only match: not a content match, so no snippet entry
+        db.upsert_file("previewterm/notes.md", "nothing here", 0, 12).unwrap();
+
+        let snippets = db.search_snippets("previewterm", 10).unwrap();
+        assert_eq!(snippets.len(), 3);
+        assert!(snippets.iter().all(|(path, snippet)| {
+            path.starts_with("src/") && snippet.contains("<b>previewterm</b>")
+        }));
+
+        assert_eq!(db.search_snippets("previewterm", 2).unwrap().len(), 2);
+        assert!(db.search_snippets("", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_with_snippets_highlights_content() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("src/lib.rs", "first line\nfn highlightterm() {}\nlast line", 0, 40)
+            .unwrap();
+        db.upsert_file("highlightterm.md", "nothing relevant in here", 0, 24).unwrap();
+
+        let results = db.search_with_snippets("highlightterm", ColumnSet::ALL, 10).unwrap();
+        assert_eq!(results.len(), 2);
+        let snippet_of = |path: &str| {
+            results.iter().find(|r| r.path == path).and_then(|r| r.snippet.clone()).unwrap()
+        };
+        assert!(snippet_of("src/lib.rs").contains("<b>highlightterm</b>"));
+        // Filename-only match: empty rather than unrelated leading content
+        assert_eq!(snippet_of("highlightterm.md"), "");
+
+        let snippet = |path| db.snippet("highlightterm", path, SnippetStyle::HIGHLIGHTED).unwrap();
+        assert!(snippet("src/lib.rs").unwrap().contains("<b>highlightterm</b>"));
+        assert_eq!(snippet("highlightterm.md"), None);
+        assert_eq!(snippet("missing.rs"), None);
+    }
+
+    #[test]
+    fn test_rebuild_fts_index_chunked_matches_files_count() {
+        let (_dir, db) = create_test_db();
+        for i in 0..1_050 {
+            db.upsert_file(
+                &format!("src/file_{i}.rs"),
+                &format!("fn rebuildterm_{i}() {{}}"),
+                0,
+                20,
+            )
+            .unwrap();
+        }
+        db.conn().execute("INSERT INTO files_fts(files_fts) VALUES('delete-all')", []).unwrap();
+
+        let mut reports = Vec::new();
+        let inserted =
+            db.rebuild_fts_index_chunked(100, |done, total| reports.push((done, total))).unwrap();
+
+        assert_eq!(inserted, 1_050);
+        assert_eq!(reports.len(), 11);
+        assert_eq!(reports.last(), Some(&(1_050, 1_050)));
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+
+        // Every row indexed exactly once: term counts and FTS5 integrity agree
+        let fts_count: i64 = db
+            .conn()
+            .query_row("SELECT COUNT(*) FROM files_fts WHERE files_fts MATCH 'fn'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(fts_count, 1_050);
+        assert_eq!(i64::try_from(db.get_file_count().unwrap()).unwrap(), fts_count);
+        db.conn()
+            .execute("INSERT INTO files_fts(files_fts) VALUES('integrity-check')", [])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_validate_query_accepts_valid_query() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("a.rs", "fn main() {}", 0, 12).unwrap();
+
+        assert!(db.validate_query("main").is_ok());
+        assert!(db.validate_query("\"fn main\" OR test*").is_ok());
+    }
+
+    #[test]
+    fn test_validate_query_rejects_unbalanced_quote() {
+        let (_dir, db) = create_test_db();
+
+        assert!(matches!(db.validate_query("\"unterminated"), Err(IndexerError::QueryParse(_))));
+        assert!(matches!(db.validate_query("   "), Err(IndexerError::EmptyQuery)));
+    }
+
+    fn create_count_fixture() -> (tempfile::TempDir, Database) {
+        let (dir, db) = create_test_db();
+        for path in [
+            "src/main.rs",
+            "src/lib.rs",
+            "src/db/mod.rs",
+            "src/notes.md",
+            "srcfoo/x.rs",
+            "README.md",
+            "a_b/c.rs",
+        ] {
+            db.upsert_file(path, "content", 0, 7).unwrap();
+        }
+        (dir, db)
+    }
+
+    #[test]
+    fn test_total_bytes_and_largest_file() {
+        let (_dir, db) = create_test_db();
+        assert_eq!(db.get_total_indexed_bytes().unwrap(), 0);
+        assert_eq!(db.get_largest_file().unwrap(), None);
+
+        db.upsert_file("a.rs", "a", 0, 10).unwrap();
+        db.upsert_file("c.rs", "c", 0, 30).unwrap();
+        db.upsert_file("b.rs", "b", 0, 30).unwrap();
+
+        assert_eq!(db.get_total_indexed_bytes().unwrap(), 70);
+        assert_eq!(db.get_largest_file().unwrap(), Some(("b.rs".to_string(), 30)));
+    }
+
+    #[test]
+    fn test_get_file_count_filtered_prefix_only() {
+        let (_dir, db) = create_count_fixture();
+
+        assert_eq!(db.get_file_count_filtered(Some("src"), None).unwrap(), 4);
+        assert_eq!(db.get_file_count_filtered(Some("src/db/"), None).unwrap(), 1);
+        // '_' is escaped, not a single-char wildcard
+        assert_eq!(db.get_file_count_filtered(Some("a_b"), None).unwrap(), 1);
+        assert_eq!(db.get_file_count_filtered(Some("a%"), None).unwrap(), 0);
+        assert_eq!(db.get_file_count_filtered(None, None).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_get_file_count_filtered_ext_only() {
+        let (_dir, db) = create_count_fixture();
+
+        assert_eq!(db.get_file_count_filtered(None, Some("rs")).unwrap(), 5);
+        assert_eq!(db.get_file_count_filtered(None, Some(".md")).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_get_file_count_filtered_combined() {
+        let (_dir, db) = create_count_fixture();
+
+        assert_eq!(db.get_file_count_filtered(Some("src"), Some("rs")).unwrap(), 3);
+        assert_eq!(db.get_file_count_filtered(Some("src"), Some("md")).unwrap(), 1);
+        assert_eq!(db.get_file_count_filtered(Some("docs"), Some("rs")).unwrap(), 0);
     }
 
     #[test]
-    fn test_wyhash_hello() {
-        // "hello" hash for verification
-        let hash = wyhash(b"hello");
-        assert_eq!(hash.len(), 16);
-        // Verify hex characters only
-        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    fn test_get_file_count_filtered_case_insensitive() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("Src/Main.RS", "a", 0, 1).unwrap();
+        db.upsert_file("src/lib.rs", "b", 0, 1).unwrap();
+        db.upsert_file("Ärger/notes.md", "c", 0, 1).unwrap();
+
+        assert_eq!(db.get_file_count_filtered(Some("SRC"), None).unwrap(), 2);
+        assert_eq!(db.get_file_count_filtered(Some("src"), Some("Rs")).unwrap(), 2);
+        // Non-ASCII folds too (SQLite's own LIKE/lower() only fold ASCII)
+        assert_eq!(db.get_file_count_filtered(Some("ärger"), None).unwrap(), 1);
+
+        // Display path keeps its original case
+        let stored: String = db
+            .conn()
+            .query_row("SELECT path FROM files WHERE path_lower = 'src/main.rs'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(stored, "Src/Main.RS");
     }
 
     #[test]
-    fn test_open_creates_db() {
-        let dir = tempdir().unwrap();
-        let db_path = dir.path().join("test.db");
-        let config = PragmaConfig::default();
+    fn test_migrate_path_lower_backfills_existing_rows() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("Docs/Guide.md", "guide", 0, 5).unwrap();
 
-        // File shouldn't exist yet
-        assert!(!db_path.exists());
+        // Simulate a database created before path_lower (and the later language column) existed
+        db.conn().execute("ALTER TABLE files DROP COLUMN language", []).unwrap();
+        db.conn().execute("ALTER TABLE files DROP COLUMN path_lower", []).unwrap();
+        assert_eq!(db.get_file_count_filtered(Some("docs"), None).ok(), None);
 
-        let db = Database::open(&db_path, &config).unwrap();
         db.init_schema().unwrap();
-
-        // Now file exists
-        assert!(db_path.exists());
+        assert_eq!(db.get_file_count_filtered(Some("DOCS"), Some("MD")).unwrap(), 1);
+        assert!(!db.migrate_path_lower().unwrap());
+        assert!(db.schema_matches_expected().unwrap().is_clean());
     }
 
     #[test]
-    fn test_init_schema_idempotent() {
+    fn test_search_in_language_excludes_other_languages() {
         let (_dir, db) = create_test_db();
+        db.upsert_file("src/parser.rs", "fn parse_tokens() {}", 0, 20).unwrap();
+        db.upsert_file("tools/parser.py", "def parse_tokens(): pass", 0, 24).unwrap();
 
-        // Running init_schema twice should not error
-        db.init_schema().unwrap();
-        db.init_schema().unwrap();
+        assert_eq!(db.search("parse_tokens", false, 10).unwrap().len(), 2);
+
+        let rust = db.search_in_language("parse_tokens", "rust", 10).unwrap();
+        assert_eq!(rust.len(), 1);
+        assert_eq!(rust[0].path, "src/parser.rs");
+
+        let python = db.search_in_language("parse_tokens", "python", 10).unwrap();
+        assert_eq!(python.len(), 1);
+        assert_eq!(python[0].path, "tools/parser.py");
+        assert!(db.search_in_language("parse_tokens", "go", 10).unwrap().is_empty());
     }
 
     #[test]
-    fn test_upsert_new_file() {
+    fn test_migrate_language_backfills_existing_rows() {
         let (_dir, db) = create_test_db();
+        db.upsert_file("src/lib.rs", "pub fn lib() {}", 0, 15).unwrap();
 
-        db.upsert_file("test.rs", "fn main() {}", 0, 12).unwrap();
+        // Simulate a database created before language existed
+        db.conn().execute("ALTER TABLE files DROP COLUMN language", []).unwrap();
 
-        let files = db.get_all_files(100).unwrap();
-        assert_eq!(files.len(), 1);
-        assert_eq!(files[0], "test.rs");
+        db.init_schema().unwrap();
+        assert_eq!(db.search_in_language("lib", "rust", 10).unwrap().len(), 1);
+        assert!(!db.migrate_language().unwrap());
+        assert!(db.schema_matches_expected().unwrap().is_clean());
     }
 
     #[test]
-    fn test_upsert_updates_existing() {
+    fn test_search_sorted_orders_all_matches() {
         let (_dir, db) = create_test_db();
+        db.upsert_file("b.rs", "sortterm sortterm sortterm", 300, 10).unwrap();
+        db.upsert_file("a.rs", "sortterm", 100, 30).unwrap();
+        db.upsert_file("c.rs", "sortterm", 200, 20).unwrap();
 
-        // Insert file
-        db.upsert_file("test.rs", "v1", 0, 2).unwrap();
-
-        // Update with new content
-        db.upsert_file("test.rs", "v2", 1, 2).unwrap();
+        let paths = |sort: SortOrder, limit: u32| -> Vec<String> {
+            db.search_sorted("sortterm", sort, limit).unwrap().into_iter().map(|r| r.path).collect()
+        };
 
-        // Should still be one file
-        let files = db.get_all_files(100).unwrap();
-        assert_eq!(files.len(), 1);
+        assert_eq!(paths(SortOrder::PathAsc, 10), ["a.rs", "b.rs", "c.rs"]);
+        assert_eq!(paths(SortOrder::MtimeDesc, 10), ["b.rs", "c.rs", "a.rs"]);
+        assert_eq!(paths(SortOrder::SizeDesc, 10), ["a.rs", "c.rs", "b.rs"]);
+        // The order covers every match, not just the best-ranked page
+        assert_eq!(paths(SortOrder::SizeDesc, 1), ["a.rs"]);
     }
 
     #[test]
-    fn test_search_path_match() {
+    fn test_search_filtered_mtime_window() {
         let (_dir, db) = create_test_db();
+        db.upsert_file("old.rs", "windowterm", 1_000, 10).unwrap();
+        db.upsert_file("new.rs", "windowterm", 5_000, 10).unwrap();
+
+        let paths = |since: Option<i64>, until: Option<i64>| -> Vec<String> {
+            let filter =
+                SearchFilter { modified_since: since, modified_until: until, ..Default::default() };
+            let mut paths: Vec<String> = db
+                .search_filtered("windowterm", &filter, 10)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect();
+            paths.sort();
+            paths
+        };
 
-        db.upsert_file("src/main.rs", "some content", 0, 12).unwrap();
-        db.upsert_file("README.md", "documentation", 0, 12).unwrap();
-
-        let results = db.search("main", false, 10).unwrap();
-        assert!(!results.is_empty());
-        assert_eq!(results[0].path, "src/main.rs");
+        assert_eq!(paths(None, None), ["new.rs", "old.rs"]);
+        assert_eq!(paths(Some(2_000), None), ["new.rs"]);
+        assert_eq!(paths(None, Some(2_000)), ["old.rs"]);
+        // Inclusive bounds
+        assert_eq!(paths(Some(1_000), Some(1_000)), ["old.rs"]);
+        assert!(paths(Some(2_000), Some(4_000)).is_empty());
     }
 
     #[test]
-    fn test_search_content_match() {
+    fn test_search_filtered_size_range_composes_with_mtime() {
         let (_dir, db) = create_test_db();
+        db.upsert_file("stub.rs", "sizeterm", 1_000, 10).unwrap();
+        db.upsert_file("big_old.rs", "sizeterm", 1_000, 10_240).unwrap();
+        db.upsert_file("big_new.rs", "sizeterm", 5_000, 10_240).unwrap();
 
-        db.upsert_file("foo.rs", "fn calculate_total()", 0, 20).unwrap();
-        db.upsert_file("bar.rs", "other content", 0, 12).unwrap();
+        let filter = SearchFilter { min_size: Some(1_024), ..Default::default() };
+        assert_eq!(db.search_filtered("sizeterm", &filter, 10).unwrap().len(), 2);
 
-        let results = db.search("calculate", false, 10).unwrap();
+        let filter = SearchFilter { max_size: Some(10), ..Default::default() };
+        let results = db.search_filtered("sizeterm", &filter, 10).unwrap();
         assert_eq!(results.len(), 1);
-        assert_eq!(results[0].path, "foo.rs");
+        assert_eq!(results[0].path, "stub.rs");
+
+        let filter = SearchFilter {
+            min_size: Some(1_024),
+            modified_since: Some(2_000),
+            ..Default::default()
+        };
+        let results = db.search_filtered("sizeterm", &filter, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "big_new.rs");
     }
 
     #[test]
-    fn test_search_bm25_path_boost() {
+    fn test_search_count_ignores_limit() {
         let (_dir, db) = create_test_db();
+        for i in 0..30 {
+            db.upsert_file(&format!("src/file{i}.rs"), "fn needle() {}", 0, 14).unwrap();
+        }
+        db.upsert_file("docs/needle.md", "unrelated", 0, 9).unwrap();
+        db.upsert_file("other.rs", "fn haystack() {}", 0, 16).unwrap();
+
+        assert_eq!(db.search("needle", false, 5).unwrap().len(), 5);
+        assert_eq!(db.search_count("needle", false).unwrap(), 31);
+        assert_eq!(db.search_count("needle", true).unwrap(), 1);
+        assert_eq!(db.search_count("fn(needle)", false).unwrap(), 30, "Query is sanitized");
+        assert_eq!(db.search_count("   ", false).unwrap(), 0);
+    }
 
-        // Path contains "test" but content doesn't
-        db.upsert_file("test.rs", "unrelated content here", 0, 25).unwrap();
-        // Content contains "test" but path doesn't
-        db.upsert_file("other.rs", "test keyword in content", 0, 26).unwrap();
+    #[test]
+    fn test_search_huge_limit_clamps_preallocation() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("tiny.rs", "fn tiny() {}", 0, 12).unwrap();
 
-        let results = db.search("test", false, 10).unwrap();
-        assert_eq!(results.len(), 2);
-        // Path match should rank higher (lower BM25 score)
-        assert!(results[0].rank <= results[1].rank);
+        // u32::MAX results would be hundreds of GB if pre-allocated
+        let results = db.search("tiny", false, u32::MAX).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results.capacity() <= MAX_PREALLOCATED_RESULTS);
+        assert_eq!(result_capacity(u32::MAX), MAX_PREALLOCATED_RESULTS);
+        assert_eq!(result_capacity(20), 20);
     }
 
     #[test]
-    fn test_search_paths_only() {
+    fn test_search_columns_combinations() {
         let (_dir, db) = create_test_db();
+        // filename: beta.rs, path: alpha/beta.rs, content: gamma
+        db.upsert_file("alpha/beta.rs", "gamma", 0, 5).unwrap();
 
-        db.upsert_file("src/main.rs", "hello world", 0, 11).unwrap();
-
-        // Paths-only search for "main" (in path)
-        let results = db.search("main", true, 10).unwrap();
-        assert!(!results.is_empty());
+        let hits = |columns: ColumnSet, query: &str| {
+            !db.search_columns(query, columns, 10).unwrap().is_empty()
+        };
 
-        // Full search for "world" (in content but not path)
-        let results_full = db.search("world", false, 10).unwrap();
-        assert!(!results_full.is_empty()); // "world" IS in content
+        let cases = [
+            (ColumnSet::FILENAME, [false, true, false]),
+            (ColumnSet::PATH, [true, true, false]),
+            (ColumnSet::CONTENT, [false, false, true]),
+            (ColumnSet::FILENAME | ColumnSet::PATH, [true, true, false]),
+            (ColumnSet::FILENAME | ColumnSet::CONTENT, [false, true, true]),
+            (ColumnSet::PATH | ColumnSet::CONTENT, [true, true, true]),
+            (ColumnSet::ALL, [true, true, true]),
+        ];
+        for (columns, expected) in cases {
+            let actual = [hits(columns, "alpha"), hits(columns, "beta"), hits(columns, "gamma")];
+            assert_eq!(actual, expected, "columns {columns:?}");
+        }
     }
 
     #[test]
-    fn test_search_empty_query() {
-        let (_dir, db) = create_test_db();
-
-        // Empty-like query
-        let results = db.search("", false, 10).unwrap();
-        assert!(results.is_empty());
+    fn test_column_set_filter_expression() {
+        assert_eq!(ColumnSet::ALL.filter_expression("a b"), "a b");
+        assert_eq!(ColumnSet::PATH.filter_expression("a b"), "{path} : (a b)");
+        assert_eq!(
+            (ColumnSet::CONTENT | ColumnSet::FILENAME).filter_expression("a*"),
+            "{filename content} : (a*)"
+        );
     }
 
     #[test]
@@ -1027,6 +3912,111 @@ mod tests {
         db.optimize_fts().unwrap();
     }
 
+    #[test]
+    fn test_checkpoint_truncate_empties_wal() {
+        let (dir, db) = create_test_db();
+        let wal_path = crate::db_sibling(&dir.path().join(DB_NAME), crate::DB_WAL_SUFFIX);
+        db.upsert_file("a.rs", "fn a() {}", 0, 9).unwrap();
+
+        let (busy, log, checkpointed) = db.checkpoint(CheckpointMode::Passive).unwrap();
+        assert_eq!(busy, 0);
+        assert!(log > 0, "writes went to the WAL");
+        assert!((0..=log).contains(&checkpointed));
+        assert!(std::fs::metadata(&wal_path).unwrap().len() > 0);
+
+        let (busy, log, checkpointed) = db.checkpoint(CheckpointMode::Truncate).unwrap();
+        assert_eq!(busy, 0);
+        assert_eq!(log, checkpointed);
+        assert_eq!(std::fs::metadata(&wal_path).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_fresh_db_has_current_schema_version() {
+        let (_dir, db) = create_test_db();
+
+        assert_eq!(db.current_schema_version().unwrap(), SCHEMA_VERSION);
+        assert!(!db.needs_migration());
+        assert_eq!(MIGRATIONS.len(), usize::try_from(SCHEMA_VERSION).unwrap());
+    }
+
+    #[test]
+    fn test_plain_index_usable_without_content_function() {
+        let (dir, db) = create_test_db();
+        db.upsert_file("src/a.rs", "fn plainneedle() {}", 0, 19).unwrap();
+        assert!(!db.compresses_content());
+        drop(db);
+
+        // A client without `ffts_content` (another tool, an older binary) can write and snippet
+        let conn = rusqlite::Connection::open(dir.path().join(DB_NAME)).unwrap();
+        conn.execute(
+            "INSERT INTO files (path, filename, content) VALUES ('b.rs', 'b.rs', 'plainneedle b')",
+            [],
+        )
+        .unwrap();
+        let snippets: Vec<String> = conn
+            .prepare(
+                "SELECT snippet(files_fts, 2, '[', ']', '', 4) FROM files_fts
+                 WHERE files_fts MATCH 'plainneedle' ORDER BY rowid",
+            )
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(snippets, ["fn [plainneedle]() {}", "[plainneedle] b"]);
+    }
+
+    #[test]
+    fn test_enabling_compression_switches_schema_and_sticks() {
+        let (dir, db) = create_test_db();
+        db.upsert_file("src/old.rs", "fn oldneedle() {}", 0, 17).unwrap();
+        drop(db);
+
+        let config = PragmaConfig { compress_content: true, ..Default::default() };
+        let db = Database::open(&dir.path().join(DB_NAME), &config).unwrap();
+        db.init_schema().unwrap();
+        assert!(db.schema_matches_expected().unwrap().is_clean());
+        assert!(db.check_schema().is_complete());
+        assert!(!db.migrate_content_compression().unwrap());
+        drop(db);
+
+        // Reopened without the option: still compressing, rows from both eras searchable
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        assert!(db.compresses_content());
+        db.upsert_file("src/new.rs", "fn newneedle() {}", 0, 17).unwrap();
+        let kinds: Vec<String> = db
+            .conn()
+            .prepare("SELECT typeof(content) FROM files ORDER BY path")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(kinds, ["blob", "text"], "new.rs compressed, old.rs untouched");
+        assert_eq!(db.search("oldneedle", false, 10).unwrap().len(), 1);
+        assert_eq!(db.search("newneedle", false, 10).unwrap().len(), 1);
+        assert!(Database::open_readonly(&dir.path().join(DB_NAME)).unwrap().compresses_content());
+    }
+
+    #[test]
+    fn test_unstamped_db_needs_migration() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("src/a.rs", "fn a() {}", 0, 9).unwrap();
+        db.conn().pragma_update(None, "user_version", 0).unwrap();
+
+        assert!(db.needs_migration());
+        // Current layout, just never stamped: no rows to migrate, only the stamp
+        assert_eq!(db.migrate_schema().unwrap(), 0);
+        assert_eq!(db.current_schema_version().unwrap(), SCHEMA_VERSION);
+        assert!(!db.needs_migration());
+
+        // Rebuilding an already populated index does not duplicate rows
+        assert_eq!(db.rebuild_fts_index().unwrap(), 1);
+        assert_eq!(db.search("a", false, 10).unwrap().len(), 1);
+        assert!(db.check_fts_sync().unwrap().in_sync());
+    }
+
     #[test]
     fn test_check_fts_integrity() {
         let (_dir, db) = create_test_db();
@@ -1060,6 +4050,29 @@ mod tests {
         assert_eq!(results.len(), 5);
     }
 
+    #[test]
+    fn test_locate_matches_reports_line_numbers() {
+        let (_dir, db) = create_test_db();
+
+        let content = "café ☕ intro\r\n日本語のテキスト\nlet Needle = 1;\n\nneedle again";
+        db.upsert_file("src/lib.rs", content, 0, i64::try_from(content.len()).unwrap()).unwrap();
+
+        let matches = db.locate_matches("src/lib.rs", "needle").unwrap();
+        assert_eq!(
+            matches,
+            vec![
+                LineMatch { line: 3, text: "let Needle = 1;".to_string() },
+                LineMatch { line: 5, text: "needle again".to_string() },
+            ]
+        );
+
+        let matches = db.locate_matches("src/lib.rs", "CAFÉ").unwrap();
+        assert_eq!(matches, vec![LineMatch { line: 1, text: "café ☕ intro".to_string() }]);
+
+        assert!(db.locate_matches("src/lib.rs", "absent").unwrap().is_empty());
+        assert!(db.locate_matches("missing.rs", "needle").unwrap().is_empty());
+    }
+
     #[test]
     fn test_search_special_chars() {
         let (_dir, db) = create_test_db();
@@ -1081,6 +4094,133 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[test]
+    fn test_get_file_content_round_trips_unicode() {
+        let (_dir, db) = create_test_db();
+
+        let content = "café 中文 🚀\nline2";
+        db.upsert_file("unicode.rs", content, 0, content.len() as i64).unwrap();
+
+        assert_eq!(db.get_file_content("unicode.rs").unwrap().as_deref(), Some(content));
+        assert_eq!(db.get_file_content("missing.rs").unwrap(), None);
+    }
+
+    #[test]
+    fn test_compressed_content_round_trips_and_stays_searchable() {
+        let dir = tempdir().unwrap();
+        let config = PragmaConfig { compress_content: true, ..Default::default() };
+        let db = Database::open(&dir.path().join("test.db"), &config).unwrap();
+        db.init_schema().unwrap();
+
+        let content = format!("fn zstdneedle() {{}}\n{}café 🚀", "let padding = 1;\n".repeat(200));
+        db.upsert_file("src/big.rs", &content, 0, content.len() as i64).unwrap();
+
+        let (kind, stored_len): (String, i64) = db
+            .conn()
+            .query_row("SELECT typeof(content), length(content) FROM files", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(kind, "blob");
+        assert!((stored_len as usize) < content.len() / 4, "{stored_len} bytes stored");
+
+        assert_eq!(db.get_file_content("src/big.rs").unwrap().as_deref(), Some(content.as_str()));
+        assert_eq!(db.search("zstdneedle", false, 10).unwrap().len(), 1);
+        let snippet = db.snippet("zstdneedle", "src/big.rs", SnippetStyle::PLAIN).unwrap().unwrap();
+        assert!(snippet.contains("zstdneedle"), "{snippet}");
+
+        // The update trigger removes the old plaintext tokens, not the compressed bytes
+        db.upsert_file("src/big.rs", "fn replaced() {}", 0, 16).unwrap();
+        assert!(db.search("zstdneedle", false, 10).unwrap().is_empty());
+        assert_eq!(db.search("replaced", false, 10).unwrap().len(), 1);
+        assert!(db.check_fts_sync().unwrap().in_sync());
+    }
+
+    #[test]
+    fn test_get_file_metadata_reads_back_fields() {
+        let (_dir, db) = create_test_db();
+
+        let before = Utc::now().timestamp();
+        db.upsert_file("src/main.rs", "fn main() {}", 1_700_000_000, 12).unwrap();
+        let after = Utc::now().timestamp();
+
+        let meta = db.get_file_metadata("src/main.rs").unwrap().unwrap();
+        assert_eq!(meta.path, "src/main.rs");
+        assert_eq!(meta.filename, "main.rs");
+        assert_eq!(meta.content_hash, Some(Database::content_hash("fn main() {}")));
+        assert_eq!(meta.mtime, 1_700_000_000);
+        assert_eq!(meta.size, 12);
+        assert!((before..=after).contains(&meta.indexed_at));
+
+        assert_eq!(db.get_file_metadata("missing.rs").unwrap(), None);
+    }
+
+    #[test]
+    fn test_trigram_tokenizer_matches_identifier_substrings() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("user.rs", "fn getUserById(id: u64) {}", 0, 26).unwrap();
+        assert!(db.search("UserBy", false, 10).unwrap().is_empty());
+
+        let dir = tempdir().unwrap();
+        let config = PragmaConfig { tokenizer: Some(TokenizerKind::Trigram), ..Default::default() };
+        let db = Database::open(&dir.path().join(DB_NAME), &config).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("user.rs", "fn getUserById(id: u64) {}", 0, 26).unwrap();
+
+        let results = db.search("UserBy", false, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "user.rs");
+        assert!(db.schema_matches_expected().unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_tokenizer_change_rebuilds_fts_index() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        {
+            let db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+            db.init_schema().unwrap();
+            db.upsert_file("user.rs", "fn getUserById(id: u64) {}", 0, 26).unwrap();
+            assert!(!db.migrate_tokenizer().unwrap());
+        }
+
+        let config = PragmaConfig { tokenizer: Some(TokenizerKind::Trigram), ..Default::default() };
+        let db = Database::open(&db_path, &config).unwrap();
+        assert!(db.migrate_tokenizer().unwrap());
+        assert!(!db.migrate_tokenizer().unwrap());
+
+        let results = db.search("UserBy", false, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(db.get_file_count().unwrap(), 1);
+
+        // Sync triggers keep working against the recreated table
+        db.upsert_file("order.rs", "fn getOrderById() {}", 0, 20).unwrap();
+        assert_eq!(db.search("OrderBy", false, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_stored_tokenizer_kept_without_explicit_choice() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        {
+            let config =
+                PragmaConfig { tokenizer: Some(TokenizerKind::Trigram), ..Default::default() };
+            let db = Database::open(&db_path, &config).unwrap();
+            db.init_schema().unwrap();
+            db.upsert_file("user.rs", "fn getUserById(id: u64) {}", 0, 26).unwrap();
+        }
+
+        let db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        assert_eq!(db.tokenizer(), TokenizerKind::Trigram);
+        db.init_schema().unwrap();
+        assert_eq!(db.search("UserBy", false, 10).unwrap().len(), 1);
+        drop(db);
+
+        let readonly = Database::open_readonly(&db_path).unwrap();
+        assert_eq!(readonly.tokenizer(), TokenizerKind::Trigram);
+        assert!(readonly.schema_matches_expected().unwrap().is_clean());
+    }
+
     #[test]
     fn test_transaction_commit() {
         let dir = tempdir().unwrap();
@@ -1200,6 +4340,39 @@ mod tests {
         assert!(check.missing_objects().is_empty());
     }
 
+    #[test]
+    fn test_schema_matches_expected_after_init() {
+        let (_dir, db) = create_test_db();
+        let report = db.schema_matches_expected().unwrap();
+        assert!(report.is_clean(), "unexpected drift: {report:?}");
+        assert!(report.recommendation().is_none());
+    }
+
+    #[test]
+    fn test_schema_drift_detects_two_column_fts_table() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        // Older layout: FTS table without the filename column
+        db.conn()
+            .execute(
+                "CREATE VIRTUAL TABLE files_fts USING fts5(
+                    path, content, content='files', content_rowid='id'
+                )",
+                [],
+            )
+            .unwrap();
+        db.init_schema().unwrap();
+
+        // Every object exists, so the boolean check misses the drift
+        assert!(db.check_schema().is_complete());
+
+        let report = db.schema_matches_expected().unwrap();
+        let drifted: Vec<&str> = report.mismatched.iter().map(|d| d.object.as_str()).collect();
+        assert_eq!(drifted, vec!["table: files_fts"]);
+        assert!(report.mismatched[0].actual.contains("path, content"));
+        assert!(report.recommendation().is_some());
+    }
+
     #[test]
     fn test_check_schema_incomplete() {
         let dir = tempdir().unwrap();
@@ -1270,6 +4443,32 @@ mod tests {
         assert!(size.unwrap() >= 4096);
     }
 
+    #[test]
+    fn test_vacuum_reclaims_space_after_deletes() {
+        let (dir, db) = create_test_db();
+        let content = "reclaimable content line\n".repeat(200);
+        db.conn().execute_batch("BEGIN").unwrap();
+        for i in 0..300 {
+            db.upsert_file(&format!("src/file_{i}.rs"), &content, 0, 5000).unwrap();
+        }
+        db.conn().execute_batch("COMMIT").unwrap();
+        db.conn().execute("DELETE FROM files", []).unwrap();
+
+        // Left open on purpose: vacuum must commit it first
+        db.conn().execute_batch("BEGIN").unwrap();
+        let before = db.get_db_size_bytes().unwrap();
+
+        let copy = dir.path().join("compact-copy.db");
+        db.vacuum_into(&copy).unwrap();
+        assert!(std::fs::metadata(&copy).unwrap().len() < before);
+        assert!(db.vacuum_into(&copy).is_err(), "VACUUM INTO must not overwrite");
+
+        db.vacuum().unwrap();
+        let after = db.get_db_size_bytes().unwrap();
+        assert!(after < before, "expected {after} < {before}");
+        assert_eq!(db.get_file_count().unwrap(), 0);
+    }
+
     #[test]
     fn test_schema_check_missing_objects() {
         let check = SchemaCheck {
@@ -1281,6 +4480,7 @@ mod tests {
             has_mtime_index: false,
             has_path_index: true,
             has_hash_index: true,
+            has_content_view: true,
         };
 
         let missing = check.missing_objects();
@@ -1290,6 +4490,60 @@ mod tests {
         assert!(missing.contains(&"index: idx_files_mtime"));
     }
 
+    #[test]
+    fn test_explain_query_mentions_fts_table() {
+        let (_dir, db) = create_test_db();
+        db.upsert_file("src/main.rs", "fn main() {}", 0, 12).unwrap();
+
+        let plan = db.explain_query("main", false).unwrap();
+        assert!(plan.contains("files_fts"), "plan should use FTS table: {plan}");
+
+        let plan = db.explain_query("main", true).unwrap();
+        assert!(plan.contains("files_fts"), "plan should use FTS table: {plan}");
+    }
+
+    // ============================================
+    // search_prefix() tests
+    // ============================================
+
+    #[test]
+    fn test_search_prefix_matches_word_starts_only() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("src/config.rs", "fn load() {}", 0, 12).unwrap();
+        db.upsert_file("src/connect.rs", "fn dial() {}", 0, 12).unwrap();
+        db.upsert_file("src/reconnect.rs", "fn retry() {}", 0, 13).unwrap();
+
+        for term in ["con", "con*", "CON"] {
+            let paths: HashSet<String> =
+                db.search_prefix(term, 10).unwrap().into_iter().map(|r| r.path).collect();
+            let expected: HashSet<String> =
+                ["src/config.rs", "src/connect.rs"].iter().map(ToString::to_string).collect();
+            assert_eq!(paths, expected, "{term}");
+        }
+        assert!(db.search_prefix("*", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_prefix_filename_words_before_content() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("notes.txt", "see the introduction chapter", 0, 28).unwrap();
+        db.upsert_file("docs/01-introduction.md", "chapter one", 0, 11).unwrap();
+
+        let results = db.search_prefix("intro", 10).unwrap();
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["docs/01-introduction.md", "notes.txt"]);
+        assert!((results[0].rank - FILENAME_MATCH_RANK).abs() < f64::EPSILON);
+        assert!(results[1].rank > FILENAME_MATCH_RANK);
+
+        assert_eq!(
+            db.search_filename_prefix("intro", 10).unwrap(),
+            vec!["docs/01-introduction.md"]
+        );
+        assert_eq!(db.search_prefix("intro", 1).unwrap().len(), 1);
+    }
+
     // ============================================
     // search_filename_contains() tests
     // ============================================
@@ -1433,4 +4687,50 @@ mod tests {
         let results = db.search_filename_contains("test", 3).unwrap();
         assert_eq!(results.len(), 3);
     }
+
+    #[test]
+    fn test_search_filename_fuzzy_finds_typos() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("src/config.rs", "content", 0, 7).unwrap();
+        db.upsert_file("src/confirm.rs", "content", 0, 7).unwrap();
+        db.upsert_file("src/parser.rs", "content", 0, 7).unwrap();
+
+        // One substitution from the stem "config"; "confirm" is three edits away
+        assert_eq!(db.search_filename_fuzzy("condig", 10, 2).unwrap(), vec!["src/config.rs"]);
+        assert_eq!(
+            db.search_filename_fuzzy("confi", 10, 2).unwrap(),
+            vec!["src/config.rs", "src/confirm.rs"]
+        );
+        assert_eq!(db.search_filename_fuzzy("CONDIG", 1, 1).unwrap(), vec!["src/config.rs"]);
+        assert!(db.search_filename_fuzzy("condig", 10, 0).unwrap().is_empty());
+        assert!(db.search_filename_fuzzy("cf", 10, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_with_highlight_wraps_matched_tokens() {
+        let (_dir, db) = create_test_db();
+
+        db.upsert_file("src/main.rs", "fn main() {\n    run();\n}", 0, 24).unwrap();
+        db.upsert_file("src/lib.rs", "pub fn run() {}", 0, 15).unwrap();
+
+        let results = db.search_with_highlight("main", 10, "[", "]").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "src/main.rs");
+        assert_eq!(results[0].highlighted.as_deref(), Some("fn [main]() {\n    run();\n}"));
+
+        assert_eq!(
+            db.highlight("\"run\"", "src/lib.rs", "<<", ">>").unwrap().as_deref(),
+            Some("pub fn <<run>>() {}")
+        );
+        assert_eq!(db.highlight("\"run\"", "missing.rs", "[", "]").unwrap(), None);
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("condig", "config"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("über", "uber"), 1);
+    }
 }
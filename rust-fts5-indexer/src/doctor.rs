@@ -9,17 +9,36 @@
 //! 3. **JSON output**: `--format json` for CI/automation integration
 //! 4. **Actionable**: Every failure includes specific fix command
 //! 5. **Fast**: No file system walking for basic checks
+//!
+//! With `--fix`, doctor also applies the remediations that are safe to automate
+//! (see [`Doctor::fix`]) and reports each fix with its before/after status.
 
 use serde::Serialize;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
-use crate::cli::OutputFormat;
+use crate::cli::{FailOn, OutputFormat};
 use crate::constants::EXPECTED_APPLICATION_ID;
-use crate::db::Database;
+use crate::db::{Database, PragmaConfig, resolve_stored_path};
+use crate::error::ExitCode;
+use crate::fs_utils::available_space;
+use crate::health::{self, DatabaseHealth};
+use crate::indexer::{IndexerConfig, root_prefixes};
 use crate::init;
-use crate::{DB_NAME, DB_SHM_SUFFIX, DB_WAL_SUFFIX};
+use crate::{DB_NAME, DB_SHM_SUFFIX, DB_WAL_SUFFIX, db_sibling};
+
+/// Maximum indexed paths the stale-entry and orphaned-row checks compare
+/// against the filesystem.
+const FILE_CHECK_SAMPLE: u32 = 1000;
+
+/// A reindex builds a full copy of the database before replacing it, so free
+/// space should be at least this multiple of the current database size.
+const REINDEX_SPACE_FACTOR: u64 = 2;
+
+/// Paths listed as examples in the check details.
+const PATH_EXAMPLES: usize = 5;
 
 /// Check severity levels.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -31,6 +50,31 @@ pub enum Severity {
     Error,
 }
 
+impl Severity {
+    /// Uppercase label used in plain output.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Pass => "PASS",
+            Self::Info => "INFO",
+            Self::Warning => "WARN",
+            Self::Error => "FAIL",
+        }
+    }
+
+    /// Terminal style for this severity in colored plain output.
+    #[must_use]
+    pub const fn style(self) -> anstyle::Style {
+        let style = anstyle::Style::new();
+        match self {
+            Self::Pass => style.fg_color(Some(anstyle::Color::Ansi(anstyle::AnsiColor::Green))),
+            Self::Info => style,
+            Self::Warning => style.fg_color(Some(anstyle::Color::Ansi(anstyle::AnsiColor::Yellow))),
+            Self::Error => style.fg_color(Some(anstyle::Color::Ansi(anstyle::AnsiColor::Red))),
+        }
+    }
+}
+
 /// Result of a single diagnostic check.
 #[derive(Debug, Serialize)]
 pub struct CheckResult {
@@ -43,6 +87,21 @@ pub struct CheckResult {
     pub details: Option<serde_json::Value>,
 }
 
+/// Outcome of one `--fix` remediation.
+#[derive(Debug, Clone, Serialize)]
+pub struct FixResult {
+    /// Name of the check the fix addresses
+    pub check: &'static str,
+    /// What was done (or why nothing was done)
+    pub action: String,
+    /// Check status before the fix
+    pub before: Severity,
+    /// Check status after re-running the checks
+    pub after: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// Summary of all doctor checks.
 #[derive(Debug, Serialize)]
 pub struct DoctorSummary {
@@ -81,6 +140,20 @@ impl DoctorSummary {
     pub const fn has_warnings(&self) -> bool {
         self.warn > 0
     }
+
+    /// Exit code under the given failure policy.
+    ///
+    /// Errors map to `DataErr` (2) and warnings to `Software` (1), unless the
+    /// policy lets that severity pass.
+    #[must_use]
+    pub const fn exit_code(&self, fail_on: FailOn) -> ExitCode {
+        match fail_on {
+            FailOn::Never => ExitCode::Ok,
+            _ if self.has_errors() => ExitCode::DataErr,
+            FailOn::Warn if self.has_warnings() => ExitCode::Software,
+            _ => ExitCode::Ok,
+        }
+    }
 }
 
 /// Doctor diagnostic output (for JSON format).
@@ -91,14 +164,25 @@ pub struct DoctorOutput {
     pub checks: Vec<CheckResult>,
     pub summary: DoctorSummary,
     pub exit_code: u8,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<FixResult>,
 }
 
 /// Doctor diagnostic runner.
 pub struct Doctor<'a> {
     project_dir: &'a Path,
+    db_path: PathBuf,
+    /// Extra index roots whose `@label/...` paths the file checks resolve
+    extra_roots: Vec<PathBuf>,
     verbose: bool,
     checks: Vec<CheckResult>,
+    fixes: Vec<FixResult>,
     exe_name: String,
+    fail_on: FailOn,
+    /// Journal mode the database is expected to use (lowercase)
+    journal_mode: String,
+    /// Color severities in plain output
+    color: bool,
 }
 
 impl<'a> Doctor<'a> {
@@ -112,11 +196,59 @@ impl<'a> Doctor<'a> {
             })
             .unwrap_or_else(|| "ffts-grep".to_string());
 
-        Self { project_dir, verbose, checks: Vec::with_capacity(10), exe_name }
+        Self {
+            project_dir,
+            db_path: project_dir.join(DB_NAME),
+            extra_roots: Vec::new(),
+            verbose,
+            checks: Vec::with_capacity(13),
+            fixes: Vec::new(),
+            exe_name,
+            fail_on: FailOn::default(),
+            journal_mode: "wal".to_string(),
+            color: false,
+        }
+    }
+
+    /// Set the journal mode the database is expected to use (default: WAL).
+    #[must_use]
+    pub fn with_journal_mode(mut self, journal_mode: &str) -> Self {
+        self.journal_mode = journal_mode.to_lowercase();
+        self
+    }
+
+    /// Check the database at `db_path` instead of `DB_NAME` in the project directory.
+    #[must_use]
+    pub fn with_db_path(mut self, db_path: PathBuf) -> Self {
+        self.db_path = db_path;
+        self
+    }
+
+    /// Resolve paths of these extra index roots (`--add-root`) in the file checks;
+    /// paths of roots not listed are skipped.
+    #[must_use]
+    pub fn with_extra_roots(mut self, extra_roots: Vec<PathBuf>) -> Self {
+        self.extra_roots = extra_roots;
+        self
+    }
+
+    /// Set the failure policy reported as `exit_code` in JSON output.
+    #[must_use]
+    pub const fn with_fail_on(mut self, fail_on: FailOn) -> Self {
+        self.fail_on = fail_on;
+        self
+    }
+
+    /// Color severities in plain output: green pass, yellow warn, red fail.
+    #[must_use]
+    pub const fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
     }
 
-    /// Run all diagnostic checks.
+    /// Run all diagnostic checks, replacing the results of any previous run.
     pub fn run(&mut self) -> DoctorSummary {
+        self.checks.clear();
         self.check_database_exists();
         self.check_database_readable();
         self.check_application_id();
@@ -127,6 +259,10 @@ impl<'a> Doctor<'a> {
         self.check_gitignore();
         self.check_binary_available();
         self.check_orphan_wal_files();
+        self.check_stale_entries();
+        self.check_orphaned_rows();
+        self.check_disk_space();
+        self.check_fts_sync();
 
         DoctorSummary::from_checks(&self.checks)
     }
@@ -137,6 +273,112 @@ impl<'a> Doctor<'a> {
         &self.checks
     }
 
+    /// Get the fixes applied by [`fix`](Self::fix).
+    #[must_use]
+    pub fn fixes(&self) -> &[FixResult] {
+        &self.fixes
+    }
+
+    /// Apply the safe remediations for failing checks, then re-run all checks.
+    ///
+    /// Call after [`run`](Self::run). Fixes applied:
+    /// - **Gitignore**: append missing entries via [`init::update_gitignore`]
+    /// - **Orphan WAL files**: delete WAL/SHM files left without a main database
+    /// - **Schema complete**: back up and rebuild a `SchemaInvalid` database
+    ///
+    /// A database with a foreign application ID is never touched; that refusal
+    /// is reported as a fix whose status does not change.
+    pub fn fix(&mut self, config: &PragmaConfig, indexer_config: IndexerConfig) -> DoctorSummary {
+        let failing: Vec<(&'static str, Severity)> = self
+            .checks
+            .iter()
+            .filter(|c| matches!(c.status, Severity::Warning | Severity::Error))
+            .map(|c| (c.name, c.status))
+            .collect();
+        let health = health::check_health_at(&self.db_path);
+        let mut indexer_config = Some(indexer_config);
+
+        let mut attempted = Vec::new();
+        for (name, before) in failing {
+            let outcome = match name {
+                "Gitignore" => match init::update_gitignore_for(self.project_dir, &self.db_path) {
+                    Ok(_) => Ok("Added missing .gitignore entries".to_string()),
+                    Err(e) => Err(("Update .gitignore".to_string(), e.to_string())),
+                },
+                "Orphan WAL files" => self.remove_orphan_wal_files(),
+                "Application ID" if health == DatabaseHealth::WrongApplicationId => Ok(format!(
+                    "Refused: {} was not created by ffts-grep; left untouched",
+                    self.db_name()
+                )),
+                "Schema complete" if health == DatabaseHealth::MigrationNeeded => {
+                    match health::migrate_in_place(&self.db_path, config) {
+                        Ok((migrated, _)) => {
+                            Ok(format!("Migrated schema in place ({migrated} rows kept)"))
+                        }
+                        Err(e) => Err(("Migrate schema".to_string(), e.to_string())),
+                    }
+                }
+                "Schema complete" if health == DatabaseHealth::SchemaInvalid => {
+                    let Some(indexer_config) = indexer_config.take() else { continue };
+                    match health::backup_and_reinit_with_config(
+                        self.project_dir,
+                        config,
+                        indexer_config,
+                        true,
+                    ) {
+                        Ok(stats) => Ok(format!(
+                            "Backed up and rebuilt database ({} files indexed)",
+                            stats.files_indexed
+                        )),
+                        Err(e) => Err(("Rebuild database".to_string(), e.to_string())),
+                    }
+                }
+                _ => continue,
+            };
+            attempted.push((name, before, outcome));
+        }
+
+        let summary = self.run();
+        self.fixes = attempted
+            .into_iter()
+            .map(|(check, before, outcome)| {
+                let after = self
+                    .checks
+                    .iter()
+                    .find(|c| c.name == check)
+                    .map_or(Severity::Pass, |c| c.status);
+                let (action, error) = match outcome {
+                    Ok(action) => (action, None),
+                    Err((action, error)) => (action, Some(error)),
+                };
+                FixResult { check, action, before, after, error }
+            })
+            .collect();
+
+        summary
+    }
+
+    /// Delete WAL/SHM files, provided the main database is still absent.
+    fn remove_orphan_wal_files(&self) -> Result<String, (String, String)> {
+        if self.db_path().exists() {
+            return Err((
+                "Delete orphan WAL files".to_string(),
+                format!("{} exists; WAL files are not orphaned", self.db_name()),
+            ));
+        }
+
+        let mut removed = Vec::new();
+        for suffix in [DB_WAL_SUFFIX, DB_SHM_SUFFIX] {
+            let name = format!("{}{suffix}", self.db_name());
+            match fs::remove_file(db_sibling(&self.db_path, suffix)) {
+                Ok(()) => removed.push(name),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err((format!("Delete {name}"), e.to_string())),
+            }
+        }
+        Ok(format!("Deleted {}", removed.join(", ")))
+    }
+
     /// Output results in plain format.
     ///
     /// # Errors
@@ -150,10 +392,12 @@ impl<'a> Doctor<'a> {
         writeln!(writer)?;
 
         let check_count = self.checks.len();
+        let style =
+            |status: Severity| if self.color { status.style() } else { anstyle::Style::new() };
 
         for (i, check) in self.checks.iter().enumerate() {
             if self.verbose {
-                // Verbose format: [N/10] Check name
+                // Verbose format: [N/14] Check name
                 writeln!(writer, "[{}/{}] {}", i + 1, check_count, check.name)?;
 
                 // Details if present
@@ -166,13 +410,13 @@ impl<'a> Doctor<'a> {
                 }
 
                 // Status
-                let status_symbol = match check.status {
-                    Severity::Pass => "PASS",
-                    Severity::Info => "INFO",
-                    Severity::Warning => "WARN",
-                    Severity::Error => "FAIL",
-                };
-                writeln!(writer, "       {} {}", status_symbol, check.message)?;
+                let style = style(check.status);
+                writeln!(
+                    writer,
+                    "       {style}{}{style:#} {}",
+                    check.status.label(),
+                    check.message
+                )?;
 
                 // Remediation if present
                 if let Some(remediation) = &check.remediation {
@@ -189,7 +433,8 @@ impl<'a> Doctor<'a> {
                     Severity::Error => '\u{2717}', // ✗
                 };
 
-                writeln!(writer, "{} {}", symbol, check.message)?;
+                let style = style(check.status);
+                writeln!(writer, "{style}{symbol}{style:#} {}", check.message)?;
 
                 if let Some(remediation) = &check.remediation {
                     writeln!(writer, "  -> {remediation}")?;
@@ -197,6 +442,24 @@ impl<'a> Doctor<'a> {
             }
         }
 
+        if !self.fixes.is_empty() {
+            writeln!(writer)?;
+            writeln!(writer, "Fixes:")?;
+            for fix in &self.fixes {
+                writeln!(
+                    writer,
+                    "  {}: {} -> {} ({})",
+                    fix.check,
+                    fix.before.label(),
+                    fix.after.label(),
+                    fix.action
+                )?;
+                if let Some(error) = &fix.error {
+                    writeln!(writer, "    error: {error}")?;
+                }
+            }
+        }
+
         // Summary
         writeln!(writer)?;
         let issue_count = summary.fail + summary.warn;
@@ -223,15 +486,7 @@ impl<'a> Doctor<'a> {
         summary: &DoctorSummary,
     ) -> std::io::Result<()> {
         // Exit code follows BSD sysexits(3) convention: 0=OK, 1=WARNING, 2=ERROR
-        // Not a simple bool-to-int conversion - three distinct states
-        #[allow(clippy::bool_to_int_with_if)]
-        let exit_code = if summary.has_errors() {
-            2 // DATAERR
-        } else if summary.has_warnings() {
-            1 // SOFTWARE
-        } else {
-            0 // OK
-        };
+        let exit_code = summary.exit_code(self.fail_on) as u8;
 
         let output = DoctorOutput {
             version: env!("CARGO_PKG_VERSION"),
@@ -239,6 +494,7 @@ impl<'a> Doctor<'a> {
             checks: self.checks.clone(),
             summary: DoctorSummary::from_checks(&self.checks),
             exit_code,
+            fixes: self.fixes.clone(),
         };
 
         let json = serde_json::to_string_pretty(&output).map_err(std::io::Error::other)?;
@@ -259,8 +515,8 @@ impl<'a> Doctor<'a> {
         summary: &DoctorSummary,
     ) -> std::io::Result<()> {
         match format {
-            OutputFormat::Json => self.output_json(writer, summary),
-            OutputFormat::Plain => self.output_plain(writer, summary),
+            OutputFormat::Json | OutputFormat::Ndjson => self.output_json(writer, summary),
+            OutputFormat::Plain | OutputFormat::Grep => self.output_plain(writer, summary),
         }
     }
 
@@ -268,8 +524,18 @@ impl<'a> Doctor<'a> {
     // Individual checks
     // -------------------------------------------------------------------------
 
-    fn db_path(&self) -> std::path::PathBuf {
-        self.project_dir.join(DB_NAME)
+    fn db_path(&self) -> PathBuf {
+        self.db_path.clone()
+    }
+
+    /// `(prefix, dir)` of each index root, for [`resolve_stored_path`].
+    fn root_prefixes(&self) -> Vec<(String, PathBuf)> {
+        root_prefixes(self.project_dir, &self.extra_roots)
+    }
+
+    /// File name of the checked database, for messages.
+    fn db_name(&self) -> String {
+        self.db_path.file_name().unwrap_or_default().to_string_lossy().into_owned()
     }
 
     /// Check 1: Database exists
@@ -282,7 +548,7 @@ impl<'a> Doctor<'a> {
             self.checks.push(CheckResult {
                 name: "Database exists",
                 status: Severity::Pass,
-                message: format!("Database: {} ({})", DB_NAME, format_bytes(size_bytes)),
+                message: format!("Database: {} ({})", self.db_name(), format_bytes(size_bytes)),
                 remediation: None,
                 details: Some(serde_json::json!({
                     "path": db_path.display().to_string(),
@@ -293,7 +559,7 @@ impl<'a> Doctor<'a> {
             self.checks.push(CheckResult {
                 name: "Database exists",
                 status: Severity::Error,
-                message: format!("Database not found: {DB_NAME}"),
+                message: format!("Database not found: {}", self.db_name()),
                 remediation: Some("Run: ffts-grep init".to_string()),
                 details: Some(serde_json::json!({
                     "path": db_path.display().to_string(),
@@ -429,21 +695,7 @@ impl<'a> Doctor<'a> {
 
     /// Check 5: FTS5 integrity
     fn check_fts_integrity(&mut self) {
-        let db_path = self.db_path();
-
-        if !db_path.exists() {
-            return;
-        }
-
-        // Need write access for integrity-check (it's an INSERT command)
-        // Open with regular mode but don't init schema
-        let Ok(db) = Database::open(&db_path, &crate::db::PragmaConfig::default()) else { return };
-
-        // Check schema first to avoid errors on missing FTS table
-        let schema = db.check_schema();
-        if !schema.has_fts_table {
-            return;
-        }
+        let Some(db) = self.open_for_fts_checks() else { return };
 
         if db.check_fts_integrity() {
             self.checks.push(CheckResult {
@@ -464,6 +716,37 @@ impl<'a> Doctor<'a> {
         }
     }
 
+    /// Open our database writable for the FTS5 checks, which are `INSERT` commands.
+    ///
+    /// Returns `None` if the database is missing, foreign, unopenable or has no
+    /// `files_fts` table.
+    fn open_for_fts_checks(&self) -> Option<Database> {
+        let db_path = self.db_path();
+
+        if !db_path.exists() {
+            return None;
+        }
+
+        // Never open a foreign database writable: doing so stamps our application ID
+        let readonly = Database::open_readonly(&db_path).ok()?;
+        if readonly.get_application_id() != Some(EXPECTED_APPLICATION_ID) {
+            return None;
+        }
+
+        // Open with regular mode but don't init schema; keep the file's current
+        // journal mode so diagnosing doesn't convert a DELETE-mode index to WAL
+        let current_mode = readonly.get_journal_mode();
+        drop(readonly);
+        let config = crate::db::PragmaConfig {
+            journal_mode: current_mode.unwrap_or_else(|| self.journal_mode.clone()).to_uppercase(),
+            ..Default::default()
+        };
+        let db = Database::open(&db_path, &config).ok()?;
+
+        // Check schema first to avoid errors on missing FTS table
+        db.check_schema().has_fts_table.then_some(db)
+    }
+
     /// Check 6: Journal mode
     fn check_journal_mode(&mut self) {
         let db_path = self.db_path();
@@ -475,11 +758,11 @@ impl<'a> Doctor<'a> {
         let Ok(db) = Database::open_readonly(&db_path) else { return };
 
         match db.get_journal_mode() {
-            Some(mode) if mode.to_lowercase() == "wal" => {
+            Some(mode) if mode.to_lowercase() == self.journal_mode => {
                 self.checks.push(CheckResult {
                     name: "Journal mode",
                     status: Severity::Pass,
-                    message: "Journal mode: WAL".to_string(),
+                    message: format!("Journal mode: {}", mode.to_uppercase()),
                     remediation: None,
                     details: Some(serde_json::json!({
                         "mode": mode,
@@ -490,13 +773,22 @@ impl<'a> Doctor<'a> {
                 self.checks.push(CheckResult {
                     name: "Journal mode",
                     status: Severity::Warning,
-                    message: format!("Journal mode: {mode} (expected WAL)"),
-                    remediation: Some(
-                        "Database may have been copied without WAL files".to_string(),
+                    message: format!(
+                        "Journal mode: {mode} (expected {})",
+                        self.journal_mode.to_uppercase()
                     ),
+                    remediation: Some(if self.journal_mode == "wal" {
+                        "Database may have been copied without WAL files".to_string()
+                    } else {
+                        format!(
+                            "Run: {} --journal-mode {} index",
+                            self.exe_name,
+                            self.journal_mode.to_uppercase()
+                        )
+                    }),
                     details: Some(serde_json::json!({
                         "mode": mode,
-                        "expected": "wal",
+                        "expected": self.journal_mode,
                     })),
                 });
             }
@@ -559,7 +851,7 @@ impl<'a> Doctor<'a> {
 
     /// Check 8: Gitignore entries
     fn check_gitignore(&mut self) {
-        let missing = init::check_gitignore(self.project_dir);
+        let missing = init::check_gitignore_for(self.project_dir, &self.db_path);
 
         if missing.is_empty() {
             self.checks.push(CheckResult {
@@ -613,11 +905,9 @@ impl<'a> Doctor<'a> {
     /// Check 10: Orphan WAL files
     fn check_orphan_wal_files(&mut self) {
         let db_path = self.db_path();
-        // Construct correct WAL/SHM filenames by appending suffix to DB_NAME
-        let shm_filename = format!("{DB_NAME}{DB_SHM_SUFFIX}");
-        let wal_filename = format!("{DB_NAME}{DB_WAL_SUFFIX}");
-        let shm_path = self.project_dir.join(&shm_filename);
-        let wal_path = self.project_dir.join(&wal_filename);
+        // WAL/SHM filenames are the database filename plus a suffix
+        let shm_path = db_sibling(&db_path, DB_SHM_SUFFIX);
+        let wal_path = db_sibling(&db_path, DB_WAL_SUFFIX);
 
         let db_exists = db_path.exists();
         let shm_exists = shm_path.exists();
@@ -626,10 +916,10 @@ impl<'a> Doctor<'a> {
         if !db_exists && (shm_exists || wal_exists) {
             let mut orphans = Vec::new();
             if shm_exists {
-                orphans.push(format!("{DB_NAME}{DB_SHM_SUFFIX}"));
+                orphans.push(format!("{}{DB_SHM_SUFFIX}", self.db_name()));
             }
             if wal_exists {
-                orphans.push(format!("{DB_NAME}{DB_WAL_SUFFIX}"));
+                orphans.push(format!("{}{DB_WAL_SUFFIX}", self.db_name()));
             }
 
             self.checks.push(CheckResult {
@@ -654,6 +944,221 @@ impl<'a> Doctor<'a> {
             });
         }
     }
+
+    /// Check 11: Stale index entries
+    ///
+    /// Samples indexed paths and compares the stored mtime with the file on disk.
+    /// Files that no longer exist are skipped (that is what `prune` is for).
+    fn check_stale_entries(&mut self) {
+        let db_path = self.db_path();
+
+        if !db_path.exists() {
+            return;
+        }
+
+        let Ok(db) = Database::open_readonly(&db_path) else { return };
+        if db.get_application_id() != Some(EXPECTED_APPLICATION_ID) {
+            return;
+        }
+        let Ok(entries) = db.get_file_mtimes(FILE_CHECK_SAMPLE) else { return };
+
+        let roots = self.root_prefixes();
+        let mut sampled = 0usize;
+        let mut stale = Vec::new();
+        for (path, stored_mtime) in entries {
+            let Some(disk_path) = resolve_stored_path(&roots, &path) else { continue };
+            let Ok(metadata) = fs::metadata(disk_path) else { continue };
+            sampled += 1;
+            let disk_mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .and_then(|d| i64::try_from(d.as_secs()).ok());
+            if disk_mtime.is_some_and(|mtime| mtime != stored_mtime) {
+                stale.push(path);
+            }
+        }
+
+        if stale.is_empty() {
+            self.checks.push(CheckResult {
+                name: "Stale entries",
+                status: Severity::Pass,
+                message: format!("Index up to date ({sampled} files sampled)"),
+                remediation: None,
+                details: Some(serde_json::json!({
+                    "sampled": sampled,
+                    "stale": 0,
+                })),
+            });
+        } else {
+            self.checks.push(CheckResult {
+                name: "Stale entries",
+                status: Severity::Warning,
+                message: format!(
+                    "{} of {sampled} sampled files changed since indexing",
+                    stale.len()
+                ),
+                remediation: Some(format!("Run: {} index --reindex", self.exe_name)),
+                details: Some(serde_json::json!({
+                    "sampled": sampled,
+                    "stale": stale.len(),
+                    "examples": &stale[..stale.len().min(PATH_EXAMPLES)],
+                })),
+            });
+        }
+    }
+
+    /// Check 12: Orphaned rows
+    ///
+    /// Counts indexed paths (up to the sample cap) whose files no longer exist.
+    fn check_orphaned_rows(&mut self) {
+        let db_path = self.db_path();
+
+        if !db_path.exists() {
+            return;
+        }
+
+        let Ok(db) = Database::open_readonly(&db_path) else { return };
+        if db.get_application_id() != Some(EXPECTED_APPLICATION_ID) {
+            return;
+        }
+        let Ok(paths) = db.get_all_files(FILE_CHECK_SAMPLE) else { return };
+
+        // Paths of roots not configured here cannot be checked
+        let roots = self.root_prefixes();
+        let resolved: Vec<(String, PathBuf)> = paths
+            .into_iter()
+            .filter_map(|path| resolve_stored_path(&roots, &path).map(|disk| (path, disk)))
+            .collect();
+        let scanned = resolved.len();
+        let orphaned: Vec<String> =
+            resolved.into_iter().filter(|(_, disk)| !disk.exists()).map(|(path, _)| path).collect();
+
+        if orphaned.is_empty() {
+            self.checks.push(CheckResult {
+                name: "Orphaned rows",
+                status: Severity::Pass,
+                message: format!("No orphaned rows ({scanned} files scanned)"),
+                remediation: None,
+                details: Some(serde_json::json!({
+                    "scanned": scanned,
+                    "orphaned": 0,
+                })),
+            });
+        } else {
+            self.checks.push(CheckResult {
+                name: "Orphaned rows",
+                status: Severity::Warning,
+                message: format!(
+                    "{} of {scanned} indexed files no longer exist on disk",
+                    orphaned.len()
+                ),
+                remediation: Some(format!("Run: {} prune", self.exe_name)),
+                details: Some(serde_json::json!({
+                    "scanned": scanned,
+                    "orphaned": orphaned.len(),
+                    "examples": &orphaned[..orphaned.len().min(PATH_EXAMPLES)],
+                })),
+            });
+        }
+    }
+
+    /// Check 13: Disk space for a reindex
+    fn check_disk_space(&mut self) {
+        let db_path = self.db_path();
+
+        if !db_path.exists() {
+            return;
+        }
+
+        let Ok(db) = Database::open_readonly(&db_path) else { return };
+        let Some(db_size) = db.get_db_size_bytes() else { return };
+        let available = available_space(self.project_dir).ok();
+        let required = db_size.saturating_mul(REINDEX_SPACE_FACTOR);
+        let details = serde_json::json!({
+            "db_size_bytes": db_size,
+            "available_bytes": available,
+            "required_bytes": required,
+        });
+
+        let check = match (disk_space_status(db_size, available), available) {
+            (Severity::Warning, Some(available)) => CheckResult {
+                name: "Disk space",
+                status: Severity::Warning,
+                message: format!(
+                    "Low disk space: {} free, a reindex needs about {}",
+                    format_bytes(available),
+                    format_bytes(required)
+                ),
+                remediation: Some("Free disk space before running index --reindex".to_string()),
+                details: Some(details),
+            },
+            (_, Some(available)) => CheckResult {
+                name: "Disk space",
+                status: Severity::Pass,
+                message: format!("Disk space: {} free", format_bytes(available)),
+                remediation: None,
+                details: Some(details),
+            },
+            (_, None) => CheckResult {
+                name: "Disk space",
+                status: Severity::Info,
+                message: "Cannot determine free disk space".to_string(),
+                remediation: None,
+                details: Some(details),
+            },
+        };
+        self.checks.push(check);
+    }
+
+    /// Check 14: FTS5 index in sync with the files table
+    ///
+    /// Catches rows the triggers never indexed (e.g. after a migration), which the
+    /// plain integrity check does not see.
+    fn check_fts_sync(&mut self) {
+        let Some(db) = self.open_for_fts_checks() else { return };
+        let Ok(sync) = db.check_fts_sync() else { return };
+        let details = serde_json::json!({
+            "files": sync.files,
+            "indexed": sync.indexed,
+            "consistent": sync.consistent,
+        });
+
+        if sync.in_sync() {
+            self.checks.push(CheckResult {
+                name: "FTS sync",
+                status: Severity::Pass,
+                message: format!("FTS5 index covers all {} files", sync.files),
+                remediation: None,
+                details: Some(details),
+            });
+        } else {
+            self.checks.push(CheckResult {
+                name: "FTS sync",
+                status: Severity::Warning,
+                message: format!(
+                    "FTS5 index out of sync: {} files, {} indexed{}",
+                    sync.files,
+                    sync.indexed,
+                    if sync.consistent { "" } else { ", content mismatch" }
+                ),
+                remediation: Some(format!("Run: {} index --reindex", self.exe_name)),
+                details: Some(details),
+            });
+        }
+    }
+}
+
+/// Severity of the disk-space check: a reindex needs room for a second copy of
+/// the database, so less than twice its size free is a warning.
+const fn disk_space_status(db_size: u64, available: Option<u64>) -> Severity {
+    match available {
+        None => Severity::Info,
+        Some(available) if available < db_size.saturating_mul(REINDEX_SPACE_FACTOR) => {
+            Severity::Warning
+        }
+        Some(_) => Severity::Pass,
+    }
 }
 
 /// Format bytes as human-readable string.
@@ -735,6 +1240,43 @@ mod tests {
         assert!(summary.has_warnings());
     }
 
+    #[test]
+    fn test_exit_code_fail_on_policy() {
+        let warnings_only = DoctorSummary { pass: 3, info: 0, warn: 1, fail: 0 };
+        assert_eq!(warnings_only.exit_code(FailOn::Warn), ExitCode::Software);
+        assert_eq!(warnings_only.exit_code(FailOn::Error), ExitCode::Ok);
+        assert_eq!(warnings_only.exit_code(FailOn::Never), ExitCode::Ok);
+
+        let with_errors = DoctorSummary { pass: 1, info: 0, warn: 1, fail: 1 };
+        assert_eq!(with_errors.exit_code(FailOn::Warn), ExitCode::DataErr);
+        assert_eq!(with_errors.exit_code(FailOn::Error), ExitCode::DataErr);
+        assert_eq!(with_errors.exit_code(FailOn::Never), ExitCode::Ok);
+    }
+
+    #[test]
+    fn test_doctor_fail_on_database_with_warnings() {
+        let dir = tempdir().unwrap();
+        let db =
+            Database::open(&dir.path().join(DB_NAME), &crate::db::PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("test.rs", "content", 0, 7).unwrap();
+        drop(db);
+
+        // No .gitignore entries => warning, but no errors
+        for (fail_on, expected) in [(FailOn::Warn, 1), (FailOn::Error, 0), (FailOn::Never, 0)] {
+            let mut doctor = Doctor::new(dir.path(), false).with_fail_on(fail_on);
+            let summary = doctor.run();
+            assert!(summary.has_warnings());
+            assert!(!summary.has_errors());
+            assert_eq!(summary.exit_code(fail_on) as u8, expected);
+
+            let mut output = Vec::new();
+            doctor.output_json(&mut output, &summary).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+            assert_eq!(json["exit_code"], expected);
+        }
+    }
+
     #[test]
     fn test_doctor_no_database() {
         let dir = tempdir().unwrap();
@@ -782,6 +1324,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_doctor_accepts_configured_journal_mode() {
+        use crate::DB_NAME;
+        let dir = tempdir().unwrap();
+        let config =
+            crate::db::PragmaConfig { journal_mode: "DELETE".to_string(), ..Default::default() };
+        let db = Database::open(&dir.path().join(DB_NAME), &config).unwrap();
+        db.init_schema().unwrap();
+        drop(db);
+
+        let journal_check = |doctor: &Doctor<'_>| {
+            doctor.checks().iter().find(|c| c.name == "Journal mode").unwrap().status
+        };
+
+        let mut doctor = Doctor::new(dir.path(), false).with_journal_mode("DELETE");
+        let summary = doctor.run();
+        assert!(!summary.has_errors());
+        assert_eq!(journal_check(&doctor), Severity::Pass);
+
+        // Default expectation is still WAL
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.run();
+        assert_eq!(journal_check(&doctor), Severity::Warning);
+    }
+
     #[test]
     fn test_doctor_verbose_output() {
         let dir = tempdir().unwrap();
@@ -823,6 +1390,209 @@ mod tests {
         assert_eq!(format_bytes(1024 * 1024 * 1024), "1.0 GB");
     }
 
+    #[test]
+    fn test_fix_refuses_foreign_database() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch("PRAGMA application_id = 42; CREATE TABLE other (x);").unwrap();
+        drop(conn);
+
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.run();
+        doctor.fix(&crate::db::PragmaConfig::default(), IndexerConfig::default());
+
+        let db = Database::open_readonly(&db_path).unwrap();
+        assert_eq!(db.get_application_id(), Some(42));
+        let fix = doctor.fixes().iter().find(|f| f.check == "Application ID").unwrap();
+        assert!(fix.action.starts_with("Refused"));
+        assert_eq!(fix.after, Severity::Error);
+        assert!(doctor.fixes().iter().all(|f| f.check != "Schema complete"));
+    }
+
+    #[test]
+    fn test_check_stale_entries_warns_after_modification() {
+        use crate::indexer::Indexer;
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("fresh.rs"), "fn fresh() {}").unwrap();
+        fs::write(dir.path().join("edited.rs"), "fn edited() {}").unwrap();
+        fs::write(dir.path().join("deleted.rs"), "fn deleted() {}").unwrap();
+
+        let db =
+            Database::open(&dir.path().join(DB_NAME), &crate::db::PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        let mut indexer = Indexer::new(dir.path(), db, IndexerConfig::default());
+        indexer.index_directory().unwrap();
+        drop(indexer);
+
+        let stale_check = |doctor: &Doctor<'_>| {
+            doctor.checks().iter().find(|c| c.name == "Stale entries").cloned().unwrap()
+        };
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.check_stale_entries();
+        assert_eq!(stale_check(&doctor).status, Severity::Pass);
+
+        let edited = fs::File::options().write(true).open(dir.path().join("edited.rs")).unwrap();
+        edited
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(3600))
+            .unwrap();
+        drop(edited);
+        fs::remove_file(dir.path().join("deleted.rs")).unwrap();
+
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.check_stale_entries();
+        let check = stale_check(&doctor);
+        assert_eq!(check.status, Severity::Warning);
+        assert!(check.remediation.unwrap().ends_with("index --reindex"));
+        let details = check.details.unwrap();
+        assert_eq!(details["stale"], 1);
+        assert_eq!(details["sampled"], 2, "Deleted files are not sampled");
+        assert_eq!(details["examples"][0], "edited.rs");
+    }
+
+    #[test]
+    fn test_check_orphaned_rows_counts_deleted_files() {
+        use crate::indexer::Indexer;
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("kept.rs"), "fn kept() {}").unwrap();
+        fs::write(dir.path().join("gone.rs"), "fn gone() {}").unwrap();
+
+        let db =
+            Database::open(&dir.path().join(DB_NAME), &crate::db::PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        let mut indexer = Indexer::new(dir.path(), db, IndexerConfig::default());
+        indexer.index_directory().unwrap();
+        drop(indexer);
+        fs::remove_file(dir.path().join("gone.rs")).unwrap();
+
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.check_orphaned_rows();
+        let check = doctor.checks().iter().find(|c| c.name == "Orphaned rows").unwrap();
+        assert_eq!(check.status, Severity::Warning);
+        assert!(check.remediation.as_deref().unwrap().ends_with(" prune"));
+        let details = check.details.as_ref().unwrap();
+        assert_eq!(details["orphaned"], 1);
+        assert_eq!(details["scanned"], 2);
+        assert_eq!(details["examples"][0], "gone.rs");
+    }
+
+    /// Index `project` plus the extra root `other`, whose paths are stored as `@other/...`.
+    fn index_with_extra_root(project: &Path, other: &Path) {
+        use crate::indexer::Indexer;
+        let db =
+            Database::open(&project.join(DB_NAME), &crate::db::PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        let config = IndexerConfig { extra_roots: vec![other.to_path_buf()], ..Default::default() };
+        let mut indexer = Indexer::new(project, db, config);
+        indexer.index_directory().unwrap();
+    }
+
+    #[test]
+    fn test_check_stale_entries_resolves_extra_roots() {
+        let project = tempdir().unwrap();
+        let parent = tempdir().unwrap();
+        let other = parent.path().join("other");
+        fs::create_dir(&other).unwrap();
+        fs::write(project.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(other.join("c.md"), "# c").unwrap();
+        index_with_extra_root(project.path(), &other);
+
+        let edited = fs::File::options().write(true).open(other.join("c.md")).unwrap();
+        edited
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(3600))
+            .unwrap();
+        drop(edited);
+
+        let mut doctor = Doctor::new(project.path(), false).with_extra_roots(vec![other]);
+        doctor.check_stale_entries();
+        let check = doctor.checks().iter().find(|c| c.name == "Stale entries").unwrap();
+        assert_eq!(check.status, Severity::Warning);
+        let details = check.details.as_ref().unwrap();
+        assert_eq!(details["sampled"], 2);
+        assert_eq!(details["stale"], 1);
+        assert_eq!(details["examples"][0], "@other/c.md");
+    }
+
+    #[test]
+    fn test_check_orphaned_rows_resolves_extra_roots() {
+        let project = tempdir().unwrap();
+        let parent = tempdir().unwrap();
+        let other = parent.path().join("other");
+        fs::create_dir(&other).unwrap();
+        fs::write(project.path().join("a.rs"), "fn a() {}").unwrap();
+        fs::write(other.join("c.md"), "# c").unwrap();
+        fs::write(other.join("gone.md"), "# gone").unwrap();
+        index_with_extra_root(project.path(), &other);
+        fs::remove_file(other.join("gone.md")).unwrap();
+
+        let orphan_check = |doctor: &Doctor<'_>| {
+            doctor.checks().iter().find(|c| c.name == "Orphaned rows").cloned().unwrap()
+        };
+        let mut doctor = Doctor::new(project.path(), false).with_extra_roots(vec![other]);
+        doctor.check_orphaned_rows();
+        let check = orphan_check(&doctor);
+        assert_eq!(check.status, Severity::Warning);
+        let details = check.details.unwrap();
+        assert_eq!(details["scanned"], 3);
+        assert_eq!(details["orphaned"], 1);
+        assert_eq!(details["examples"][0], "@other/gone.md");
+
+        // Without the extra root its paths cannot be checked, and are not reported
+        let mut doctor = Doctor::new(project.path(), false);
+        doctor.check_orphaned_rows();
+        let check = orphan_check(&doctor);
+        assert_eq!(check.status, Severity::Pass);
+        assert_eq!(check.details.unwrap()["scanned"], 1);
+    }
+
+    #[test]
+    fn test_check_fts_sync_detects_missing_fts_row() {
+        let dir = tempdir().unwrap();
+        let db =
+            Database::open(&dir.path().join(DB_NAME), &crate::db::PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("a.rs", "fn a() {}", 0, 9).unwrap();
+        db.upsert_file("b.rs", "fn b() {}", 0, 9).unwrap();
+
+        let sync_check = |doctor: &Doctor<'_>| {
+            doctor.checks().iter().find(|c| c.name == "FTS sync").cloned().unwrap()
+        };
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.check_fts_sync();
+        assert_eq!(sync_check(&doctor).status, Severity::Pass);
+
+        // Drop b.rs from the index only, as if its insert trigger never ran
+        db.conn()
+            .execute(
+                "INSERT INTO files_fts(files_fts, rowid, filename, path, content)
+                 SELECT 'delete', id, filename, path, content FROM files WHERE path = 'b.rs'",
+                [],
+            )
+            .unwrap();
+        drop(db);
+
+        let mut doctor = Doctor::new(dir.path(), false);
+        doctor.check_fts_sync();
+        let check = sync_check(&doctor);
+        assert_eq!(check.status, Severity::Warning);
+        assert!(check.remediation.unwrap().ends_with("index --reindex"));
+        let details = check.details.unwrap();
+        assert_eq!(details["files"], 2);
+        assert_eq!(details["indexed"], 1);
+        assert_eq!(details["consistent"], false);
+    }
+
+    #[test]
+    fn test_disk_space_status() {
+        let mb = 1024 * 1024;
+        assert_eq!(disk_space_status(10 * mb, Some(100 * mb)), Severity::Pass);
+        assert_eq!(disk_space_status(10 * mb, Some(20 * mb)), Severity::Pass);
+        assert_eq!(disk_space_status(10 * mb, Some(20 * mb - 1)), Severity::Warning);
+        assert_eq!(disk_space_status(10 * mb, Some(0)), Severity::Warning);
+        assert_eq!(disk_space_status(u64::MAX, Some(u64::MAX)), Severity::Pass);
+        assert_eq!(disk_space_status(10 * mb, None), Severity::Info);
+    }
+
     #[test]
     fn test_check_orphan_wal_files() {
         use crate::{DB_NAME, DB_WAL_SUFFIX};
@@ -0,0 +1,381 @@
+//! Portable JSON dumps of the index (`ffts-grep export` / `ffts-grep import`).
+//!
+//! A dump holds every indexed file as a [`FileRecord`] (path, filename, content
+//! hash, mtime, size and content), independent of the `SQLite` file format, so it
+//! can be kept as a backup or moved between versions.
+//!
+//! Both directions stream: export writes one record at a time from a prepared
+//! statement, import reads one record at a time (JSON arrays included).
+
+use std::fmt;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path};
+
+use serde::de::{self, Deserializer as _, SeqAccess, Visitor};
+
+use crate::db::{Database, FileRecord, PragmaConfig};
+use crate::error::{IndexerError, Result};
+use crate::indexer::install_database;
+use crate::{DB_TMP_SUFFIX, db_sibling};
+
+/// Records written per transaction during import.
+const IMPORT_BATCH_SIZE: usize = 1_000;
+
+/// Layout of a dump.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// One JSON array of records
+    #[default]
+    Json,
+    /// One JSON record per line (JSON Lines)
+    Ndjson,
+}
+
+/// Write every indexed file to `out` as `format`, streaming one record at a time.
+///
+/// Returns the number of records written.
+///
+/// # Errors
+/// Returns `IndexerError` if reading the index, serializing, or writing fails.
+pub fn export_index(db: &Database, out: &mut impl Write, format: DumpFormat) -> Result<usize> {
+    if format == DumpFormat::Json {
+        out.write_all(b"[")?;
+    }
+
+    let mut first = true;
+    let count = db.for_each_file(|record| {
+        if format == DumpFormat::Json {
+            out.write_all(if first { b"\n" } else { b",\n" })?;
+            first = false;
+        }
+        serde_json::to_writer(&mut *out, &record)?;
+        if format == DumpFormat::Ndjson {
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    })?;
+
+    if format == DumpFormat::Json {
+        out.write_all(b"\n]\n")?;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+/// Outcome of an import.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ImportStats {
+    /// Records written to the index
+    pub imported: usize,
+    /// Malformed or invalid records that were reported and skipped
+    pub skipped: usize,
+}
+
+/// Read a dump (JSON array or JSON Lines, detected from the first byte) into `db`.
+///
+/// Each record is validated: the path must be relative without `..`, the size
+/// non-negative, and a stored `content_hash` must match the content. Invalid
+/// records and malformed lines are logged and counted in
+/// [`ImportStats::skipped`]; they never abort the import. `filename` is derived
+/// from the path again, as during indexing.
+///
+/// Writes are batched in transactions of [`IMPORT_BATCH_SIZE`] records.
+///
+/// # Errors
+/// Returns `IndexerError` if reading the input fails, a JSON array is not
+/// well-formed, or a database write fails.
+pub fn import_index(db: &Database, mut input: impl BufRead) -> Result<ImportStats> {
+    let mut importer = Importer { db, stats: ImportStats::default(), pending: 0 };
+    db.conn().execute("BEGIN IMMEDIATE", []).map_err(|e| IndexerError::Database { source: e })?;
+
+    let result = match first_non_whitespace(&mut input)? {
+        Some(b'[') => importer.read_array(input),
+        Some(_) => importer.read_lines(input),
+        None => Ok(()),
+    };
+
+    match result {
+        Ok(()) => {
+            db.conn().execute("COMMIT", []).map_err(|e| IndexerError::Database { source: e })?;
+            Ok(importer.stats)
+        }
+        Err(e) => {
+            let _ = db.conn().execute("ROLLBACK", []);
+            Err(e)
+        }
+    }
+}
+
+/// Build a new index at `db_path` from a dump and atomically install it.
+///
+/// The database is written to a [`DB_TMP_SUFFIX`] sibling first and only replaces
+/// `db_path` once the import succeeded, so a failed import leaves any existing
+/// index untouched. Callers decide whether replacing one is allowed.
+///
+/// # Errors
+/// Returns `IndexerError` if creating the database, the import, or the final
+/// rename fails.
+pub fn import_into_project(
+    db_path: &Path,
+    config: &PragmaConfig,
+    input: impl BufRead,
+) -> Result<ImportStats> {
+    let tmp_path = db_sibling(db_path, DB_TMP_SUFFIX);
+    let _ = fs::remove_file(&tmp_path);
+
+    let db = Database::open(&tmp_path, config)?;
+    db.init_schema()?;
+    let stats = match import_index(&db, input) {
+        Ok(stats) => stats,
+        Err(e) => {
+            drop(db);
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+
+    install_database(db, &tmp_path, db_path, config)?;
+    Ok(stats)
+}
+
+/// Skip leading whitespace and return (without consuming) the next byte.
+fn first_non_whitespace(input: &mut impl BufRead) -> Result<Option<u8>> {
+    loop {
+        let buf = input.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        if let Some(pos) = buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            let byte = buf[pos];
+            input.consume(pos);
+            return Ok(Some(byte));
+        }
+        let len = buf.len();
+        input.consume(len);
+    }
+}
+
+struct Importer<'a> {
+    db: &'a Database,
+    stats: ImportStats,
+    /// Records written since the last commit
+    pending: usize,
+}
+
+impl Importer<'_> {
+    /// JSON Lines: one record per line; blank lines are ignored.
+    fn read_lines(&mut self, mut input: impl BufRead) -> Result<()> {
+        let mut line = Vec::new();
+        let mut line_no = 0;
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                return Ok(());
+            }
+            line_no += 1;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice::<FileRecord>(&line) {
+                Ok(record) => self.import(record, &format!("line {line_no}"))?,
+                Err(e) => self.skip(&format!("line {line_no}"), &e.to_string()),
+            }
+        }
+    }
+
+    /// JSON array, streamed element by element.
+    fn read_array(&mut self, input: impl BufRead) -> Result<()> {
+        let mut de = serde_json::Deserializer::from_reader(input);
+        let mut failure = None;
+        let parsed = de.deserialize_seq(ArrayVisitor { importer: self, failure: &mut failure });
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        parsed?;
+        de.end()?;
+        Ok(())
+    }
+
+    /// Validate and write one record, committing every [`IMPORT_BATCH_SIZE`] records.
+    fn import(&mut self, record: FileRecord, location: &str) -> Result<()> {
+        if let Err(reason) = validate_record(&record) {
+            self.skip(location, &reason);
+            return Ok(());
+        }
+
+        self.db.upsert_file(&record.path, &record.content, record.mtime, record.size)?;
+        self.stats.imported += 1;
+        self.pending += 1;
+
+        if self.pending >= IMPORT_BATCH_SIZE {
+            let conn = self.db.conn();
+            conn.execute("COMMIT", []).map_err(|e| IndexerError::Database { source: e })?;
+            conn.execute("BEGIN IMMEDIATE", [])
+                .map_err(|e| IndexerError::Database { source: e })?;
+            self.pending = 0;
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, location: &str, reason: &str) {
+        tracing::warn!(record = location, reason, "Skipping invalid import record");
+        self.stats.skipped += 1;
+    }
+}
+
+/// Check a record before it is written (see [`import_index`]).
+fn validate_record(record: &FileRecord) -> std::result::Result<(), String> {
+    let path = Path::new(&record.path);
+    if record.path.is_empty() {
+        return Err("empty path".to_string());
+    }
+    if !path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+        return Err(format!("path '{}' is not relative to the project root", record.path));
+    }
+    if record.size < 0 {
+        return Err(format!("negative size {}", record.size));
+    }
+    if let Some(hash) = &record.content_hash {
+        if *hash != Database::content_hash(&record.content) {
+            return Err(format!("content_hash does not match the content of '{}'", record.path));
+        }
+    }
+    Ok(())
+}
+
+/// Feeds the elements of a JSON array to an [`Importer`] without collecting them.
+///
+/// Elements are read as untyped values first, so one record with wrong fields is
+/// skipped instead of failing the whole array. Database errors are handed back
+/// through `failure`, since a visitor can only return serde errors.
+struct ArrayVisitor<'i, 'a> {
+    importer: &'i mut Importer<'a>,
+    failure: &'i mut Option<IndexerError>,
+}
+
+impl<'de> Visitor<'de> for ArrayVisitor<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an array of file records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        let mut index = 0;
+        while let Some(value) = seq.next_element::<serde_json::Value>()? {
+            let location = format!("element {index}");
+            index += 1;
+            let outcome = match serde_json::from_value::<FileRecord>(value) {
+                Ok(record) => self.importer.import(record, &location),
+                Err(e) => {
+                    self.importer.skip(&location, &e.to_string());
+                    Ok(())
+                }
+            };
+            if let Err(e) = outcome {
+                *self.failure = Some(e);
+                return Err(de::Error::custom("import aborted"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DB_NAME;
+    use crate::db::{FileRecord, PragmaConfig};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_export_formats_round_trip_through_serde() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("b.rs", "fn b() {}\n\"quoted\" \u{1F600}", 20, 26).unwrap();
+        db.upsert_file("a.md", "# A", 10, 3).unwrap();
+
+        let mut json = Vec::new();
+        assert_eq!(export_index(&db, &mut json, DumpFormat::Json).unwrap(), 2);
+        let records: Vec<FileRecord> = serde_json::from_slice(&json).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].path, "b.rs");
+        assert_eq!(records[0].content, "fn b() {}\n\"quoted\" \u{1F600}");
+        assert_eq!(
+            records[0].content_hash.as_deref(),
+            Some(&*Database::content_hash(&records[0].content))
+        );
+
+        let mut ndjson = Vec::new();
+        assert_eq!(export_index(&db, &mut ndjson, DumpFormat::Ndjson).unwrap(), 2);
+        let lines: Vec<FileRecord> = String::from_utf8(ndjson)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines, records);
+    }
+
+    #[test]
+    fn test_import_skips_malformed_lines() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        let good = r#"{"path":"src/a.rs","filename":"a.rs","content_hash":null,"mtime":1,"size":9,"content":"fn a() {}"}"#;
+        let input = [
+            good,
+            "",
+            "{not json",
+            r#"{"path":"src/b.rs"}"#,
+            r#"{"path":"../escape.rs","filename":"x","content_hash":null,"mtime":1,"size":1,"content":"x"}"#,
+            r#"{"path":"c.rs","filename":"c.rs","content_hash":"0000","mtime":1,"size":1,"content":"c"}"#,
+        ]
+        .join("\n");
+
+        let stats = import_index(&db, input.as_bytes()).unwrap();
+        assert_eq!(stats, ImportStats { imported: 1, skipped: 4 });
+        assert_eq!(db.get_file_count().unwrap(), 1);
+        assert_eq!(db.get_file_content("src/a.rs").unwrap().as_deref(), Some("fn a() {}"));
+    }
+
+    #[test]
+    fn test_import_array_round_trips_export() {
+        let dir = tempdir().unwrap();
+        let source = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        source.init_schema().unwrap();
+        source.upsert_file("a.rs", "fn a() {}", 10, 9).unwrap();
+        source.upsert_file("docs/b.md", "# B \u{e9}", 20, 6).unwrap();
+        let mut dump = Vec::new();
+        export_index(&source, &mut dump, DumpFormat::Json).unwrap();
+
+        let target = Database::open(&dir.path().join("copy.db"), &PragmaConfig::default()).unwrap();
+        target.init_schema().unwrap();
+        let stats = import_index(&target, dump.as_slice()).unwrap();
+        assert_eq!(stats, ImportStats { imported: 2, skipped: 0 });
+        assert_eq!(target.get_file_metadata("docs/b.md").unwrap().unwrap().mtime, 20);
+
+        // A well-formed array with one bad element still imports the rest
+        let mixed = r#"[{"path":"x.rs","filename":"x.rs","content_hash":null,"mtime":1,"size":1,"content":"x"}, 42]"#;
+        let stats = import_index(&target, mixed.as_bytes()).unwrap();
+        assert_eq!(stats, ImportStats { imported: 1, skipped: 1 });
+
+        // A truncated array is a hard error and writes nothing
+        assert!(import_index(&target, &b"[{\"path\""[..]).is_err());
+        assert_eq!(target.get_file_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_export_empty_index_is_valid_json() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        let mut json = Vec::new();
+        assert_eq!(export_index(&db, &mut json, DumpFormat::Json).unwrap(), 0);
+        let records: Vec<FileRecord> = serde_json::from_slice(&json).unwrap();
+        assert!(records.is_empty());
+    }
+}
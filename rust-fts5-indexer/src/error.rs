@@ -32,6 +32,10 @@ pub enum IndexerError {
     #[error("invalid UTF-8 in file: {path}")]
     InvalidUtf8 { path: String },
 
+    /// File content looks binary (see [`is_probably_binary`](crate::indexer::is_probably_binary))
+    #[error("binary content in file: {path}")]
+    BinaryContent { path: String },
+
     /// Gitignore parsing error
     #[error("gitignore parse error in '{path}': {source}")]
     GitignoreParse {
@@ -44,6 +48,10 @@ pub enum IndexerError {
     #[error("invalid {field}: {value} ({reason})")]
     ConfigInvalid { field: String, value: String, reason: String },
 
+    /// Config file (`.ffts.toml`) could not be parsed
+    #[error("config file '{path}': {reason}")]
+    ConfigFile { path: String, reason: String },
+
     /// FTS5 index integrity check failed
     #[error("index corrupted, run --reindex")]
     IndexCorrupted,
@@ -1,7 +1,55 @@
+use std::borrow::Cow;
 use std::fs;
 use std::io;
 use std::path::Path;
 
+/// Longest path (in UTF-16 units, excluding the terminator) Win32 accepts without `\\?\`.
+#[cfg(windows)]
+const WINDOWS_MAX_PATH: usize = 259;
+
+/// Path to use for file I/O: on Windows, paths beyond `MAX_PATH` get the
+/// extended-length `\\?\` (or `\\?\UNC\`) prefix so deep trees can be opened.
+///
+/// Short paths, already-prefixed paths, and all paths on other platforms are
+/// returned unchanged. The result is only for opening files; stored index paths
+/// must keep using the original.
+#[cfg(windows)]
+pub(crate) fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, PathBuf, Prefix};
+
+    if path.as_os_str().encode_wide().count() <= WINDOWS_MAX_PATH {
+        return Cow::Borrowed(path);
+    }
+
+    // Verbatim paths skip normalization, so resolve `.`/`..`/`/` first
+    let Ok(absolute) = std::path::absolute(path) else {
+        return Cow::Borrowed(path);
+    };
+    let Some(Component::Prefix(prefix)) = absolute.components().next() else {
+        return Cow::Borrowed(path);
+    };
+    let Some(absolute_str) = absolute.to_str() else {
+        return Cow::Borrowed(path);
+    };
+
+    match prefix.kind() {
+        Prefix::Disk(_) => Cow::Owned(PathBuf::from(format!(r"\\?\{absolute_str}"))),
+        Prefix::UNC(..) => {
+            let rest = absolute_str.trim_start_matches('\\');
+            Cow::Owned(PathBuf::from(format!(r"\\?\UNC\{rest}")))
+        }
+        // Already verbatim or a device path
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Non-Windows platforms have no `MAX_PATH` limit to work around.
+#[cfg(not(windows))]
+pub(crate) const fn extended_length_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
 pub(crate) fn sync_file(path: &Path) -> io::Result<()> {
     #[cfg(windows)]
     {
@@ -76,6 +124,59 @@ pub(crate) fn sync_parent_dir(path: &Path) -> io::Result<()> {
     }
 }
 
+/// Bytes available to the current user on the filesystem holding `path`.
+///
+/// # Errors
+/// Returns the OS error if the query fails, or `Unsupported` on platforms
+/// without an implementation.
+pub(crate) fn available_space(path: &Path) -> io::Result<u64> {
+    #[cfg(unix)]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+        if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let stat = unsafe { stat.assume_init() };
+        // Field widths differ between platforms (u32 on macOS, u64 on Linux)
+        #[allow(clippy::useless_conversion)]
+        Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+        let mut wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+        wide.push(0);
+
+        let mut available = 0u64;
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                wide.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(available)
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        Err(io::Error::new(io::ErrorKind::Unsupported, "free-space query not supported"))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,6 +198,30 @@ mod tests {
         sync_parent_dir(&path).unwrap();
     }
 
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn test_available_space_ok() {
+        let dir = tempdir().unwrap();
+        assert!(available_space(dir.path()).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_extended_length_path_keeps_short_paths() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        assert_eq!(extended_length_path(&path), Cow::Borrowed(path.as_path()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_extended_length_path_prefixes_long_paths() {
+        let dir = tempdir().unwrap();
+        let long = dir.path().join("a".repeat(200)).join("b".repeat(200)).join("file.txt");
+        let extended = extended_length_path(&long);
+        assert!(extended.to_str().unwrap().starts_with(r"\\?\"));
+        assert!(extended.ends_with("file.txt"));
+    }
+
     #[test]
     fn test_sync_parent_dir_missing_parent_fails() {
         let dir = tempdir().unwrap();
@@ -46,11 +46,11 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::constants::EXPECTED_APPLICATION_ID;
-use crate::db::{Database, PragmaConfig};
+use crate::db::{CheckpointMode, Database, PragmaConfig};
 use crate::error::Result;
 use crate::fs_utils::{sync_file, sync_parent_dir};
 use crate::indexer::{IndexStats, Indexer, IndexerConfig};
-use crate::{DB_NAME, DB_TMP_SUFFIX, init};
+use crate::{DB_NAME, DB_SHM_SUFFIX, DB_TMP_SUFFIX, DB_WAL_SUFFIX, db_sibling, init};
 
 /// Database health status for auto-init decisions.
 ///
@@ -65,6 +65,10 @@ pub enum DatabaseHealth {
     /// Schema exists but contains zero indexed files.
     /// Action: Run indexing only (skip schema init).
     Empty,
+    /// Our database with its core tables, but an older schema version
+    /// (see [`Database::needs_migration`]).
+    /// Action: Migrate in place ([`migrate_in_place`]); indexed rows are kept.
+    MigrationNeeded,
 
     /// No database file found at expected location.
     /// Action: Run full init + index.
@@ -100,6 +104,12 @@ impl DatabaseHealth {
         matches!(self, Self::Missing | Self::Empty)
     }
 
+    /// Returns true if the schema should be upgraded in place with [`migrate_in_place`].
+    #[must_use]
+    pub const fn needs_migration(&self) -> bool {
+        matches!(self, Self::MigrationNeeded)
+    }
+
     /// Returns true if database needs backup + reinit.
     #[must_use]
     pub const fn needs_reinit(&self) -> bool {
@@ -111,6 +121,21 @@ impl DatabaseHealth {
     pub const fn is_unrecoverable(&self) -> bool {
         matches!(self, Self::WrongApplicationId | Self::Unreadable)
     }
+
+    /// Stable `snake_case` name, used in machine-readable output.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Empty => "empty",
+            Self::MigrationNeeded => "migration_needed",
+            Self::Missing => "missing",
+            Self::Unreadable => "unreadable",
+            Self::WrongApplicationId => "wrong_application_id",
+            Self::SchemaInvalid => "schema_invalid",
+            Self::Corrupted => "corrupted",
+        }
+    }
 }
 
 /// Method used to detect project root.
@@ -224,8 +249,9 @@ pub fn find_project_root(start_dir: &Path) -> ProjectRoot {
 /// 1. Database file exists
 /// 2. Database readable (open with `SQLITE_OPEN_READ_ONLY`)
 /// 3. Application ID matches `EXPECTED_APPLICATION_ID`
-/// 4. Schema complete (tables, triggers, indexes)
-/// 5. File count > 0
+/// 4. Schema version current (core tables present but older version = migration needed)
+/// 5. Schema complete (tables, triggers, indexes)
+/// 6. File count > 0
 ///
 /// Note: Does NOT run FTS5 integrity check (that requires write access).
 ///
@@ -238,15 +264,19 @@ pub fn find_project_root(start_dir: &Path) -> ProjectRoot {
 /// `DatabaseHealth` indicating current state.
 #[must_use]
 pub fn check_health_fast(project_dir: &Path) -> DatabaseHealth {
-    let db_path = project_dir.join(DB_NAME);
+    check_health_at(&project_dir.join(DB_NAME))
+}
 
+/// [`check_health_fast`] for a database at an explicit location.
+#[must_use]
+pub fn check_health_at(db_path: &Path) -> DatabaseHealth {
     // Check 1: File exists
     if !db_path.exists() {
         return DatabaseHealth::Missing;
     }
 
     // Check 2: Can open read-only
-    let Ok(db) = Database::open_readonly(&db_path) else {
+    let Ok(db) = Database::open_readonly(db_path) else {
         return DatabaseHealth::Unreadable;
     };
 
@@ -257,12 +287,19 @@ pub fn check_health_fast(project_dir: &Path) -> DatabaseHealth {
         None => return DatabaseHealth::Corrupted,
     }
 
-    // Check 4: Schema complete
-    if !db.check_schema().is_complete() {
+    // Check 4: Old but migratable schema (checked before completeness, since a
+    // legacy schema is also incomplete and must not be wiped by a reinit)
+    let schema = db.check_schema();
+    if schema.has_files_table && schema.has_fts_table && db.needs_migration() {
+        return DatabaseHealth::MigrationNeeded;
+    }
+
+    // Check 5: Schema complete
+    if !schema.is_complete() {
         return DatabaseHealth::SchemaInvalid;
     }
 
-    // Check 5: Has content
+    // Check 6: Has content
     match db.get_file_count() {
         Ok(0) => DatabaseHealth::Empty,
         Ok(_) => DatabaseHealth::Healthy,
@@ -299,7 +336,8 @@ pub fn auto_init(project_dir: &Path, config: &PragmaConfig, quiet: bool) -> Resu
 
 /// Auto-initialize database with explicit indexer configuration.
 ///
-/// Use this to override indexing defaults (e.g., symlink handling).
+/// Use this to override indexing defaults (e.g., symlink handling). The database
+/// is created at [`IndexerConfig::database_path`].
 ///
 /// # Errors
 ///
@@ -310,10 +348,10 @@ pub fn auto_init_with_config(
     indexer_config: IndexerConfig,
     quiet: bool,
 ) -> Result<IndexStats> {
-    // Update gitignore first (idempotent operation)
-    let _ = init::update_gitignore(project_dir);
+    let db_path = indexer_config.database_path(project_dir);
 
-    let db_path = project_dir.join(DB_NAME);
+    // Update gitignore first (idempotent operation)
+    let _ = init::update_gitignore_for(project_dir, &db_path);
 
     // Use unique temp file per process+thread to prevent concurrent overwrites
     // Format: .ffts-index.db.tmp.{pid}_{thread_id_hash}
@@ -325,14 +363,14 @@ pub fn auto_init_with_config(
         hasher.finish()
     };
     let unique_suffix = format!("{}_{:x}", std::process::id(), thread_id_hash);
-    let tmp_path = project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}"));
+    let tmp_path = db_sibling(&db_path, &format!("{DB_TMP_SUFFIX}.{unique_suffix}"));
+    let tmp_shm_path = db_sibling(&tmp_path, DB_SHM_SUFFIX);
+    let tmp_wal_path = db_sibling(&tmp_path, DB_WAL_SUFFIX);
 
     // Clean up any stale temp file from previous failed attempt (same process/thread)
     let _ = fs::remove_file(&tmp_path);
-    let _ =
-        fs::remove_file(project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}-shm")));
-    let _ =
-        fs::remove_file(project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}-wal")));
+    let _ = fs::remove_file(&tmp_shm_path);
+    let _ = fs::remove_file(&tmp_wal_path);
 
     // Create database in temp location (atomic pattern)
     let db = Database::open(&tmp_path, config)?;
@@ -362,10 +400,13 @@ pub fn auto_init_with_config(
     // - busy: 0 = no blocking, >0 = checkpoint was blocked
     // - log: frames in WAL before checkpoint
     // - checkpointed: frames moved to database file
-    let checkpoint_result: std::result::Result<(i64, i64, i64), _> =
-        indexer.db().conn().query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-        });
+    //
+    // Rollback-journal modes (DELETE/TRUNCATE) have nothing to checkpoint.
+    let checkpoint_result = if config.uses_wal() {
+        indexer.db().checkpoint(CheckpointMode::Truncate)
+    } else {
+        Ok((0, 0, 0))
+    };
 
     // Close the database connection before rename to release locks
     drop(indexer);
@@ -404,12 +445,8 @@ pub fn auto_init_with_config(
     // Helper to clean up temp files on failure/skip
     let cleanup_temp = || {
         let _ = fs::remove_file(&tmp_path);
-        let _ = fs::remove_file(
-            project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}-shm")),
-        );
-        let _ = fs::remove_file(
-            project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}-wal")),
-        );
+        let _ = fs::remove_file(&tmp_shm_path);
+        let _ = fs::remove_file(&tmp_wal_path);
     };
 
     if checkpoint_ok {
@@ -434,12 +471,8 @@ pub fn auto_init_with_config(
                 tracing::debug!(error = %e, "Rename failed, using existing database");
             } else {
                 // Rename succeeded - only clean up WAL files (main file was renamed)
-                let _ = fs::remove_file(
-                    project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}-shm")),
-                );
-                let _ = fs::remove_file(
-                    project_dir.join(format!("{DB_NAME}{DB_TMP_SUFFIX}.{unique_suffix}-wal")),
-                );
+                let _ = fs::remove_file(&tmp_shm_path);
+                let _ = fs::remove_file(&tmp_wal_path);
                 sync_parent_dir(&db_path)
                     .map_err(|e| crate::error::IndexerError::Io { source: e })?;
             }
@@ -506,14 +539,14 @@ pub fn backup_and_reinit_with_config(
     indexer_config: IndexerConfig,
     quiet: bool,
 ) -> Result<IndexStats> {
-    let db_path = project_dir.join(DB_NAME);
+    let db_path = indexer_config.database_path(project_dir);
 
     // Create timestamped backup filename
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_secs())
         .unwrap_or(0);
-    let backup_path = project_dir.join(format!("{DB_NAME}.backup.{timestamp}"));
+    let backup_path = db_sibling(&db_path, &format!(".backup.{timestamp}"));
 
     // Attempt backup via rename (atomic move)
     if let Err(e) = fs::rename(&db_path, &backup_path) {
@@ -529,13 +562,40 @@ pub fn backup_and_reinit_with_config(
     }
 
     // Clean up WAL/SHM files from corrupted database
-    let _ = fs::remove_file(project_dir.join(format!("{DB_NAME}-shm")));
-    let _ = fs::remove_file(project_dir.join(format!("{DB_NAME}-wal")));
+    let _ = fs::remove_file(db_sibling(&db_path, DB_SHM_SUFFIX));
+    let _ = fs::remove_file(db_sibling(&db_path, DB_WAL_SUFFIX));
 
     // Perform fresh initialization
     auto_init_with_config(project_dir, config, indexer_config, quiet)
 }
 
+/// Upgrade the schema of the database at `db_path` without reindexing from disk.
+///
+/// Runs [`Database::migrate_schema`] and [`Database::init_schema`], then rebuilds
+/// FTS5 from the stored rows if a migration step dropped it. Unlike
+/// [`backup_and_reinit`], every indexed row survives. A current schema is left
+/// alone.
+///
+/// Returns `(rows migrated, rows reindexed)`.
+///
+/// # Errors
+///
+/// Returns `IndexerError` if the database cannot be opened or a step fails.
+pub fn migrate_in_place(db_path: &Path, config: &PragmaConfig) -> Result<(usize, usize)> {
+    let db = Database::open(db_path, config)?;
+    if !db.needs_migration() {
+        tracing::debug!("Schema is current; nothing to migrate");
+        return Ok((0, 0));
+    }
+
+    let migrated = db.migrate_schema()?;
+    let fts_dropped = !db.check_schema().has_fts_table;
+    db.init_schema()?;
+    let reindexed = if fts_dropped { db.rebuild_fts_index()? } else { 0 };
+    tracing::info!(migrated, reindexed, "Schema migrated in place");
+    Ok((migrated, reindexed))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -744,6 +804,71 @@ mod tests {
         assert!(health.needs_reinit());
     }
 
+    #[test]
+    fn test_health_legacy_schema_needs_migration() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+
+        // Our app ID, but the v0 layout: no filename column, 2-column FTS5
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.pragma_update(None, "application_id", APPLICATION_ID_I32).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE files (
+                id INTEGER PRIMARY KEY,
+                path TEXT UNIQUE NOT NULL,
+                content_hash TEXT,
+                mtime INTEGER,
+                size INTEGER,
+                indexed_at INTEGER,
+                content TEXT
+            );
+            CREATE VIRTUAL TABLE files_fts USING fts5(
+                path, content, content='files', content_rowid='id'
+            );
+            INSERT INTO files (path, content_hash, mtime, size, indexed_at, content)
+            VALUES ('src/kept.rs', 'hash', 0, 22, 0, 'fn survivor_needle() {}');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let health = check_health_fast(dir.path());
+        assert_eq!(health, DatabaseHealth::MigrationNeeded);
+        assert!(health.needs_migration());
+        assert!(!health.needs_reinit());
+
+        assert_eq!(migrate_in_place(&db_path, &PragmaConfig::default()).unwrap(), (1, 1));
+        assert_eq!(check_health_fast(dir.path()), DatabaseHealth::Healthy);
+
+        // The indexed row survived and is searchable through the new schema
+        let db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        assert_eq!(db.get_file_count().unwrap(), 1);
+        let hits: i64 = db
+            .conn()
+            .query_row(
+                "SELECT COUNT(*) FROM files_fts WHERE files_fts MATCH 'survivor_needle'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(hits, 1);
+    }
+
+    #[test]
+    fn test_health_unstamped_schema_migrates_without_reindex() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/main.rs", "fn main() {}", 0, 12).unwrap();
+        // Current layout written before versions were stamped
+        db.conn().pragma_update(None, "user_version", 0).unwrap();
+        drop(db);
+
+        assert_eq!(check_health_fast(dir.path()), DatabaseHealth::MigrationNeeded);
+        assert_eq!(migrate_in_place(&db_path, &PragmaConfig::default()).unwrap(), (0, 0));
+        assert_eq!(check_health_fast(dir.path()), DatabaseHealth::Healthy);
+    }
+
     // === DatabaseHealth enum tests ===
 
     #[test]
@@ -759,6 +884,11 @@ mod tests {
         assert!(DatabaseHealth::SchemaInvalid.needs_reinit());
         assert!(DatabaseHealth::Corrupted.needs_reinit());
         assert!(!DatabaseHealth::Missing.needs_reinit());
+        assert!(!DatabaseHealth::MigrationNeeded.needs_reinit());
+
+        assert!(DatabaseHealth::MigrationNeeded.needs_migration());
+        assert!(!DatabaseHealth::MigrationNeeded.is_usable());
+        assert!(!DatabaseHealth::SchemaInvalid.needs_migration());
 
         assert!(DatabaseHealth::WrongApplicationId.is_unrecoverable());
         assert!(DatabaseHealth::Unreadable.is_unrecoverable());
@@ -18,7 +18,9 @@ use std::path::Path;
 
 use crate::error::Result;
 use crate::fs_utils::sync_parent_dir;
-use crate::{DB_NAME, DB_SHM_NAME, DB_TMP_GLOB, DB_WAL_NAME};
+use crate::{
+    DB_NAME, DB_SHM_NAME, DB_SHM_SUFFIX, DB_TMP_GLOB, DB_TMP_SUFFIX, DB_WAL_NAME, DB_WAL_SUFFIX,
+};
 
 /// Required gitignore entries for ffts-grep.
 /// Returns a static array with all database file patterns.
@@ -27,6 +29,25 @@ pub const fn gitignore_entries() -> [&'static str; 4] {
     [DB_NAME, DB_SHM_NAME, DB_WAL_NAME, DB_TMP_GLOB]
 }
 
+/// Gitignore entries for a database at `db_path` used by `project_dir`.
+///
+/// The same four patterns as [`gitignore_entries`], derived from the database's
+/// own name (and its directory, when nested inside the project). A database
+/// outside the project needs no entries.
+#[must_use]
+pub fn gitignore_entries_for(project_dir: &Path, db_path: &Path) -> Vec<String> {
+    let Ok(relative) = db_path.strip_prefix(project_dir) else {
+        return Vec::new();
+    };
+    let base = relative.to_string_lossy().replace('\\', "/");
+    vec![
+        base.clone(),
+        format!("{base}{DB_SHM_SUFFIX}"),
+        format!("{base}{DB_WAL_SUFFIX}"),
+        format!("{base}{DB_TMP_SUFFIX}*"),
+    ]
+}
+
 /// Header comment for gitignore section.
 const GITIGNORE_HEADER: &str = "# ffts-grep database files (auto-generated)";
 
@@ -78,6 +99,19 @@ pub struct InitResult {
     pub files_indexed: usize,
 }
 
+/// Like [`check_gitignore`], for a database at `db_path` (see [`gitignore_entries_for`]).
+#[must_use]
+pub fn check_gitignore_for(project_dir: &Path, db_path: &Path) -> Vec<String> {
+    let existing = fs::read_to_string(project_dir.join(".gitignore")).unwrap_or_default();
+    let existing_patterns: HashSet<&str> =
+        existing.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).collect();
+
+    gitignore_entries_for(project_dir, db_path)
+        .into_iter()
+        .filter(|e| !existing_patterns.contains(e.as_str()))
+        .collect()
+}
+
 /// Check if all required gitignore entries are present.
 ///
 /// Returns a list of missing entries (empty if all present).
@@ -112,6 +146,15 @@ pub fn check_gitignore(project_dir: &Path) -> Vec<&'static str> {
 ///
 /// Returns error if file operations fail.
 pub fn update_gitignore(project_dir: &Path) -> Result<GitignoreResult> {
+    update_gitignore_for(project_dir, &project_dir.join(DB_NAME))
+}
+
+/// Like [`update_gitignore`], for a database at `db_path` (see [`gitignore_entries_for`]).
+///
+/// # Errors
+///
+/// Returns error if file operations fail.
+pub fn update_gitignore_for(project_dir: &Path, db_path: &Path) -> Result<GitignoreResult> {
     let gitignore_path = project_dir.join(".gitignore");
 
     // Read existing content (empty if file doesn't exist)
@@ -123,8 +166,10 @@ pub fn update_gitignore(project_dir: &Path) -> Result<GitignoreResult> {
         existing.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).collect();
 
     // Find missing entries
-    let missing: Vec<&str> =
-        gitignore_entries().iter().filter(|e| !existing_patterns.contains(*e)).copied().collect();
+    let missing: Vec<String> = gitignore_entries_for(project_dir, db_path)
+        .into_iter()
+        .filter(|e| !existing_patterns.contains(e.as_str()))
+        .collect();
 
     if missing.is_empty() {
         return Ok(GitignoreResult::AlreadyComplete);
@@ -242,6 +287,21 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_gitignore_entries_for_custom_location() {
+        let dir = tempdir().unwrap();
+        let default_entries = gitignore_entries_for(dir.path(), &dir.path().join(DB_NAME));
+        assert_eq!(default_entries, gitignore_entries());
+
+        assert_eq!(
+            gitignore_entries_for(dir.path(), &dir.path().join(".cache/ffts.db")),
+            [".cache/ffts.db", ".cache/ffts.db-shm", ".cache/ffts.db-wal", ".cache/ffts.db.tmp*"]
+        );
+
+        let outside = tempdir().unwrap();
+        assert!(gitignore_entries_for(dir.path(), &outside.path().join("ffts.db")).is_empty());
+    }
+
     #[test]
     fn test_check_gitignore_no_file() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,116 @@
+//! Programming language detection from file paths.
+//!
+//! Languages are detected from the file extension (or a few well-known file
+//! names) and stored per file in `files.language`, so searches can be
+//! restricted to one language (see [`Database::search_in_language`](crate::Database::search_in_language)).
+
+use std::path::Path;
+
+/// Extension → language name. Names are lowercase and stable: they are stored
+/// in the database and accepted by `--lang`.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("rs", "rust"),
+    ("py", "python"),
+    ("pyi", "python"),
+    ("js", "javascript"),
+    ("mjs", "javascript"),
+    ("cjs", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("mts", "typescript"),
+    ("cts", "typescript"),
+    ("tsx", "typescript"),
+    ("go", "go"),
+    ("c", "c"),
+    ("h", "c"),
+    ("cc", "cpp"),
+    ("cpp", "cpp"),
+    ("cxx", "cpp"),
+    ("hh", "cpp"),
+    ("hpp", "cpp"),
+    ("hxx", "cpp"),
+    ("cs", "csharp"),
+    ("java", "java"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("scala", "scala"),
+    ("swift", "swift"),
+    ("m", "objc"),
+    ("mm", "objc"),
+    ("rb", "ruby"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("zig", "zig"),
+    ("hs", "haskell"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("erl", "erlang"),
+    ("dart", "dart"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("fish", "shell"),
+    ("ps1", "powershell"),
+    ("sql", "sql"),
+    ("html", "html"),
+    ("htm", "html"),
+    ("css", "css"),
+    ("scss", "css"),
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("md", "markdown"),
+    ("markdown", "markdown"),
+    ("json", "json"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("toml", "toml"),
+    ("xml", "xml"),
+];
+
+/// Extensionless file names with a conventional language.
+const FILENAMES: &[(&str, &str)] =
+    &[("Makefile", "make"), ("makefile", "make"), ("Dockerfile", "dockerfile")];
+
+/// Detect the language of `path` from its extension (case-insensitive) or file name.
+///
+/// Returns `None` for unknown or missing extensions.
+#[must_use]
+pub fn detect_language(path: &str) -> Option<&'static str> {
+    let path = Path::new(path);
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        let ext = ext.to_ascii_lowercase();
+        return EXTENSIONS.iter().find(|(e, _)| *e == ext).map(|(_, lang)| *lang);
+    }
+
+    let name = path.file_name()?.to_str()?;
+    FILENAMES.iter().find(|(n, _)| *n == name).map(|(_, lang)| *lang)
+}
+
+/// Whether `language` is a name [`detect_language`] can return.
+#[must_use]
+pub fn is_known_language(language: &str) -> bool {
+    EXTENSIONS.iter().chain(FILENAMES).any(|(_, lang)| *lang == language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("src/main.rs"), Some("rust"));
+        assert_eq!(detect_language("scripts/Build.PY"), Some("python"));
+        assert_eq!(detect_language("web/App.tsx"), Some("typescript"));
+        assert_eq!(detect_language("Makefile"), Some("make"));
+        assert_eq!(detect_language("notes.unknownext"), None);
+        assert_eq!(detect_language("LICENSE"), None);
+    }
+
+    #[test]
+    fn test_is_known_language() {
+        assert!(is_known_language("rust"));
+        assert!(is_known_language("make"));
+        assert!(!is_known_language("Rust"));
+        assert!(!is_known_language("cobol"));
+    }
+}
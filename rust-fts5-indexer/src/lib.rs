@@ -63,26 +63,70 @@ pub const DB_TMP_NAME: &str = ".ffts-index.db.tmp";
 /// This glob matches all temporary database files created during reindex.
 pub const DB_TMP_GLOB: &str = ".ffts-index.db.tmp*";
 
+/// Path of a database companion file: `db_path` with `suffix` appended to the
+/// file name (e.g. [`DB_WAL_SUFFIX`] or [`DB_TMP_SUFFIX`]).
+///
+/// Companion names are derived from whichever database file is in use, so a
+/// custom database location gets matching `-wal`/`-shm`/`.tmp` files.
+#[must_use]
+pub fn db_sibling(db_path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    db_path.with_file_name(name)
+}
+
+/// Per-directory ignore file for indexing-only exclusions.
+///
+/// Uses gitignore syntax and applies to its directory and below, on top of
+/// `.gitignore`, without affecting git itself.
+pub const IGNORE_FILENAME: &str = ".ffts-ignore";
+
+/// Alternate spelling of [`IGNORE_FILENAME`]; both are read when present.
+pub const IGNORE_FILENAME_ALT: &str = ".fftsignore";
+
+/// Leading character of stored paths that come from an extra index root
+/// (`@label/src/lib.rs`, see `IndexerConfig::extra_roots`).
+pub const ROOT_LABEL_PREFIX: char = '@';
+
 pub mod cli;
+pub mod config;
 pub mod constants;
+#[cfg(unix)]
+pub mod daemon;
 pub mod db;
 pub mod doctor;
+pub mod dump;
 pub mod error;
 mod fs_utils;
 pub mod health;
 pub mod indexer;
 pub mod init;
+pub mod language;
+pub mod repl;
 pub mod search;
+pub mod server;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod watch;
 
 pub use cli::OutputFormat;
-pub use constants::EXPECTED_APPLICATION_ID;
-pub use db::{Database, PragmaConfig, SchemaCheck, SearchResult};
+pub use config::Config;
+pub use constants::{EXPECTED_APPLICATION_ID, SCHEMA_VERSION};
+pub use db::{
+    Bm25Weights, CheckpointMode, ColumnSet, Database, FileMetadata, FileRecord, FtsSync, LineMatch,
+    MatchDensity, PragmaConfig, RankBreakdown, SchemaCheck, SchemaDriftReport, SearchFilter,
+    SearchResult, SnippetStyle, TokenizerKind,
+};
 pub use doctor::{CheckResult, Doctor, DoctorOutput, DoctorSummary, Severity};
 pub use error::{ExitCode, IndexerError, Result};
 pub use health::{
     DatabaseHealth, DetectionMethod, ProjectRoot, auto_init, auto_init_with_config,
     backup_and_reinit, backup_and_reinit_with_config, check_health_fast, find_project_root,
 };
-pub use indexer::{IndexStats, Indexer, IndexerConfig};
+pub use indexer::{
+    IndexPlan, IndexProgress, IndexStats, Indexer, IndexerConfig, PROGRESS_INTERVAL,
+    ProgressCallback, ReindexPlan,
+};
 pub use init::{GitignoreResult, InitResult, check_gitignore, gitignore_entries, update_gitignore};
-pub use search::{SearchConfig, Searcher};
+pub use search::{HighlightMarkers, QueryMode, SearchConfig, Searcher};
+pub use watch::Watcher;
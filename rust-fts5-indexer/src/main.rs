@@ -1,9 +1,11 @@
+use std::collections::HashSet;
 use std::fs;
-use std::io::{self, BufRead, IsTerminal};
-use std::path::Path;
+use std::io::{self, BufRead, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use clap::Parser;
-use serde::Deserialize;
+use clap::CommandFactory;
+use serde::{Deserialize, Serialize};
 
 /// JSON input format for Claude Code file suggestion integration.
 /// Claude Code sends: {"query": "search term", "refresh": true}
@@ -19,25 +21,73 @@ struct SearchOptions {
     refresh: bool,
     no_auto_init: bool,
     quiet: bool,
+    /// Print the match count instead of results
+    count: bool,
+}
+
+/// Latency percentiles for one benchmark query (`search --benchmark --stats-json`).
+#[derive(Serialize)]
+struct BenchQueryStats<'a> {
+    query: &'a str,
+    results: usize,
+    p50_ms: f64,
+    p95_ms: f64,
+}
+
+/// JSON envelope for `search --benchmark --stats-json`.
+/// `stats --json` output. Field names are stable for scripting.
+#[derive(Serialize)]
+struct StatsReport {
+    /// Indexed files (honors `--in` / `--ext`)
+    file_count: usize,
+    /// The remaining fields always describe the whole index
+    total_indexed_bytes: u64,
+    average_file_bytes: u64,
+    largest_file: Option<LargestFile>,
+    db_size_bytes: u64,
+    journal_mode: String,
+}
+
+#[derive(Serialize)]
+struct LargestFile {
+    path: String,
+    size_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct BenchReport<'a> {
+    file_count: usize,
+    db_size_bytes: u64,
+    iterations: u32,
+    queries: Vec<BenchQueryStats<'a>>,
 }
 
-const DEFAULT_MAX_RESULTS: u32 = 50;
 use ffts_indexer::{
-    DB_NAME, DB_SHM_SUFFIX, DB_WAL_SUFFIX,
-    cli::{Cli, Commands, OutputFormat},
-    db::{Database, PragmaConfig},
+    Config, DB_NAME, DB_SHM_SUFFIX, DB_WAL_SUFFIX,
+    cli::{
+        Cli, Commands, DEFAULT_SEARCH_LIMIT, FailOn, MAX_FILE_SIZE_WARN_THRESHOLD, OutputFormat,
+    },
+    db::{Bm25Weights, CheckpointMode, Database, PragmaConfig},
+    db_sibling,
     doctor::Doctor,
+    dump::{DumpFormat, export_index, import_into_project},
     error::{ExitCode, IndexerError},
     health::{self, DatabaseHealth},
-    indexer::{IndexStats, Indexer, IndexerConfig, atomic_reindex_with_config},
+    indexer::{
+        IndexStats, Indexer, IndexerConfig, atomic_reindex_with_config, load_hash_denylist,
+        root_prefixes,
+    },
     init::{self, InitResult},
-    search::{SearchConfig, Searcher},
+    repl::Repl,
+    search::{HighlightMarkers, SearchConfig, Searcher},
+    server::Server,
+    watch::Watcher,
 };
 
 #[allow(clippy::too_many_lines)]
 fn main() -> std::process::ExitCode {
     // Parse CLI arguments
-    let cli = Cli::parse();
+    let cli = Cli::parse_args();
 
     // Initialize structured logging (respects RUST_LOG env var)
     // Default: WARN level (only errors and warnings)
@@ -84,23 +134,102 @@ fn main() -> std::process::ExitCode {
         return ExitCode::IoErr.into(); // IOERR
     }
 
-    // Build PRAGMA configuration
-    let pragma_config = PragmaConfig {
-        journal_mode: "WAL".to_string(),
-        synchronous: cli.pragma_synchronous.clone(),
-        cache_size: cli.pragma_cache_size,
-        temp_store: "MEMORY".to_string(),
-        mmap_size: cli.pragma_mmap_size,
-        page_size: cli.pragma_page_size,
-        busy_timeout_ms: cli.pragma_busy_timeout,
+    let hash_denylist = match cli.hash_denylist_file.as_deref().map(load_hash_denylist) {
+        Some(Ok(denylist)) => denylist,
+        Some(Err(e)) => {
+            tracing::error!(error = %e, "Failed to read hash denylist file");
+            return ExitCode::IoErr.into();
+        }
+        None => HashSet::new(),
+    };
+    let file_config = match Config::resolve(cli.config.as_deref(), &project_dir) {
+        Ok((config, source)) => {
+            if let Some(source) = source {
+                tracing::debug!(path = %source.display(), "Loaded config file");
+            }
+            config
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to load config file");
+            return match e {
+                IndexerError::Io { .. } => ExitCode::IoErr.into(),
+                _ => ExitCode::DataErr.into(),
+            };
+        }
     };
-    let indexer_config =
-        || IndexerConfig { follow_symlinks: cli.follow_symlinks, ..Default::default() };
+    // Build PRAGMA configuration: defaults, then `[pragma]`, then explicit flags
+    let mut pragma_config = PragmaConfig::default();
+    file_config.apply_pragma(&mut pragma_config);
+    cli.apply_pragma_overrides(&mut pragma_config);
+    let db_override = match cli.db_location(&project_dir, file_config.db_path.as_deref()) {
+        Ok(location) => location,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to resolve database path");
+            return ExitCode::NoInput.into();
+        }
+    };
+    if let Some(parent) = db_override.as_deref().and_then(Path::parent) {
+        if let Err(e) = fs::create_dir_all(parent) {
+            tracing::error!(error = %e, path = %parent.display(), "Failed to create database directory");
+            return ExitCode::IoErr.into();
+        }
+    }
+    let db_path = db_override.clone().unwrap_or_else(|| project_dir.join(DB_NAME));
+    if let Some(size) = cli.max_file_size.filter(|&size| size > MAX_FILE_SIZE_WARN_THRESHOLD) {
+        tracing::warn!(
+            max_file_size = size,
+            "--max-file-size is above 64MB; each file is read fully into memory while indexing"
+        );
+    }
+    let indexer_config = || {
+        let mut config = IndexerConfig::default();
+        file_config.apply(&mut config);
+        config.follow_symlinks |= cli.follow_symlinks;
+        config.lossy_utf8 |= cli.lossy_utf8;
+        config.index_content &= !cli.no_content;
+        config.hidden_allowlist.extend(cli.index_hidden_files.iter().cloned());
+        config.index_hidden |= cli.hidden;
+        if let Some(max_file_size) = cli.max_file_size {
+            config.max_file_size = max_file_size;
+        }
+        config.hash_denylist.clone_from(&hash_denylist);
+        if !cli.include_extensions.is_empty() {
+            config.include_extensions = Some(cli.include_extensions.clone());
+        }
+        config.exclude_extensions.extend(cli.exclude_extensions.iter().cloned());
+        config.db_path.clone_from(&db_override);
+        config.extra_roots.extend(cli.add_roots.iter().cloned());
+        if let Some(batch_size) = cli.batch_size {
+            config.batch_size = batch_size;
+        }
+        if let Some(threshold) = cli.transaction_threshold {
+            config.transaction_threshold = threshold;
+        }
+        config
+    };
+    if let Err(e) = indexer_config().validate() {
+        tracing::error!(error = %e, "Invalid indexing configuration");
+        return ExitCode::DataErr.into();
+    }
 
     if cli.refresh
         && matches!(
             cli.command,
-            Some(Commands::Index { .. } | Commands::Doctor { .. } | Commands::Init { .. })
+            Some(
+                Commands::Index { .. }
+                    | Commands::Prune { .. }
+                    | Commands::Rebuild
+                    | Commands::Watch { .. }
+                    | Commands::Compact { .. }
+                    | Commands::Doctor { .. }
+                    | Commands::Init { .. }
+                    | Commands::Hash { .. }
+                    | Commands::Completions { .. }
+                    | Commands::Manpage { .. }
+                    | Commands::Stats { .. }
+                    | Commands::Export { .. }
+                    | Commands::Import { .. }
+            )
         )
     {
         tracing::error!("--refresh is only valid for search operations");
@@ -109,9 +238,94 @@ fn main() -> std::process::ExitCode {
 
     // Handle subcommands
     match &cli.command {
-        Some(Commands::Doctor { verbose, json }) => {
+        Some(Commands::Doctor { verbose, json, fail_on, fix }) => {
             let format = if *json { OutputFormat::Json } else { OutputFormat::Plain };
-            return run_doctor(&project_dir, *verbose, format);
+            let fix_configs = fix.then(|| (&pragma_config, indexer_config()));
+            return run_doctor(
+                &project_dir,
+                &db_path,
+                indexer_config().extra_roots,
+                *verbose,
+                format,
+                *fail_on,
+                &pragma_config.journal_mode,
+                fix_configs,
+                cli.color.enabled(),
+            );
+        }
+        Some(Commands::Hash { file }) => {
+            return run_hash(file);
+        }
+        Some(Commands::Completions { shell }) => {
+            return run_completions(*shell);
+        }
+        Some(Commands::Manpage { out }) => {
+            return run_manpage(out.as_deref());
+        }
+        Some(Commands::Prune { optimize }) => {
+            return run_prune(
+                &project_dir,
+                &indexer_config().extra_roots,
+                &db_path,
+                &pragma_config,
+                *optimize,
+            );
+        }
+        Some(Commands::Rebuild) => {
+            return run_rebuild(&db_path, &pragma_config);
+        }
+        Some(Commands::Watch { debounce_ms, interval_ms }) => {
+            return run_watch(
+                &project_dir,
+                &pragma_config,
+                indexer_config(),
+                Duration::from_millis(*debounce_ms),
+                Duration::from_millis(*interval_ms),
+            );
+        }
+        Some(Commands::Compact { into }) => {
+            return run_compact(&db_path, &pragma_config, into.as_deref());
+        }
+        Some(Commands::Export { out, ndjson }) => {
+            let format = if *ndjson { DumpFormat::Ndjson } else { DumpFormat::Json };
+            return run_export(&db_path, out.as_deref(), format);
+        }
+        Some(Commands::Import { input, force }) => {
+            return run_import(&db_path, &pragma_config, input.as_deref(), *force);
+        }
+        Some(Commands::Stats { in_dir, ext, json }) => {
+            let format = if *json { OutputFormat::Json } else { OutputFormat::Plain };
+            return run_stats(&db_path, in_dir.as_deref(), ext.as_deref(), format);
+        }
+        Some(Commands::Repl { limit, paths }) => {
+            if cli.refresh {
+                tracing::error!("--refresh is only valid for search operations");
+                return ExitCode::DataErr.into();
+            }
+            return run_repl(&db_path, *limit, *paths);
+        }
+        Some(Commands::Serve { addr, workers }) => {
+            if cli.refresh {
+                tracing::error!("--refresh is only valid for search operations");
+                return ExitCode::DataErr.into();
+            }
+            return run_serve(&db_path, addr, *workers);
+        }
+        #[cfg(unix)]
+        Some(Commands::Daemon { socket }) => {
+            if cli.refresh {
+                tracing::error!("--refresh is only valid for search operations");
+                return ExitCode::DataErr.into();
+            }
+            return run_daemon(&db_path, socket);
+        }
+        #[cfg(feature = "tui")]
+        Some(Commands::Interactive) => {
+            if cli.refresh {
+                tracing::error!("--refresh is only valid for search operations");
+                return ExitCode::DataErr.into();
+            }
+            return run_interactive(&db_path);
         }
         Some(Commands::Init { gitignore_only, force }) => {
             return run_init(
@@ -123,31 +337,97 @@ fn main() -> std::process::ExitCode {
                 cli.quiet,
             );
         }
-        Some(Commands::Index { reindex }) => {
+        Some(Commands::Index { reindex, since, dry_run, stdin, name, json, checkpoint }) => {
+            if *dry_run {
+                return run_index_dry_run(&project_dir, indexer_config(), *reindex);
+            }
+            if let (true, Some(name)) = (*stdin, name) {
+                return run_index_stdin(&project_dir, &pragma_config, indexer_config(), name);
+            }
             return run_indexing(
                 &project_dir,
                 &pragma_config,
                 indexer_config(),
                 *reindex,
-                cli.quiet,
+                since.as_deref(),
+                *json,
+                *checkpoint,
             );
         }
-        Some(Commands::Search { query, paths, format, benchmark, no_auto_init }) => {
+        Some(Commands::Search {
+            query,
+            paths,
+            format,
+            limit,
+            offset,
+            benchmark,
+            iterations,
+            stats_json,
+            no_auto_init,
+            explain_plan,
+            validate_only,
+            line_ending,
+            recency,
+            position_ranks,
+            verify_exists,
+            density,
+            sort,
+            snippets,
+            explain_ranking,
+            mode,
+            raw_query,
+            prefix,
+            line_numbers,
+            json_pretty,
+            lang,
+            since,
+            until,
+            min_size,
+            max_size,
+            count,
+            files_with_matches,
+            fuzzy,
+            highlight,
+            highlight_open,
+            highlight_close,
+            ..
+        }) => {
             // Run benchmark mode if requested
             if *benchmark {
                 if cli.refresh {
                     tracing::warn!("--refresh ignored in benchmark mode");
                 }
-                return run_benchmark(&project_dir, &pragma_config, cli.quiet);
+                return run_benchmark(&db_path, &pragma_config, *iterations, *stats_json);
             }
 
             // Use subcommand query if provided, fall back to top-level query
             let search_query = if query.is_empty() { &cli.query } else { query };
+            if *explain_plan {
+                return run_explain_plan(&db_path, search_query, *paths);
+            }
+            if *validate_only {
+                return run_validate_query(&db_path, search_query);
+            }
+            if let (Some(since), Some(until)) = (since, until) {
+                if since > until {
+                    tracing::error!("--since is later than --until; no file can match");
+                    return ExitCode::DataErr.into();
+                }
+            }
+            if let (Some(min), Some(max)) = (min_size, max_size) {
+                if min > max {
+                    tracing::error!("--min-size is larger than --max-size; no file can match");
+                    return ExitCode::DataErr.into();
+                }
+            }
             if cli.refresh && query_is_empty(search_query) {
                 tracing::error!("--refresh requires a search query or stdin JSON");
                 return ExitCode::DataErr.into();
             }
             let output_format = format.unwrap_or(OutputFormat::Plain);
+            let mut weights = Bm25Weights::default();
+            file_config.apply_weights(&mut weights);
+            cli.apply_weight_overrides(&mut weights);
             return run_search(
                 &project_dir,
                 &pragma_config,
@@ -157,11 +437,43 @@ fn main() -> std::process::ExitCode {
                     config: SearchConfig {
                         paths_only: *paths,
                         format: output_format,
-                        max_results: DEFAULT_MAX_RESULTS,
+                        max_results: *limit,
+                        offset: *offset,
+                        recency_boost: *recency,
+                        line_ending: *line_ending,
+                        position_ranks: *position_ranks,
+                        verify_exists: *verify_exists,
+                        project_root: Some(project_dir.clone()),
+                        extra_roots: indexer_config().extra_roots,
+                        match_density: *density,
+                        sort: *sort,
+                        snippets: *snippets,
+                        weights,
+                        explain_ranking: *explain_ranking,
+                        query_mode: *mode,
+                        raw_query: *raw_query,
+                        prefix: *prefix,
+                        line_numbers: *line_numbers,
+                        pretty: *json_pretty,
+                        language: lang.clone(),
+                        modified_since: *since,
+                        modified_until: *until,
+                        // Stored sizes are i64; anything larger matches no file anyway
+                        min_size: min_size.map(|n| i64::try_from(n).unwrap_or(i64::MAX)),
+                        max_size: max_size.map(|n| i64::try_from(n).unwrap_or(i64::MAX)),
+                        color: cli.color.enabled(),
+                        files_with_matches: *files_with_matches,
+                        fuzzy: *fuzzy,
+                        highlight: highlight.then(|| HighlightMarkers {
+                            open: highlight_open.clone(),
+                            close: highlight_close.clone(),
+                        }),
+                        ..Default::default()
                     },
                     refresh: cli.refresh,
                     no_auto_init: *no_auto_init,
                     quiet: cli.quiet,
+                    count: *count,
                 },
             );
         }
@@ -175,23 +487,42 @@ fn main() -> std::process::ExitCode {
                     &cli.query,
                     SearchOptions {
                         config: SearchConfig {
-                            paths_only: false,
-                            format: OutputFormat::Plain,
-                            max_results: DEFAULT_MAX_RESULTS,
+                            max_results: DEFAULT_SEARCH_LIMIT,
+                            color: cli.color.enabled(),
+                            ..Default::default()
                         },
                         refresh: cli.refresh,
                         no_auto_init: false, // auto-init enabled for implicit search
                         quiet: cli.quiet,
+                        count: false,
                     },
                 );
             }
 
             // No args - try reading JSON from stdin (Claude Code integration)
-            // Format: {"query": "search term"}
+            // Format: {"query": "search term"}, or a batch: ["term one", "term two"]
             // Only read stdin if it's not a terminal (i.e., data is being piped)
             let stdin = io::stdin();
             if !stdin.is_terminal() {
                 if let Some(Ok(line)) = stdin.lock().lines().next() {
+                    if let Ok(queries) = serde_json::from_str::<Vec<String>>(&line) {
+                        return run_search_batch(
+                            &project_dir,
+                            &pragma_config,
+                            indexer_config(),
+                            &queries,
+                            SearchOptions {
+                                config: SearchConfig {
+                                    max_results: DEFAULT_SEARCH_LIMIT,
+                                    ..Default::default()
+                                },
+                                refresh: cli.refresh,
+                                no_auto_init: false, // auto-init enabled for stdin search
+                                quiet: cli.quiet,
+                                count: false,
+                            },
+                        );
+                    }
                     if let Ok(input) = serde_json::from_str::<StdinQuery>(&line) {
                         let trimmed_query = input.query.trim();
                         if trimmed_query.is_empty() {
@@ -210,13 +541,14 @@ fn main() -> std::process::ExitCode {
                                 &query_parts,
                                 SearchOptions {
                                     config: SearchConfig {
-                                        paths_only: false,
-                                        format: OutputFormat::Plain,
-                                        max_results: DEFAULT_MAX_RESULTS,
+                                        max_results: DEFAULT_SEARCH_LIMIT,
+                                        color: cli.color.enabled(),
+                                        ..Default::default()
                                     },
                                     refresh,
                                     no_auto_init: false, // auto-init enabled for stdin search
                                     quiet: cli.quiet,
+                                    count: false,
                                 },
                             );
                         }
@@ -224,109 +556,813 @@ fn main() -> std::process::ExitCode {
                 }
             }
 
-            if cli.refresh {
-                tracing::error!("--refresh requires a search query or stdin JSON");
-                return ExitCode::DataErr.into();
-            }
+            if cli.refresh {
+                tracing::error!("--refresh requires a search query or stdin JSON");
+                return ExitCode::DataErr.into();
+            }
+        }
+    }
+
+    ExitCode::Ok.into() // OK
+}
+
+fn query_is_empty(parts: &[String]) -> bool {
+    parts.iter().all(|part| part.trim().is_empty())
+}
+
+/// Run indexing operation (incremental or full reindex).
+///
+/// This function orchestrates the complete indexing workflow including database
+/// initialization, reindex logic, error handling, and logging. Keeping it as a
+/// single function maintains clarity of the full operation flow.
+#[allow(clippy::too_many_lines)]
+fn run_indexing(
+    project_dir: &Path,
+    config: &PragmaConfig,
+    indexer_config: IndexerConfig,
+    force_reindex: bool,
+    since: Option<&str>,
+    json: bool,
+    checkpoint: bool,
+) -> std::process::ExitCode {
+    let db_path = indexer_config.database_path(project_dir);
+
+    let stats = if force_reindex {
+        // Atomic reindex with temp file
+        tracing::info!("Running atomic reindex");
+
+        match atomic_reindex_with_config(project_dir, config, indexer_config) {
+            Ok(stats) => stats,
+            Err(e) => {
+                tracing::error!(error = %e, "Atomic reindex failed");
+                return match e {
+                    IndexerError::Io { .. } => ExitCode::IoErr.into(),
+                    _ => ExitCode::Software.into(),
+                };
+            }
+        }
+    } else {
+        // Incremental index (optionally limited to a git diff)
+        match index_incremental(project_dir, &db_path, config, indexer_config, since) {
+            Ok(stats) => stats,
+            Err(e) => {
+                tracing::error!(error = %e, "Indexing failed");
+                return map_index_error(&e);
+            }
+        }
+    };
+    log_index_stats(&stats, "Indexing complete");
+
+    if checkpoint {
+        let result =
+            Database::open(&db_path, config).and_then(|db| db.checkpoint(CheckpointMode::Truncate));
+        match result {
+            Ok((busy, log, checkpointed)) => {
+                tracing::info!(busy, log, checkpointed, "WAL checkpoint complete");
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "WAL checkpoint failed");
+                return map_index_error(&e);
+            }
+        }
+    }
+
+    if json {
+        match serde_json::to_string(&stats) {
+            Ok(line) => println!("{line}"),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize index statistics");
+                return ExitCode::Software.into();
+            }
+        }
+    }
+
+    ExitCode::Ok.into() // OK
+}
+
+fn index_incremental(
+    project_dir: &Path,
+    db_path: &Path,
+    config: &PragmaConfig,
+    indexer_config: IndexerConfig,
+    since: Option<&str>,
+) -> std::result::Result<IndexStats, IndexerError> {
+    let db = Database::open(db_path, config)?;
+    db.init_schema()?;
+    let mut indexer = Indexer::new(project_dir, db, indexer_config);
+    match since {
+        Some(git_ref) => indexer.index_changed_since(git_ref),
+        None => indexer.index_directory(),
+    }
+}
+
+/// Index stdin under a virtual path, bypassing the directory walk.
+fn run_index_stdin(
+    project_dir: &Path,
+    config: &PragmaConfig,
+    indexer_config: IndexerConfig,
+    name: &str,
+) -> std::process::ExitCode {
+    let mut bytes = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut bytes) {
+        tracing::error!(error = %e, "Failed to read stdin");
+        return ExitCode::IoErr.into();
+    }
+    let Ok(content) = String::from_utf8(bytes) else {
+        tracing::error!("stdin is not valid UTF-8 and cannot be indexed");
+        return ExitCode::DataErr.into();
+    };
+
+    let db_path = indexer_config.database_path(project_dir);
+    let result = Database::open(&db_path, config).and_then(|db| {
+        db.init_schema()?;
+        Indexer::new(project_dir, db, indexer_config).add_virtual_file(name, &content)
+    });
+    match result {
+        Ok(()) => ExitCode::Ok.into(),
+        Err(e @ (IndexerError::PathTraversal { .. } | IndexerError::FileTooLarge { .. })) => {
+            tracing::error!(error = %e, name, "Cannot index stdin");
+            ExitCode::DataErr.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to index stdin");
+            map_index_error(&e)
+        }
+    }
+}
+
+/// Print what `index` (or `index --reindex`) would change, without touching the database.
+fn run_index_dry_run(
+    project_dir: &Path,
+    indexer_config: IndexerConfig,
+    reindex: bool,
+) -> std::process::ExitCode {
+    let db_path = indexer_config.database_path(project_dir);
+    if !db_path.exists() {
+        tracing::error!(db_path = %db_path.display(), "No index to compare against - run index first");
+        return ExitCode::NoInput.into();
+    }
+
+    let planned = Database::open_readonly(&db_path).and_then(|db| {
+        let indexer = Indexer::new(project_dir, db, indexer_config);
+        if reindex {
+            let plan = indexer.plan_reindex()?;
+            println!("would add: {}", plan.would_add);
+            println!("would update: {}", plan.would_update);
+            println!("would delete: {}", plan.would_delete);
+            println!("unchanged: {}", plan.unchanged);
+        } else {
+            let plan = indexer.plan_index()?;
+            println!("would add: {}", plan.would_add);
+            println!("would update: {}", plan.would_update);
+            println!("would skip: {}", plan.would_skip);
+            println!("would prune: {}", plan.would_prune);
+            println!("unchanged: {}", plan.unchanged);
+        }
+        Ok(())
+    });
+    if let Err(e) = planned {
+        tracing::error!(error = %e, "Failed to compare tree against index");
+        return map_index_error(&e);
+    }
+    ExitCode::Ok.into()
+}
+
+/// Bring the index up to date, then re-index changed files until interrupted.
+fn run_watch(
+    project_dir: &Path,
+    config: &PragmaConfig,
+    indexer_config: IndexerConfig,
+    debounce: Duration,
+    interval: Duration,
+) -> std::process::ExitCode {
+    let db_path = indexer_config.database_path(project_dir);
+    let opened = Database::open(&db_path, config).and_then(|db| {
+        db.init_schema()?;
+        Ok(Indexer::new(project_dir, db, indexer_config))
+    });
+    let mut indexer = match opened {
+        Ok(indexer) => indexer,
+        Err(e) => {
+            tracing::error!(error = %e, db_path = %db_path.display(), "Failed to open database");
+            return map_index_error(&e);
+        }
+    };
+
+    match indexer.index_directory() {
+        Ok(stats) => log_index_stats(&stats, "Initial index complete"),
+        Err(e) => {
+            tracing::error!(error = %e, "Indexing failed");
+            return map_index_error(&e);
+        }
+    }
+
+    tracing::info!(root = %project_dir.display(), "Watching for changes (Ctrl-C to stop)");
+    if let Err(e) = Watcher::new(indexer, debounce).run(interval) {
+        tracing::error!(error = %e, "Watch failed");
+        return map_index_error(&e);
+    }
+    ExitCode::Ok.into()
+}
+
+/// Remove entries for files deleted from disk, then optionally optimize FTS5.
+///
+/// Entries of extra roots are checked only when those roots are given (`--add-root`).
+fn run_prune(
+    project_dir: &Path,
+    extra_roots: &[PathBuf],
+    db_path: &Path,
+    config: &PragmaConfig,
+    optimize: bool,
+) -> std::process::ExitCode {
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
+
+    let mut db = match Database::open(db_path, config) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into();
+        }
+    };
+
+    let roots = root_prefixes(project_dir, extra_roots);
+    let roots: Vec<(&str, &Path)> =
+        roots.iter().map(|(prefix, dir)| (prefix.as_str(), dir.as_path())).collect();
+    let pruned = match db.prune_missing_files_under(&roots) {
+        Ok(pruned) => pruned,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to prune missing files");
+            return map_index_error(&e);
+        }
+    };
+
+    if optimize {
+        if let Err(e) = db.optimize_fts() {
+            tracing::error!(error = %e, "Failed to optimize FTS5 index");
+            return map_index_error(&e);
+        }
+    }
+
+    println!("pruned: {pruned}");
+    ExitCode::Ok.into()
+}
+
+/// Migrate a legacy schema in place: `migrate_schema`, `init_schema`, `rebuild_fts_index`
+/// (see [`health::migrate_in_place`]).
+fn run_rebuild(db_path: &Path, config: &PragmaConfig) -> std::process::ExitCode {
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
+
+    match health::migrate_in_place(db_path, config) {
+        Ok((migrated, reindexed)) => {
+            println!("migrated: {migrated}");
+            println!("reindexed: {reindexed}");
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Schema rebuild failed");
+            map_index_error(&e)
+        }
+    }
+}
+
+/// Compact the database in place, or into a new file with `--into`.
+fn run_compact(
+    db_path: &Path,
+    config: &PragmaConfig,
+    into: Option<&Path>,
+) -> std::process::ExitCode {
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
+
+    let db = match Database::open(db_path, config) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into();
+        }
+    };
+    let before = db.get_db_size_bytes().unwrap_or(0);
+
+    let result = match into {
+        Some(dest) => db.vacuum_into(dest).map(|()| fs::metadata(dest).map_or(0, |m| m.len())),
+        None => db.vacuum().map(|()| db.get_db_size_bytes().unwrap_or(0)),
+    };
+
+    match result {
+        Ok(after) => {
+            println!("before: {before} bytes");
+            println!("after: {after} bytes");
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to compact database");
+            map_index_error(&e)
+        }
+    }
+}
+
+/// Dump the index as JSON to stdout or `--out`.
+fn run_export(db_path: &Path, out: Option<&Path>, format: DumpFormat) -> std::process::ExitCode {
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
+
+    let db = match Database::open_readonly(db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into();
+        }
+    };
+
+    let result = match out {
+        Some(path) => fs::File::create(path)
+            .map_err(IndexerError::from)
+            .and_then(|file| export_index(&db, &mut io::BufWriter::new(file), format)),
+        None => export_index(&db, &mut io::stdout().lock(), format),
+    };
+
+    match result {
+        Ok(count) => {
+            tracing::info!(files = count, "Exported index");
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to export index");
+            map_index_error(&e)
+        }
+    }
+}
+
+/// Build the index from an export dump read from `input` or stdin.
+fn run_import(
+    db_path: &Path,
+    config: &PragmaConfig,
+    input: Option<&Path>,
+    force: bool,
+) -> std::process::ExitCode {
+    if db_path.exists() && !force {
+        tracing::error!("Database already exists. Use --force to replace it");
+        return ExitCode::DataErr.into();
+    }
+
+    let result = match input {
+        Some(path) => fs::File::open(path)
+            .map_err(IndexerError::from)
+            .and_then(|file| import_into_project(db_path, config, io::BufReader::new(file))),
+        None => import_into_project(db_path, config, io::stdin().lock()),
+    };
+
+    match result {
+        Ok(stats) => {
+            println!("imported: {}", stats.imported);
+            println!("skipped: {}", stats.skipped);
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to import index");
+            // A dump that is not valid JSON is bad input, not an internal failure
+            if matches!(e, IndexerError::Json { .. }) {
+                return ExitCode::DataErr.into();
+            }
+            map_index_error(&e)
+        }
+    }
+}
+
+fn log_index_stats(stats: &IndexStats, message: &str) {
+    tracing::info!(
+        files = stats.files_indexed,
+        bytes = stats.bytes_indexed,
+        pruned = stats.pruned,
+        duration_secs = %format!("{:.2}", stats.duration.as_secs_f64()),
+        "{message}"
+    );
+}
+
+/// One-line summary printed after search auto-indexed the project.
+fn auto_index_summary(stats: &IndexStats) -> String {
+    let noun = if stats.files_indexed == 1 { "file" } else { "files" };
+    format!("auto-indexed {} {noun} before searching", stats.files_indexed)
+}
+
+fn map_index_error(error: &IndexerError) -> std::process::ExitCode {
+    match error {
+        IndexerError::Io { .. } => ExitCode::IoErr.into(),
+        _ => ExitCode::Software.into(),
+    }
+}
+
+/// Run benchmark mode.
+fn run_benchmark(
+    db_path: &Path,
+    config: &PragmaConfig,
+    iterations: u32,
+    stats_json: bool,
+) -> std::process::ExitCode {
+    let db = match Database::open(db_path, config) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into(); // IOERR
+        }
+    };
+
+    if let Err(e) = db.init_schema() {
+        tracing::error!(
+            error = %e,
+            "Failed to initialize schema"
+        );
+        return ExitCode::Software.into(); // SOFTWARE
+    }
+
+    // Check if database has content
+    let file_count = match db.get_file_count() {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                "Failed to get file count"
+            );
+            return ExitCode::Software.into(); // SOFTWARE
+        }
+    };
+
+    if file_count == 0 {
+        tracing::error!("Database is empty - run --index first");
+        return ExitCode::DataErr.into(); // DATAERR
+    }
+
+    tracing::info!(file_count, iterations, "Benchmarking indexed files");
+
+    // Run sample queries
+    let queries = ["main", "test", "use ", "fn ", "struct", "impl"];
+    let mut stats = Vec::with_capacity(queries.len());
+
+    for query in &queries {
+        let mut durations_ms = Vec::with_capacity(iterations as usize);
+        let mut result_count = 0;
+
+        for _ in 0..iterations {
+            let start = std::time::Instant::now();
+            let results = db.search(query, false, 20);
+            let elapsed = start.elapsed();
+
+            match results {
+                Ok(res) => {
+                    result_count = res.len();
+                    durations_ms.push(elapsed.as_secs_f64() * 1000.0);
+                }
+                Err(e) => {
+                    tracing::error!(
+                        query,
+                        error = %e,
+                        "Query failed"
+                    );
+                    break;
+                }
+            }
+        }
+
+        if durations_ms.is_empty() {
+            continue;
+        }
+
+        durations_ms.sort_by(f64::total_cmp);
+        let query_stats = BenchQueryStats {
+            query,
+            results: result_count,
+            p50_ms: percentile(&durations_ms, 50.0),
+            p95_ms: percentile(&durations_ms, 95.0),
+        };
+        tracing::info!(
+            query,
+            results = query_stats.results,
+            p50_ms = %format!("{:.2}", query_stats.p50_ms),
+            p95_ms = %format!("{:.2}", query_stats.p95_ms),
+            "Query benchmark result"
+        );
+        stats.push(query_stats);
+    }
+
+    if stats_json {
+        let report = BenchReport {
+            file_count,
+            db_size_bytes: fs::metadata(db_path).map(|m| m.len()).unwrap_or(0),
+            iterations,
+            queries: stats,
+        };
+        match serde_json::to_string(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize benchmark stats");
+                return ExitCode::Software.into();
+            }
+        }
+    }
+
+    ExitCode::Ok.into() // OK
+}
+
+/// Nearest-rank percentile of an ascending, non-empty slice.
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    // Bounded by sorted.len(), which always fits in f64/usize round-trips here
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Print index statistics, optionally scoped by directory prefix and extension.
+fn run_stats(
+    db_path: &Path,
+    in_dir: Option<&str>,
+    ext: Option<&str>,
+    format: OutputFormat,
+) -> std::process::ExitCode {
+    let db = match Database::open_readonly(db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into();
+        }
+    };
+
+    let report = match stats_report(&db, in_dir, ext) {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to read index statistics");
+            return ExitCode::Software.into();
+        }
+    };
+
+    if format == OutputFormat::Json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize stats");
+                return ExitCode::Software.into();
+            }
+        }
+        return ExitCode::Ok.into();
+    }
+
+    println!("files: {}", report.file_count);
+    println!("indexed bytes: {}", report.total_indexed_bytes);
+    println!("average file bytes: {}", report.average_file_bytes);
+    if let Some(largest) = &report.largest_file {
+        println!("largest file: {} ({} bytes)", largest.path, largest.size_bytes);
+    }
+    println!("database bytes: {}", report.db_size_bytes);
+    println!("journal mode: {}", report.journal_mode);
+    ExitCode::Ok.into()
+}
+
+fn stats_report(
+    db: &Database,
+    in_dir: Option<&str>,
+    ext: Option<&str>,
+) -> std::result::Result<StatsReport, IndexerError> {
+    let total_indexed_bytes = db.get_total_indexed_bytes()?;
+    let total_files = db.get_file_count()?;
+    Ok(StatsReport {
+        file_count: db.get_file_count_filtered(in_dir, ext)?,
+        total_indexed_bytes,
+        average_file_bytes: total_indexed_bytes
+            .checked_div(u64::try_from(total_files).unwrap_or(u64::MAX))
+            .unwrap_or(0),
+        largest_file: db
+            .get_largest_file()?
+            .map(|(path, size_bytes)| LargestFile { path, size_bytes }),
+        db_size_bytes: db.get_db_size_bytes().unwrap_or(0),
+        journal_mode: db.get_journal_mode().unwrap_or_default().to_lowercase(),
+    })
+}
+
+/// Print the content hash of a file exactly as the indexer would store it.
+fn run_hash(file: &Path) -> std::process::ExitCode {
+    let bytes = match fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!(error = %e, path = %file.display(), "Failed to read file");
+            return ExitCode::IoErr.into();
+        }
+    };
+
+    // The indexer only stores valid UTF-8, so hash the same validated string
+    let Ok(content) = String::from_utf8(bytes) else {
+        tracing::error!(path = %file.display(), "File is not valid UTF-8 and would not be indexed");
+        return ExitCode::DataErr.into();
+    };
+
+    println!("{}", Database::content_hash(&content));
+    ExitCode::Ok.into()
+}
+
+/// Write a completion script for `shell` to stdout.
+fn run_completions(shell: clap_complete::Shell) -> std::process::ExitCode {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+    ExitCode::Ok.into()
+}
+
+/// Render the man page to `out`, or stdout.
+fn run_manpage(out: Option<&Path>) -> std::process::ExitCode {
+    let man = clap_mangen::Man::new(Cli::command()).title("FFTS-GREP");
+    let result = match out {
+        Some(path) => {
+            fs::File::create(path).and_then(|file| man.render(&mut io::BufWriter::new(file)))
+        }
+        None => man.render(&mut io::stdout().lock()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::Ok.into(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to write man page");
+            ExitCode::IoErr.into()
+        }
+    }
+}
+
+/// Answer queries from stdin over one database connection until EOF or `:quit`.
+fn run_repl(db_path: &Path, limit: u32, paths: bool) -> std::process::ExitCode {
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
+
+    let mut db = match Database::open_readonly(db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into();
+        }
+    };
+
+    let config = SearchConfig { max_results: limit, paths_only: paths, ..Default::default() };
+    let stdin = io::stdin();
+    let mut repl = Repl::new(&mut db, config);
+    if stdin.is_terminal() {
+        repl = repl.with_prompt("ffts> ");
+    }
+
+    match repl.run(stdin.lock(), &mut io::stdout().lock()) {
+        Ok(queries) => {
+            tracing::info!(queries, "REPL finished");
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "REPL failed");
+            map_index_error(&e)
+        }
+    }
+}
+
+/// Serve HTTP searches on `addr` until SIGTERM or SIGINT.
+fn run_serve(db_path: &Path, addr: &str, workers: usize) -> std::process::ExitCode {
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
+
+    let config = SearchConfig { max_results: DEFAULT_SEARCH_LIMIT, ..Default::default() };
+    let server = match Server::bind(addr, db_path, config) {
+        Ok(server) => server,
+        Err(e) => {
+            tracing::error!(error = %e, addr, "Failed to bind HTTP server");
+            return ExitCode::IoErr.into();
         }
+    };
+    // The bound address goes to stdout so callers using port 0 can find it
+    if let Some(local) = server.local_addr() {
+        println!("Listening on http://{local}");
     }
 
-    ExitCode::Ok.into() // OK
-}
+    #[cfg(unix)]
+    let shutdown = ffts_indexer::daemon::install_shutdown_handler();
+    #[cfg(not(unix))]
+    let shutdown = &std::sync::atomic::AtomicBool::new(false);
 
-fn query_is_empty(parts: &[String]) -> bool {
-    parts.iter().all(|part| part.trim().is_empty())
+    match server.serve(workers, shutdown) {
+        Ok(()) => ExitCode::Ok.into(),
+        Err(e) => {
+            tracing::error!(error = %e, "HTTP server failed");
+            map_index_error(&e)
+        }
+    }
 }
 
-/// Run indexing operation (incremental or full reindex).
-///
-/// This function orchestrates the complete indexing workflow including database
-/// initialization, reindex logic, error handling, and logging. Keeping it as a
-/// single function maintains clarity of the full operation flow.
-#[allow(clippy::too_many_lines)]
-fn run_indexing(
-    project_dir: &Path,
-    config: &PragmaConfig,
-    indexer_config: IndexerConfig,
-    force_reindex: bool,
-    _quiet: bool,
-) -> std::process::ExitCode {
-    let db_path = project_dir.join(DB_NAME);
+/// Serve searches on `socket` until SIGTERM or SIGINT.
+#[cfg(unix)]
+fn run_daemon(db_path: &Path, socket: &Path) -> std::process::ExitCode {
+    use ffts_indexer::daemon::{Daemon, install_shutdown_handler};
 
-    if force_reindex {
-        // Atomic reindex with temp file
-        tracing::info!("Running atomic reindex");
+    if !db_path.exists() {
+        tracing::error!("Database not initialized. Run: ffts-grep init");
+        return ExitCode::DataErr.into();
+    }
 
-        match atomic_reindex_with_config(project_dir, config, indexer_config) {
-            Ok(stats) => {
-                tracing::info!(
-                    files = stats.files_indexed,
-                    bytes = stats.bytes_indexed,
-                    duration_secs = %format!("{:.2}", stats.duration.as_secs_f64()),
-                    "Indexing complete"
-                );
-            }
-            Err(e) => {
-                tracing::error!(error = %e, "Atomic reindex failed");
-                return match e {
-                    IndexerError::Io { .. } => ExitCode::IoErr.into(),
-                    _ => ExitCode::Software.into(),
-                };
-            }
-        }
-    } else {
-        // Incremental index
-        match index_incremental(project_dir, &db_path, config, indexer_config) {
-            Ok(stats) => {
-                log_index_stats(&stats, "Indexing complete");
-            }
-            Err(e) => {
-                tracing::error!(error = %e, "Indexing failed");
-                return map_index_error(&e);
-            }
+    let db = match Database::open_readonly(db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database"
+            );
+            return ExitCode::IoErr.into();
         }
-    }
+    };
 
-    ExitCode::Ok.into() // OK
-}
+    let config = SearchConfig { max_results: DEFAULT_SEARCH_LIMIT, ..Default::default() };
+    let daemon = match Daemon::bind(db, socket, config) {
+        Ok(daemon) => daemon,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to bind daemon socket");
+            return ExitCode::IoErr.into();
+        }
+    };
+    tracing::info!(socket = %daemon.socket().display(), "Daemon listening");
 
-fn index_incremental(
-    project_dir: &Path,
-    db_path: &Path,
-    config: &PragmaConfig,
-    indexer_config: IndexerConfig,
-) -> std::result::Result<IndexStats, IndexerError> {
-    let db = Database::open(db_path, config)?;
-    db.init_schema()?;
-    let mut indexer = Indexer::new(project_dir, db, indexer_config);
-    indexer.index_directory()
+    match daemon.serve(install_shutdown_handler()) {
+        Ok(()) => {
+            tracing::info!("Daemon stopped");
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Daemon failed");
+            map_index_error(&e)
+        }
+    }
 }
 
-fn log_index_stats(stats: &IndexStats, message: &str) {
-    tracing::info!(
-        files = stats.files_indexed,
-        bytes = stats.bytes_indexed,
-        duration_secs = %format!("{:.2}", stats.duration.as_secs_f64()),
-        "{message}"
-    );
-}
+/// Run the interactive fuzzy finder and print the selected path.
+#[cfg(feature = "tui")]
+fn run_interactive(db_path: &Path) -> std::process::ExitCode {
+    // Read-only: the finder only ever searches
+    let mut db = match Database::open_readonly(db_path) {
+        Ok(db) => db,
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                db_path = %db_path.display(),
+                "Failed to open database (run `ffts-grep init` first)"
+            );
+            return ExitCode::IoErr.into();
+        }
+    };
 
-fn map_index_error(error: &IndexerError) -> std::process::ExitCode {
-    match error {
-        IndexerError::Io { .. } => ExitCode::IoErr.into(),
-        _ => ExitCode::Software.into(),
+    let config = SearchConfig { max_results: DEFAULT_SEARCH_LIMIT, ..Default::default() };
+    match ffts_indexer::tui::run(&mut db, &config) {
+        Ok(Some(path)) => {
+            println!("{path}");
+            ExitCode::Ok.into()
+        }
+        Ok(None) => ExitCode::Ok.into(),
+        Err(e) => {
+            tracing::error!(error = %e, "Interactive mode failed");
+            ExitCode::Software.into()
+        }
     }
 }
 
-/// Run benchmark mode.
-fn run_benchmark(
-    project_dir: &Path,
-    config: &PragmaConfig,
-    _quiet: bool,
-) -> std::process::ExitCode {
-    let db_path = project_dir.join(DB_NAME);
-
-    let db = match Database::open(&db_path, config) {
+/// Check a raw FTS5 query for syntax errors without searching.
+fn run_validate_query(db_path: &Path, query: &[String]) -> std::process::ExitCode {
+    let db = match Database::open_readonly(db_path) {
         Ok(db) => db,
         Err(e) => {
             tracing::error!(
@@ -334,86 +1370,135 @@ fn run_benchmark(
                 db_path = %db_path.display(),
                 "Failed to open database"
             );
-            return ExitCode::IoErr.into(); // IOERR
+            return ExitCode::IoErr.into();
         }
     };
 
-    if let Err(e) = db.init_schema() {
-        tracing::error!(
-            error = %e,
-            "Failed to initialize schema"
-        );
-        return ExitCode::Software.into(); // SOFTWARE
+    match db.validate_query(&query.join(" ")) {
+        Ok(()) => {
+            println!("valid");
+            ExitCode::Ok.into()
+        }
+        Err(e @ (IndexerError::QueryParse(_) | IndexerError::EmptyQuery)) => {
+            println!("{e}");
+            ExitCode::DataErr.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to validate query");
+            ExitCode::Software.into()
+        }
     }
+}
 
-    // Check if database has content
-    let file_count = match db.get_file_count() {
-        Ok(count) => count,
+/// Print the FTS5 query plan for a search without executing it.
+fn run_explain_plan(db_path: &Path, query: &[String], paths_only: bool) -> std::process::ExitCode {
+    let sanitized = Searcher::sanitize_query(&query.join(" "));
+    if sanitized.is_empty() {
+        tracing::error!("--explain-plan requires a search query");
+        return ExitCode::DataErr.into();
+    }
+
+    // Read-only: explaining a plan must never touch the index
+    let db = match Database::open_readonly(db_path) {
+        Ok(db) => db,
         Err(e) => {
             tracing::error!(
                 error = %e,
-                "Failed to get file count"
+                db_path = %db_path.display(),
+                "Failed to open database"
             );
-            return ExitCode::Software.into(); // SOFTWARE
+            return ExitCode::IoErr.into();
         }
     };
 
-    if file_count == 0 {
-        tracing::error!("Database is empty - run --index first");
-        return ExitCode::DataErr.into(); // DATAERR
+    match db.explain_query(&sanitized, paths_only) {
+        Ok(plan) => {
+            println!("{plan}");
+            ExitCode::Ok.into()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, query = %sanitized, "Failed to explain query plan");
+            ExitCode::DataErr.into()
+        }
     }
+}
 
-    tracing::info!(file_count, "Benchmarking indexed files");
-
-    // Run sample queries
-    let queries = ["main", "test", "use ", "fn ", "struct", "impl"];
+/// Run search operation with auto-init support.
+///
+/// This function checks database health before searching and can automatically
+/// initialize or repair the database unless `no_auto_init` is set.
+#[allow(clippy::too_many_arguments)]
+fn run_search(
+    project_dir: &Path,
+    config: &PragmaConfig,
+    indexer_config: IndexerConfig,
+    query: &[String],
+    options: SearchOptions,
+) -> std::process::ExitCode {
+    let SearchOptions { config: search_config, refresh, no_auto_init, quiet, count } = options;
+    let query_str = query.join(" ");
+    let mut db =
+        match open_search_db(project_dir, config, indexer_config, refresh, no_auto_init, quiet) {
+            Ok(db) => db,
+            Err(code) => return code,
+        };
 
-    for query in &queries {
-        let start = std::time::Instant::now();
-        let results = db.search(query, false, 20);
-        let elapsed = start.elapsed();
+    let mut searcher = Searcher::new(&mut db, search_config);
 
-        match results {
-            Ok(res) => {
-                tracing::info!(
-                    query,
-                    results = res.len(),
-                    duration_ms = %format!("{:.2}", elapsed.as_secs_f64() * 1000.0),
-                    "Query benchmark result"
-                );
+    if count {
+        return match searcher.count(&query_str) {
+            Ok(n) => {
+                println!("{n}");
+                ExitCode::Ok.into()
             }
             Err(e) => {
+                tracing::error!(error = %e, query = %query_str, "Search count failed");
+                ExitCode::DataErr.into()
+            }
+        };
+    }
+
+    match searcher.search(&query_str) {
+        Ok(results) => {
+            if let Err(e) = searcher.format_results(&results, &mut std::io::stdout()) {
                 tracing::error!(
-                    query,
                     error = %e,
-                    "Query failed"
+                    "Failed to output search results"
                 );
+                return ExitCode::Software.into();
             }
         }
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                query = %query_str,
+                "Search query failed"
+            );
+            return ExitCode::DataErr.into();
+        }
     }
 
-    ExitCode::Ok.into() // OK
+    ExitCode::Ok.into()
 }
 
-/// Run search operation with auto-init support.
+/// Open the database for searching: check health, auto-init, migrate or reinit
+/// unless `no_auto_init`, refresh if asked, then open it with the schema ensured.
 ///
-/// This function checks database health before searching and can automatically
-/// initialize or repair the database unless `no_auto_init` is set.
-#[allow(clippy::too_many_arguments)]
-fn run_search(
+/// On failure the error is logged and the exit code to return is given back.
+fn open_search_db(
     project_dir: &Path,
     config: &PragmaConfig,
     indexer_config: IndexerConfig,
-    query: &[String],
-    options: SearchOptions,
-) -> std::process::ExitCode {
-    let SearchOptions { config: search_config, refresh, no_auto_init, quiet } = options;
-    let db_path = project_dir.join(DB_NAME);
-    let query_str = query.join(" ");
-    let mut already_indexed = false;
+    refresh: bool,
+    no_auto_init: bool,
+    quiet: bool,
+) -> std::result::Result<Database, std::process::ExitCode> {
+    let db_path = indexer_config.database_path(project_dir);
+    // Stats from an auto-init or reinit; also means no refresh is needed
+    let mut auto_indexed: Option<IndexStats> = None;
 
     // Check health and handle auto-init BEFORE opening database
-    let health = health::check_health_fast(project_dir);
+    let health = health::check_health_at(&db_path);
 
     match health {
         DatabaseHealth::Healthy => {
@@ -428,39 +1513,58 @@ fn run_search(
                     "Auto-initializing database"
                 );
             }
-            if let Err(e) =
-                health::auto_init_with_config(project_dir, config, indexer_config.clone(), quiet)
+            match health::auto_init_with_config(project_dir, config, indexer_config.clone(), quiet)
             {
-                tracing::error!(error = %e, "Auto-init failed");
-                return ExitCode::Software.into();
+                Ok(stats) => auto_indexed = Some(stats),
+                Err(e) => {
+                    tracing::error!(error = %e, "Auto-init failed");
+                    return Err(ExitCode::Software.into());
+                }
+            }
+        }
+
+        DatabaseHealth::MigrationNeeded if !no_auto_init => {
+            if !quiet {
+                tracing::info!("Migrating database schema in place");
+            }
+            if let Err(e) = health::migrate_in_place(&db_path, config) {
+                tracing::error!(error = %e, "Schema migration failed");
+                return Err(map_index_error(&e));
             }
-            already_indexed = true;
         }
 
         DatabaseHealth::SchemaInvalid | DatabaseHealth::Corrupted if !no_auto_init => {
             tracing::warn!(health = ?health, "Database corrupted, reinitializing");
-            if let Err(e) = health::backup_and_reinit_with_config(
+            match health::backup_and_reinit_with_config(
                 project_dir,
                 config,
                 indexer_config.clone(),
                 quiet,
             ) {
-                tracing::error!(error = %e, "Reinit failed");
-                return ExitCode::Software.into();
+                Ok(stats) => auto_indexed = Some(stats),
+                Err(e) => {
+                    tracing::error!(error = %e, "Reinit failed");
+                    return Err(ExitCode::Software.into());
+                }
             }
-            already_indexed = true;
         }
 
         DatabaseHealth::Missing | DatabaseHealth::Empty => {
             // --no-auto-init specified
             tracing::error!("Database not initialized. Run: ffts-grep init");
-            return ExitCode::DataErr.into();
+            return Err(ExitCode::DataErr.into());
+        }
+
+        DatabaseHealth::MigrationNeeded => {
+            // --no-auto-init specified
+            tracing::error!("Database schema is outdated. Run: ffts-grep rebuild");
+            return Err(ExitCode::DataErr.into());
         }
 
         DatabaseHealth::SchemaInvalid | DatabaseHealth::Corrupted => {
             // --no-auto-init specified
             tracing::error!("Database corrupted. Run: ffts-grep init --force");
-            return ExitCode::DataErr.into();
+            return Err(ExitCode::DataErr.into());
         }
 
         DatabaseHealth::WrongApplicationId => {
@@ -469,36 +1573,41 @@ fn run_search(
                  Remove manually or use different directory.",
                 DB_NAME
             );
-            return ExitCode::DataErr.into();
+            return Err(ExitCode::DataErr.into());
         }
 
         DatabaseHealth::Unreadable => {
             tracing::error!("Cannot read database - check file permissions");
-            return ExitCode::NoPerm.into();
+            return Err(ExitCode::NoPerm.into());
         }
 
         // Future-proofing: DatabaseHealth is #[non_exhaustive]
         _ => {
             tracing::error!("Unknown database health state");
-            return ExitCode::Software.into();
+            return Err(ExitCode::Software.into());
         }
     }
 
-    if refresh && !already_indexed {
+    if let Some(stats) = &auto_indexed {
+        // stderr keeps stdout clean for piped search results
+        if !quiet {
+            eprintln!("{}", auto_index_summary(stats));
+        }
+    } else if refresh {
         if !quiet {
             tracing::info!("Refreshing index before search");
         }
-        match index_incremental(project_dir, &db_path, config, indexer_config) {
+        match index_incremental(project_dir, &db_path, config, indexer_config, None) {
             Ok(stats) => log_index_stats(&stats, "Index refresh complete"),
             Err(e) => {
                 tracing::error!(error = %e, "Index refresh failed");
-                return map_index_error(&e);
+                return Err(map_index_error(&e));
             }
         }
     }
 
     // Now open the database for search
-    let mut db = match Database::open(&db_path, config) {
+    let db = match Database::open(&db_path, config) {
         Ok(db) => db,
         Err(e) => {
             tracing::error!(
@@ -506,7 +1615,7 @@ fn run_search(
                 db_path = %db_path.display(),
                 "Failed to open database"
             );
-            return ExitCode::IoErr.into();
+            return Err(ExitCode::IoErr.into());
         }
     };
 
@@ -515,38 +1624,66 @@ fn run_search(
             error = %e,
             "Failed to initialize schema"
         );
-        return ExitCode::Software.into();
+        return Err(ExitCode::Software.into());
     }
 
-    let mut searcher = Searcher::new(&mut db, search_config);
+    Ok(db)
+}
 
-    match searcher.search(&query_str) {
-        Ok(results) => {
-            if let Err(e) = searcher.format_results(&results, &mut std::io::stdout()) {
-                tracing::error!(
-                    error = %e,
-                    "Failed to output search results"
-                );
-                return ExitCode::Software.into();
-            }
-        }
+/// Run a batch of queries in one database session (stdin JSON array) and print
+/// a JSON array with one `search --format json` object per query.
+fn run_search_batch(
+    project_dir: &Path,
+    config: &PragmaConfig,
+    indexer_config: IndexerConfig,
+    queries: &[String],
+    options: SearchOptions,
+) -> std::process::ExitCode {
+    let SearchOptions { config: search_config, refresh, no_auto_init, quiet, .. } = options;
+    let mut db =
+        match open_search_db(project_dir, config, indexer_config, refresh, no_auto_init, quiet) {
+            Ok(db) => db,
+            Err(code) => return code,
+        };
+
+    let mut searcher = Searcher::new(&mut db, search_config);
+    let result_sets = match searcher.search_many(queries) {
+        Ok(result_sets) => result_sets,
         Err(e) => {
-            tracing::error!(
-                error = %e,
-                query = %query_str,
-                "Search query failed"
-            );
+            tracing::error!(error = %e, queries = queries.len(), "Batch search failed");
             return ExitCode::DataErr.into();
         }
+    };
+    if let Err(e) = searcher.format_batch(queries, &result_sets, &mut std::io::stdout()) {
+        tracing::error!(error = %e, "Failed to output search results");
+        return ExitCode::Software.into();
     }
-
     ExitCode::Ok.into()
 }
 
 /// Run doctor diagnostic checks.
-fn run_doctor(project_dir: &Path, verbose: bool, format: OutputFormat) -> std::process::ExitCode {
-    let mut doctor = Doctor::new(project_dir, verbose);
-    let summary = doctor.run();
+#[allow(clippy::too_many_arguments)]
+fn run_doctor(
+    project_dir: &Path,
+    db_path: &Path,
+    extra_roots: Vec<PathBuf>,
+    verbose: bool,
+    format: OutputFormat,
+    fail_on: FailOn,
+    journal_mode: &str,
+    fix: Option<(&PragmaConfig, IndexerConfig)>,
+    color: bool,
+) -> std::process::ExitCode {
+    let mut doctor = Doctor::new(project_dir, verbose)
+        .with_db_path(db_path.to_path_buf())
+        .with_extra_roots(extra_roots)
+        .with_fail_on(fail_on)
+        .with_journal_mode(journal_mode)
+        .with_color(color);
+    let mut summary = doctor.run();
+    if let Some((config, indexer_config)) = fix {
+        summary = doctor.fix(config, indexer_config);
+    }
 
     // Output results
     if let Err(e) = doctor.output(&mut std::io::stdout(), format, &summary) {
@@ -557,14 +1694,8 @@ fn run_doctor(project_dir: &Path, verbose: bool, format: OutputFormat) -> std::p
         return ExitCode::Software.into();
     }
 
-    // Return appropriate exit code
-    if summary.has_errors() {
-        ExitCode::DataErr.into() // Database corruption or missing
-    } else if summary.has_warnings() {
-        ExitCode::Software.into() // Non-critical issues
-    } else {
-        ExitCode::Ok.into()
-    }
+    // Errors => DataErr, warnings => Software, filtered by the --fail-on policy
+    summary.exit_code(fail_on).into()
 }
 
 /// Run project initialization.
@@ -581,7 +1712,8 @@ fn run_init(
     quiet: bool,
 ) -> std::process::ExitCode {
     // Update gitignore
-    let gitignore_result = match init::update_gitignore(project_dir) {
+    let db_path = indexer_config.database_path(project_dir);
+    let gitignore_result = match init::update_gitignore_for(project_dir, &db_path) {
         Ok(result) => result,
         Err(e) => {
             tracing::error!(
@@ -608,7 +1740,6 @@ fn run_init(
     }
 
     // Check if database exists
-    let db_path = project_dir.join(DB_NAME);
     let db_exists = db_path.exists();
 
     if db_exists && !force {
@@ -647,11 +1778,9 @@ fn run_init(
     // Create and index database
     if force && db_exists {
         // Delete existing database files for force mode (must append suffix)
-        let shm_filename = format!("{DB_NAME}{DB_SHM_SUFFIX}");
-        let wal_filename = format!("{DB_NAME}{DB_WAL_SUFFIX}");
         let _ = fs::remove_file(&db_path);
-        let _ = fs::remove_file(project_dir.join(&shm_filename));
-        let _ = fs::remove_file(project_dir.join(&wal_filename));
+        let _ = fs::remove_file(db_sibling(&db_path, DB_SHM_SUFFIX));
+        let _ = fs::remove_file(db_sibling(&db_path, DB_WAL_SUFFIX));
     }
 
     let db = match Database::open(&db_path, config) {
@@ -723,11 +1852,21 @@ mod tests {
             &PragmaConfig::default(),
             IndexerConfig::default(),
             true,
+            None,
             true,
+            false,
         );
         assert_eq!(exit, ExitCode::Ok.into());
         assert!(dir.path().join(DB_NAME).exists());
         assert!(!shm_path.exists());
         assert!(!wal_path.exists());
     }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let sorted: Vec<f64> = (1..=20).map(f64::from).collect();
+        assert!((percentile(&sorted, 50.0) - 10.0).abs() < f64::EPSILON);
+        assert!((percentile(&sorted, 95.0) - 19.0).abs() < f64::EPSILON);
+        assert!((percentile(&[3.0], 95.0) - 3.0).abs() < f64::EPSILON);
+    }
 }
@@ -0,0 +1,220 @@
+//! Line-oriented search REPL (`ffts-grep repl`).
+//!
+//! Reads one query per line and prints ranked results after each, until EOF or
+//! `:quit`.
+//!
+//! # Design
+//!
+//! - **One connection**: the database is opened once, so each query skips the
+//!   open/PRAGMA cost of a fresh process
+//! - **Reuses search**: every line is a plain [`Searcher::search`] call with the
+//!   current [`SearchConfig`]
+//! - **Forgiving**: a failed query or bad command prints `error: ...` and the loop
+//!   continues
+//! - **Testable**: input and output are any `BufRead`/`Write`, not the terminal
+//!
+//! # Commands
+//!
+//! | Line | Effect |
+//! |------|--------|
+//! | `:limit N` | Return at most `N` results per query |
+//! | `:paths` | Toggle path-only matching |
+//! | `:help` | List commands |
+//! | `:quit` / `:q` | Leave the REPL |
+
+use std::io::{BufRead, Write};
+
+use crate::cli::MAX_SEARCH_LIMIT;
+use crate::db::Database;
+use crate::error::{IndexerError, Result};
+use crate::search::{SearchConfig, Searcher};
+
+/// Help text printed by `:help`.
+const HELP: &str = "\
+:limit N   return at most N results per query
+:paths     toggle path-only matching
+:quit      leave (also :q or EOF)";
+
+/// What the loop does after one input line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Continue,
+    Quit,
+}
+
+/// Search REPL over one open database.
+pub struct Repl<'a> {
+    db: &'a mut Database,
+    config: SearchConfig,
+    /// Printed before each line is read (`None` when input is not a terminal)
+    prompt: Option<&'static str>,
+}
+
+impl<'a> Repl<'a> {
+    /// Create a REPL searching `db` with `config`; `:limit`/`:paths` mutate the config.
+    pub const fn new(db: &'a mut Database, config: SearchConfig) -> Self {
+        Self { db, config, prompt: None }
+    }
+
+    /// Print `prompt` before reading each line.
+    #[must_use]
+    pub const fn with_prompt(mut self, prompt: &'static str) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// Current search settings.
+    #[must_use]
+    pub const fn config(&self) -> &SearchConfig {
+        &self.config
+    }
+
+    /// Run until EOF or `:quit`; returns the number of queries run.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Io` if reading `input` or writing `output` fails.
+    /// Query and command errors are printed and do not end the loop.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: &mut W) -> Result<usize> {
+        let mut queries = 0;
+        let mut lines = input.lines();
+        loop {
+            if let Some(prompt) = self.prompt {
+                write!(output, "{prompt}")?;
+                output.flush()?;
+            }
+            let Some(line) = lines.next().transpose()? else {
+                break;
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let step = if let Some(command) = line.strip_prefix(':') {
+                self.command(command, output)
+            } else {
+                queries += 1;
+                self.query(line, output)
+            };
+            match step {
+                Ok(Step::Continue) => {}
+                Ok(Step::Quit) => break,
+                Err(IndexerError::Io { source }) => return Err(IndexerError::Io { source }),
+                Err(e) => writeln!(output, "error: {e}")?,
+            }
+        }
+        Ok(queries)
+    }
+
+    /// Run one query and print its results.
+    fn query<W: Write>(&mut self, query: &str, output: &mut W) -> Result<Step> {
+        let mut searcher = Searcher::new(self.db, self.config.clone());
+        let results = searcher.search(query)?;
+        searcher.format_results(&results, output)?;
+        Ok(Step::Continue)
+    }
+
+    /// Apply a `:command` line.
+    fn command<W: Write>(&mut self, command: &str, output: &mut W) -> Result<Step> {
+        let (name, arg) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+        match (name, arg.trim()) {
+            ("q" | "quit", _) => return Ok(Step::Quit),
+            ("limit", n) => {
+                self.config.max_results = match n.parse() {
+                    Ok(n @ 1..=MAX_SEARCH_LIMIT) => n,
+                    _ => {
+                        return Err(IndexerError::ConfigInvalid {
+                            field: "limit".to_string(),
+                            value: n.to_string(),
+                            reason: format!("must be between 1 and {MAX_SEARCH_LIMIT}"),
+                        });
+                    }
+                };
+                writeln!(output, "limit: {}", self.config.max_results)?;
+            }
+            ("paths", _) => {
+                self.config.paths_only = !self.config.paths_only;
+                writeln!(
+                    output,
+                    "paths only: {}",
+                    if self.config.paths_only { "on" } else { "off" }
+                )?;
+            }
+            ("help", _) => writeln!(output, "{HELP}")?,
+            _ => {
+                return Err(IndexerError::ConfigInvalid {
+                    field: "command".to_string(),
+                    value: format!(":{name}"),
+                    reason: "unknown command (try :help)".to_string(),
+                });
+            }
+        }
+        Ok(Step::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::PragmaConfig;
+    use std::io::Cursor;
+    use tempfile::tempdir;
+
+    fn test_db() -> (tempfile::TempDir, Database) {
+        let dir = tempdir().unwrap();
+        let db = Database::open(&dir.path().join("test.db"), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/alpha.rs", "fn alpha() { shared(); }", 0, 24).unwrap();
+        db.upsert_file("src/beta.rs", "fn beta() { shared(); }", 0, 23).unwrap();
+        (dir, db)
+    }
+
+    #[test]
+    fn test_two_queries_both_print_results() {
+        let (_dir, mut db) = test_db();
+        let mut output = Vec::new();
+        let queries = Repl::new(&mut db, SearchConfig::default())
+            .run(Cursor::new("alpha\nbeta\n"), &mut output)
+            .unwrap();
+
+        assert_eq!(queries, 2);
+        assert_eq!(String::from_utf8(output).unwrap(), "src/alpha.rs\nsrc/beta.rs\n");
+    }
+
+    #[test]
+    fn test_commands_mutate_config_and_quit_stops() {
+        let (_dir, mut db) = test_db();
+        let mut output = Vec::new();
+        let mut repl = Repl::new(&mut db, SearchConfig::default());
+        let input = ":limit 1\n:paths\nshared\n:paths\nshared\n:quit\nalpha\n";
+        let queries = repl.run(Cursor::new(input), &mut output).unwrap();
+
+        assert_eq!(queries, 2, "lines after :quit are not read");
+        assert_eq!(repl.config().max_results, 1);
+        assert!(!repl.config().paths_only);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "limit: 1");
+        assert_eq!(lines[1], "paths only: on");
+        assert_eq!(lines[2], "paths only: off");
+        assert!(lines[3].starts_with("src/"), "{output}");
+        assert_eq!(lines.len(), 4, "{output}");
+    }
+
+    #[test]
+    fn test_errors_do_not_end_the_loop() {
+        let (_dir, mut db) = test_db();
+        let mut output = Vec::new();
+        let input = ":limit zero\n:frobnicate\nbeta\n";
+        let queries = Repl::new(&mut db, SearchConfig::default())
+            .run(Cursor::new(input), &mut output)
+            .unwrap();
+
+        assert_eq!(queries, 1);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].starts_with("error: ") && lines[0].contains("limit"), "{output}");
+        assert!(lines[1].starts_with("error: ") && lines[1].contains(":frobnicate"), "{output}");
+        assert_eq!(lines[2], "src/beta.rs");
+    }
+}
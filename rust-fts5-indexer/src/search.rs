@@ -1,52 +1,305 @@
 use serde::Serialize;
 use std::collections::HashSet;
 use std::io::Write;
+use std::path::PathBuf;
 
-use crate::db::{Database, SearchResult};
-use crate::error::Result;
+use crate::db::{
+    Bm25Weights, ColumnSet, Database, FILENAME_MATCH_RANK, LineMatch, MatchDensity, RankBreakdown,
+    SearchFilter, SearchResult, SnippetStyle, age_factor, query_terms, resolve_stored_path,
+    result_capacity,
+};
+use crate::error::{IndexerError, Result};
+use crate::indexer::root_prefixes;
 
-pub use crate::cli::OutputFormat;
+pub use crate::cli::{LineEnding, OutputFormat, SortOrder};
 
 /// Configuration for search operations.
 #[derive(Debug, Clone)]
 pub struct SearchConfig {
-    /// Search paths only (no content)
+    /// Search paths only (no content); compatibility shim for `columns: PATH`
     pub paths_only: bool,
+    /// FTS5 columns to search (ignored when `paths_only` is set)
+    pub columns: ColumnSet,
     /// Output format
     pub format: OutputFormat,
     /// Maximum results to return
     pub max_results: u32,
+    /// Results to skip before the first returned one (pagination)
+    pub offset: u32,
+    /// Recency weighting: adds `boost * age_factor` to each rank so newer files
+    /// win ties (`None` = pure BM25 ordering)
+    pub recency_boost: Option<f64>,
+    /// Attach content snippets to the top N results only; the rest stay path-only
+    /// (`None` = no snippets)
+    pub snippet_top_n: Option<usize>,
+    /// Attach highlighted snippets (`<b>match</b>`) to results; limited by
+    /// `snippet_top_n` when set, skipped in `paths_only` mode
+    pub snippets: bool,
+    /// Line terminator for formatted output (default `Lf` for cross-platform consistency)
+    pub line_ending: LineEnding,
+    /// Emit 1-based result positions instead of BM25 scores as `rank` in output
+    pub position_ranks: bool,
+    /// Populate `SearchResult::filename` from the stored column (costs a join)
+    pub with_filename: bool,
+    /// Drop results whose file no longer exists on disk (deleted since the last
+    /// index run); costs one `stat` per result. Requires `project_root`
+    pub verify_exists: bool,
+    /// Project root the stored paths are relative to (used by `verify_exists`)
+    pub project_root: Option<PathBuf>,
+    /// Extra index roots, so `@label/...` paths resolve as the indexer stored them
+    /// (see [`IndexerConfig::extra_roots`](crate::indexer::IndexerConfig::extra_roots))
+    pub extra_roots: Vec<PathBuf>,
+    /// Annotate results with [`MatchDensity`] (reads each result's stored content)
+    pub match_density: bool,
+    /// Result ordering (`Density` implies `match_density`)
+    pub sort: SortOrder,
+    /// How multi-word queries combine (see [`Searcher::build_fts_query`])
+    pub query_mode: QueryMode,
+    /// Pass the query to FTS5 unchanged (operators, `*`, column filters); ignores `query_mode`
+    pub raw_query: bool,
+    /// Treat the last query word as a prefix (see [`Database::search_prefix`])
+    pub prefix: bool,
+    /// BM25 column weights for FTS5 ranking (default filename=100, path=50, content=1)
+    pub weights: Bm25Weights,
+    /// Attach a per-column [`RankBreakdown`] to each result (three extra `bm25()`
+    /// evaluations per result; diagnostic only)
+    pub explain_ranking: bool,
+    /// Attach the content lines containing query terms (see [`Database::locate_matches`]);
+    /// plain output becomes one `path:line: text` row per matching line
+    pub line_numbers: bool,
+    /// Indent JSON output (`OutputFormat::Json` only); compact single-line JSON otherwise
+    pub pretty: bool,
+    /// Only return files whose detected language is this (see [`detect_language`](crate::language::detect_language))
+    pub language: Option<String>,
+    /// Only return files modified at or after this time (Unix seconds)
+    pub modified_since: Option<i64>,
+    /// Only return files modified at or before this time (Unix seconds)
+    pub modified_until: Option<i64>,
+    /// Only return files of at least this many bytes
+    pub min_size: Option<i64>,
+    /// Only return files of at most this many bytes
+    pub max_size: Option<i64>,
+    /// Bold the file name in plain output (see [`ColorChoice`](crate::cli::ColorChoice))
+    pub color: bool,
+    /// Output only the matching file paths, once each (`grep -l`); JSON becomes
+    /// `{"query", "count", "files": [path, ...]}`
+    pub files_with_matches: bool,
+    /// Also match filenames within [`FUZZY_MAX_DISTANCE`] edits of the first query
+    /// word (see [`Database::search_filename_fuzzy`]), after exact filename matches
+    pub fuzzy: bool,
+    /// Attach each result's content with matched tokens wrapped in these markers
+    /// (see [`Database::highlight`]); ignored with `paths_only`
+    pub highlight: Option<HighlightMarkers>,
 }
 
 impl Default for SearchConfig {
     fn default() -> Self {
-        Self { paths_only: false, format: OutputFormat::Plain, max_results: 15 }
+        Self {
+            paths_only: false,
+            columns: ColumnSet::ALL,
+            format: OutputFormat::Plain,
+            max_results: 15,
+            offset: 0,
+            recency_boost: None,
+            snippet_top_n: None,
+            snippets: false,
+            line_ending: LineEnding::Lf,
+            position_ranks: false,
+            with_filename: false,
+            verify_exists: false,
+            project_root: None,
+            extra_roots: Vec::new(),
+            match_density: false,
+            sort: SortOrder::Rank,
+            query_mode: QueryMode::And,
+            raw_query: false,
+            prefix: false,
+            weights: Bm25Weights::default(),
+            explain_ranking: false,
+            line_numbers: false,
+            pretty: false,
+            language: None,
+            modified_since: None,
+            modified_until: None,
+            min_size: None,
+            max_size: None,
+            color: false,
+            files_with_matches: false,
+            fuzzy: false,
+            highlight: None,
+        }
+    }
+}
+
+/// Strings wrapped around matched tokens by [`SearchConfig::highlight`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightMarkers {
+    pub open: String,
+    pub close: String,
+}
+
+impl Default for HighlightMarkers {
+    fn default() -> Self {
+        Self { open: "[".to_string(), close: "]".to_string() }
+    }
+}
+
+/// How the words of a multi-word query are combined into an FTS5 expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryMode {
+    /// Every word must match, anywhere in the file (default)
+    #[default]
+    And,
+    /// Any word may match
+    Or,
+    /// The words must appear consecutively, in order
+    Phrase,
+    /// All words within this many tokens of each other
+    Near(u32),
+}
+
+/// Default `NEAR` distance, matching FTS5's own default.
+pub const DEFAULT_NEAR_DISTANCE: u32 = 10;
+
+impl std::str::FromStr for QueryMode {
+    type Err = String;
+
+    /// Parse `and`, `or`, `phrase`, `near` or `near:N` (case-insensitive).
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        match lower.as_str() {
+            "and" => Ok(Self::And),
+            "or" => Ok(Self::Or),
+            "phrase" => Ok(Self::Phrase),
+            "near" => Ok(Self::Near(DEFAULT_NEAR_DISTANCE)),
+            _ => lower
+                .strip_prefix("near:")
+                .and_then(|n| n.parse().ok())
+                .map(Self::Near)
+                .ok_or_else(|| "must be and, or, phrase, near, or near:N".to_string()),
+        }
     }
 }
 
+/// Make arbitrary user input safe for FTS5 `MATCH`.
+///
+/// Splits on whitespace and double-quotes every term (embedded `"` doubled), so
+/// `test-function`, `foo.bar` or `a(b)` become phrase queries the tokenizer splits
+/// the same way it split the indexed text, instead of FTS5 syntax errors. Operators
+/// (`AND`, `OR`, `NOT`, `NEAR`, `*`) are matched literally; callers that want them
+/// must pass the raw expression (e.g. [`Database::search_raw`]).
+#[must_use]
+pub fn sanitize_fts_query(raw: &str) -> String {
+    raw.split_whitespace().map(quote_term).collect::<Vec<_>>().join(" ")
+}
+
+/// Wrap one term in FTS5 double quotes, doubling any embedded quotes.
+fn quote_term(term: &str) -> String {
+    format!("\"{}\"", term.replace('"', "\"\""))
+}
+
+/// Largest edit distance accepted by fuzzy filename matching ([`SearchConfig::fuzzy`]).
+pub const FUZZY_MAX_DISTANCE: usize = 2;
+
 /// Search result for JSON output.
 #[derive(Debug, Serialize)]
 pub struct JsonSearchResult<'a> {
     pub path: &'a str,
-    pub rank: f64,
+    pub rank: JsonRank,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filename: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matches: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub density: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranking: Option<JsonRankBreakdown>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_lines: Option<Vec<JsonLineMatch<'a>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highlighted: Option<&'a str>,
+}
+
+/// A matching content line for JSON output (`--line-numbers`).
+///
+/// Named `match_lines` in results because `lines` already holds the line count
+/// reported by `--density`.
+#[derive(Debug, Serialize)]
+pub struct JsonLineMatch<'a> {
+    pub line: u32,
+    pub text: &'a str,
+}
+
+impl<'a> From<&'a LineMatch> for JsonLineMatch<'a> {
+    fn from(m: &'a LineMatch) -> Self {
+        Self { line: m.line, text: &m.text }
+    }
+}
+
+/// Per-column BM25 scores for JSON output (`--explain-ranking`).
+#[derive(Debug, Serialize)]
+pub struct JsonRankBreakdown {
+    pub filename: f64,
+    pub path: f64,
+    pub content: f64,
+}
+
+impl From<RankBreakdown> for JsonRankBreakdown {
+    fn from(b: RankBreakdown) -> Self {
+        Self { filename: b.filename, path: b.path, content: b.content }
+    }
+}
+
+/// Rank as emitted in JSON: raw BM25 score or 1-based result position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum JsonRank {
+    /// BM25 score (lower = better)
+    Score(f64),
+    /// 1-based position in the result list
+    Position(usize),
 }
 
 /// JSON output structure.
+///
+/// Keys always serialize in declaration order: `query`, `count`, `results`.
 #[derive(Debug, Serialize)]
 pub struct JsonOutput<'a> {
+    /// Query passed to the last [`Searcher::search`] call
+    pub query: &'a str,
+    /// Number of entries in `results`
+    pub count: usize,
     pub results: Vec<JsonSearchResult<'a>>,
 }
 
+/// JSON output of a files-with-matches search.
+///
+/// Keys always serialize in declaration order: `query`, `count`, `files`.
+#[derive(Debug, Serialize)]
+pub struct JsonFilesOutput<'a> {
+    /// Query passed to the last [`Searcher::search`] call
+    pub query: &'a str,
+    /// Number of entries in `files`
+    pub count: usize,
+    pub files: Vec<&'a str>,
+}
+
 /// FTS5 search executor.
 pub struct Searcher<'a> {
     db: &'a mut Database,
     config: SearchConfig,
+    /// Query of the last `search` call, echoed in JSON output
+    query: String,
 }
 
 impl<'a> Searcher<'a> {
     /// Create a new searcher.
     pub const fn new(db: &'a mut Database, config: SearchConfig) -> Self {
-        Self { db, config }
+        Self { db, config, query: String::new() }
     }
 
     /// Execute a search query with two-phase search.
@@ -64,40 +317,121 @@ impl<'a> Searcher<'a> {
     /// - Database query execution fails
     /// - FTS5 MATCH syntax is invalid (after sanitization)
     pub fn search(&mut self, query: &str) -> Result<Vec<SearchResult>> {
+        query.clone_into(&mut self.query);
         let sanitized = Self::sanitize_query(query);
 
         if sanitized.trim().is_empty() {
             return Ok(vec![]);
         }
+        let fts_query = self.fts_query(query, &sanitized);
 
-        let max = self.config.max_results as usize;
+        // Pages are cut from the merged ranking, so fetch everything up to the page end
+        let fetch = self.config.max_results.saturating_add(self.config.offset);
+        let max = fetch as usize;
+        let columns = self.columns();
 
         // Phase A: Filename CONTAINS matches (absolute priority)
         // Use first token for filename search (most relevant for file lookup)
+        // Skipped when neither filename nor path is searched (e.g. content-only).
+        // In prefix mode only filename words starting with the token qualify.
         let filename_query = sanitized.split_whitespace().next().unwrap_or(&sanitized);
-        let filename_matches =
-            self.db.search_filename_contains(filename_query, self.config.max_results)?;
+        let mut filename_matches = if !columns.intersects(ColumnSet::FILENAME | ColumnSet::PATH) {
+            Vec::new()
+        } else if self.config.prefix {
+            self.db.search_filename_prefix(filename_query, fetch)?
+        } else {
+            self.db.search_filename_contains(filename_query, fetch)?
+        };
+        // Typo-tolerant names rank after every exact filename match
+        if self.config.fuzzy && columns.intersects(ColumnSet::FILENAME | ColumnSet::PATH) {
+            for path in self.db.search_filename_fuzzy(filename_query, fetch, FUZZY_MAX_DISTANCE)? {
+                if filename_matches.len() >= max {
+                    break;
+                }
+                if !filename_matches.contains(&path) {
+                    filename_matches.push(path);
+                }
+            }
+        }
+        // Same restrictions as Phase B (the stored language is derived from the path)
+        let filter = self.filter();
+        if filter.restricts() {
+            let mut kept = Vec::with_capacity(filename_matches.len());
+            for path in filename_matches {
+                let Some(meta) = self.db.get_file_metadata(&path)? else { continue };
+                if filter.accepts(&path, meta.mtime, meta.size) {
+                    kept.push(path);
+                }
+            }
+            filename_matches = kept;
+        }
 
-        let mut seen: HashSet<String> = HashSet::with_capacity(max);
-        let mut results: Vec<SearchResult> = Vec::with_capacity(max);
+        let capacity = result_capacity(fetch);
+        let mut seen: HashSet<String> = HashSet::with_capacity(capacity);
+        let mut results: Vec<SearchResult> = Vec::with_capacity(capacity);
 
-        // Add filename matches first with synthetic high-priority rank (-1000.0)
-        // Lower rank = better match in BM25, so -1000.0 ensures filename matches come first
+        // Add filename matches first with synthetic high-priority rank (FILENAME_MATCH_RANK)
+        // Lower rank = better match in BM25, so this ensures filename matches come first
         for path in filename_matches {
             if results.len() >= max {
                 break;
             }
             seen.insert(path.clone());
-            results.push(SearchResult { path, rank: -1000.0 });
+            let filename =
+                if self.config.with_filename { self.db.get_filename(&path)? } else { None };
+            results.push(SearchResult {
+                path,
+                rank: FILENAME_MATCH_RANK,
+                snippet: None,
+                filename,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            });
         }
 
-        // Phase B: FTS5 BM25 for remaining slots (content/path matches)
+        let filename_hits = results.len();
+
+        // Phase B: FTS5 BM25 for remaining slots (content/path matches); the recency
+        // blend is part of its SQL ordering, so it applies before the limit
         if results.len() < max {
             // Request more results to account for deduplication.
             // This is bounded by max_results (u32), but stay defensive.
             let fts_limit =
                 u32::try_from(max.saturating_sub(results.len()) + seen.len()).unwrap_or(u32::MAX);
-            let fts_results = self.db.search(&sanitized, self.config.paths_only, fts_limit)?;
+            let fts_results = if filter.restricts()
+                || filter.sort.is_file_order()
+                || filter.recency_boost.is_some()
+            {
+                let mut results = self.db.search_columns_filtered(
+                    &fts_query,
+                    columns,
+                    self.config.weights,
+                    &filter,
+                    fts_limit,
+                )?;
+                if self.config.with_filename {
+                    for result in &mut results {
+                        result.filename = self.db.get_filename(&result.path)?;
+                    }
+                }
+                results
+            } else if self.config.with_filename {
+                self.db.search_columns_with_filename(
+                    &fts_query,
+                    columns,
+                    self.config.weights,
+                    fts_limit,
+                )?
+            } else {
+                self.db.search_columns_weighted(
+                    &fts_query,
+                    columns,
+                    self.config.weights,
+                    fts_limit,
+                )?
+            };
 
             for result in fts_results {
                 if results.len() >= max {
@@ -111,9 +445,239 @@ impl<'a> Searcher<'a> {
             }
         }
 
+        if let Some(boost) = self.config.recency_boost {
+            self.apply_recency_boost(&mut results[..filename_hits], boost)?;
+            // Stable sort keeps the two-phase order among equal ranks
+            results.sort_by(|a, b| a.rank.total_cmp(&b.rank));
+        }
+
+        if self.config.match_density || self.config.sort == SortOrder::Density {
+            self.apply_match_density(&mut results, &sanitized)?;
+        }
+
+        if self.config.sort.is_file_order() {
+            self.apply_file_order(&mut results)?;
+        }
+
+        let skip = (self.config.offset as usize).min(results.len());
+        results.drain(..skip);
+
+        // Stored paths are relative to their index root, wherever the database lives;
+        // paths of roots not configured here cannot be checked and are kept
+        if self.config.verify_exists {
+            let Some(project_root) = &self.config.project_root else {
+                return Err(IndexerError::ConfigInvalid {
+                    field: "project_root".to_string(),
+                    value: String::new(),
+                    reason: "required by verify_exists".to_string(),
+                });
+            };
+            let roots = root_prefixes(project_root, &self.config.extra_roots);
+            results.retain(|result| {
+                resolve_stored_path(&roots, &result.path).is_none_or(|path| path.exists())
+            });
+        }
+
+        if self.config.explain_ranking {
+            let match_expr = columns.filter_expression(&fts_query);
+            for result in &mut results {
+                result.ranking =
+                    self.db.explain_rank(&match_expr, &result.path, self.config.weights)?;
+            }
+        }
+
+        // A file list needs no per-result detail
+        if self.config.files_with_matches {
+            return Ok(results);
+        }
+
+        let wants_lines = self.config.line_numbers || self.config.format == OutputFormat::Grep;
+        if wants_lines && !self.config.paths_only {
+            for result in &mut results {
+                result.line_matches = Some(self.db.locate_matches(&result.path, &sanitized)?);
+            }
+        }
+
+        // Snippets are the expensive part; only generate them where users look
+        if self.config.snippets && !self.config.paths_only {
+            let top_n = self.config.snippet_top_n.unwrap_or(usize::MAX);
+            for result in results.iter_mut().take(top_n) {
+                // Filename/path-only matches have no content context: empty, not None
+                let snippet =
+                    self.db.snippet(&fts_query, &result.path, SnippetStyle::HIGHLIGHTED)?;
+                result.snippet = Some(snippet.unwrap_or_default());
+            }
+        } else if let Some(top_n) = self.config.snippet_top_n {
+            for result in results.iter_mut().take(top_n) {
+                result.snippet = self.db.snippet(&fts_query, &result.path, SnippetStyle::PLAIN)?;
+            }
+        }
+
+        if let Some(markers) = self.config.highlight.as_ref().filter(|_| !self.config.paths_only) {
+            for result in &mut results {
+                result.highlighted =
+                    self.db.highlight(&fts_query, &result.path, &markers.open, &markers.close)?;
+            }
+        }
+
         Ok(results)
     }
 
+    /// Count the files matching `query`, ignoring `max_results` and `offset`.
+    ///
+    /// Counts the same FTS5 expression [`search`](Self::search) runs (sanitized,
+    /// auto-prefixed, per mode): with `COUNT(*)` when no filter applies, else by
+    /// the rows of the filtered query. Filename substring matches that FTS5 does not
+    /// see are not counted.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if the FTS5 query fails.
+    pub fn count(&mut self, query: &str) -> Result<usize> {
+        query.clone_into(&mut self.query);
+        let sanitized = Self::sanitize_query(query);
+        if sanitized.trim().is_empty() {
+            return Ok(0);
+        }
+
+        let fts_query = self.fts_query(query, &sanitized);
+        let filter = self.filter();
+        if !filter.restricts() {
+            return self.db.count_columns(&fts_query, self.columns());
+        }
+
+        let matches = self.db.search_columns_filtered(
+            &fts_query,
+            self.columns(),
+            self.config.weights,
+            &filter,
+            u32::MAX,
+        )?;
+        Ok(matches.len())
+    }
+
+    /// FTS5 expression for `query` under the configured mode, prefix and raw settings.
+    fn fts_query(&self, query: &str, sanitized: &str) -> String {
+        if self.config.raw_query {
+            query.trim().to_string()
+        } else if self.config.prefix && !sanitized.ends_with('*') {
+            Self::build_fts_query(&format!("{sanitized}*"), self.config.query_mode)
+        } else {
+            Self::build_fts_query(sanitized, self.config.query_mode)
+        }
+    }
+
+    /// Language, modification-time window and order from the config.
+    fn filter(&self) -> SearchFilter<'_> {
+        SearchFilter {
+            language: self.config.language.as_deref(),
+            modified_since: self.config.modified_since,
+            modified_until: self.config.modified_until,
+            min_size: self.config.min_size,
+            max_size: self.config.max_size,
+            sort: self.config.sort,
+            recency_boost: self.config.recency_boost,
+        }
+    }
+
+    /// Effective column set: `paths_only` maps to `{path}` for compatibility.
+    const fn columns(&self) -> ColumnSet {
+        if self.config.paths_only { ColumnSet::PATH } else { self.config.columns }
+    }
+
+    /// Blend the ranks of Phase A filename matches with file age (lower rank = better).
+    ///
+    /// Older files are penalized by up to `boost` (see [`age_factor`]), the same
+    /// blend Phase B applies in SQL through [`SearchFilter::recency_boost`].
+    fn apply_recency_boost(&self, results: &mut [SearchResult], boost: f64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+
+        for result in results.iter_mut() {
+            let Some(mtime) = self.db.get_mtime(&result.path)? else { continue };
+            result.rank += boost * age_factor(mtime, now);
+        }
+        Ok(())
+    }
+
+    /// Measure [`MatchDensity`] per result; with `SortOrder::Density`, re-sort densest first.
+    fn apply_match_density(&self, results: &mut [SearchResult], sanitized: &str) -> Result<()> {
+        let terms = query_terms(sanitized);
+
+        for result in results.iter_mut() {
+            result.density = self
+                .db
+                .get_file_content(&result.path)?
+                .map(|content| MatchDensity::measure(&content, &terms));
+        }
+
+        if self.config.sort == SortOrder::Density {
+            let ratio = |r: &SearchResult| r.density.map_or(-1.0, MatchDensity::ratio);
+            // Stable sort keeps rank order among equal densities
+            results.sort_by(|a, b| ratio(b).total_cmp(&ratio(a)));
+        }
+        Ok(())
+    }
+
+    /// Re-sort merged results by path, mtime or size (`SortOrder::is_file_order`).
+    ///
+    /// Phase B already arrives in this order; this places the filename matches
+    /// from Phase A among them. Stable, so equal keys keep their rank order.
+    fn apply_file_order(&self, results: &mut Vec<SearchResult>) -> Result<()> {
+        if self.config.sort == SortOrder::PathAsc {
+            results.sort_by(|a, b| a.path.cmp(&b.path));
+            return Ok(());
+        }
+
+        let mut keyed = Vec::with_capacity(results.len());
+        for result in results.drain(..) {
+            let key = self.db.get_file_metadata(&result.path)?.map_or(i64::MIN, |meta| {
+                if self.config.sort == SortOrder::MtimeDesc { meta.mtime } else { meta.size }
+            });
+            keyed.push((key, result));
+        }
+        keyed.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+        results.extend(keyed.into_iter().map(|(_, result)| result));
+        Ok(())
+    }
+
+    /// Build the FTS5 MATCH expression for a sanitized query.
+    ///
+    /// Every token is double-quoted, so words like `AND`, `OR`, `NOT` or `NEAR` in
+    /// user input are searched literally instead of acting as operators. A trailing
+    /// `*` from auto-prefix stays a prefix marker on the last token (or phrase).
+    ///
+    /// - `And`: `"main" "function"`
+    /// - `Or`: `"main" OR "function"`
+    /// - `Phrase`: `"main function"`
+    /// - `Near(5)`: `NEAR("main" "function", 5)`
+    #[must_use]
+    pub fn build_fts_query(sanitized: &str, mode: QueryMode) -> String {
+        let (body, prefix) = match sanitized.strip_suffix('*') {
+            Some(body) => (body, "*"),
+            None => (sanitized, ""),
+        };
+        let tokens: Vec<&str> = body.split_whitespace().collect();
+        if tokens.is_empty() {
+            return String::new();
+        }
+
+        let quoted = |sep: &str| {
+            let mut terms: Vec<String> = tokens.iter().map(|token| quote_term(token)).collect();
+            if let Some(last) = terms.last_mut() {
+                last.push_str(prefix);
+            }
+            terms.join(sep)
+        };
+
+        match mode {
+            QueryMode::And => quoted(" "),
+            QueryMode::Or => quoted(" OR "),
+            QueryMode::Phrase => format!("{}{prefix}", quote_term(&tokens.join(" "))),
+            // NEAR needs at least two phrases; a single token is just that token
+            QueryMode::Near(_) if tokens.len() < 2 => quoted(" "),
+            QueryMode::Near(distance) => format!("NEAR({}, {distance})", quoted(" ")),
+        }
+    }
+
     /// Sanitize query for FTS5 MATCH with auto-prefix detection.
     ///
     /// Replaces FTS5 special characters with spaces to prevent
@@ -177,6 +741,56 @@ impl<'a> Searcher<'a> {
         result
     }
 
+    /// Run several queries with the same settings, returning one result set per
+    /// query in order.
+    ///
+    /// Equivalent to a [`Self::search`] call per query in one database session: the
+    /// connection's statement cache keeps each prepared FTS5 statement, so only the
+    /// first query pays for preparing it.
+    ///
+    /// # Errors
+    /// Returns the first error of [`Self::search`]; later queries are not run.
+    pub fn search_many(&mut self, queries: &[String]) -> Result<Vec<Vec<SearchResult>>> {
+        queries.iter().map(|query| self.search(query)).collect()
+    }
+
+    /// Write [`Self::search_many`] results as one JSON array with a
+    /// [`JsonOutput`] per query (the object `--format json` prints), regardless
+    /// of the configured format.
+    ///
+    /// # Errors
+    /// Returns `IndexerError` if writing or JSON serialization fails.
+    pub fn format_batch<W: Write>(
+        &self,
+        queries: &[String],
+        result_sets: &[Vec<SearchResult>],
+        output: &mut W,
+    ) -> Result<()> {
+        let eol = self.config.line_ending.as_str();
+        let outputs: Vec<JsonOutput<'_>> = queries
+            .iter()
+            .zip(result_sets)
+            .map(|(query, results)| JsonOutput {
+                query,
+                count: results.len(),
+                results: results
+                    .iter()
+                    .enumerate()
+                    .map(|(i, r)| Self::json_result(i, r, self.config.position_ranks))
+                    .collect(),
+            })
+            .collect();
+
+        if self.config.pretty {
+            let json = serde_json::to_string_pretty(&outputs)?;
+            write!(output, "{}{eol}", json.replace('\n', eol))?;
+        } else {
+            serde_json::to_writer(&mut *output, &outputs)?;
+            write!(output, "{eol}")?;
+        }
+        Ok(())
+    }
+
     /// Format and output search results.
     ///
     /// # Errors
@@ -184,31 +798,204 @@ impl<'a> Searcher<'a> {
     /// - Writing to the output stream fails (wrapped as `IndexerError::Io`)
     /// - JSON serialization fails (when using JSON format)
     pub fn format_results<W: Write>(&self, results: &[SearchResult], output: &mut W) -> Result<()> {
+        let eol = self.config.line_ending.as_str();
+        if self.config.files_with_matches {
+            return self.format_files(results, output, eol);
+        }
         match self.config.format {
-            OutputFormat::Plain => Self::format_plain(results, output),
-            OutputFormat::Json => Self::format_json(results, output),
+            OutputFormat::Plain => Self::format_plain(results, output, eol, self.config.color),
+            OutputFormat::Json => self.format_json(results, output, eol),
+            OutputFormat::Grep => Self::format_grep(results, output, eol),
+            OutputFormat::Ndjson => {
+                Self::format_ndjson(results, output, eol, self.config.position_ranks)
+            }
+        }
+    }
+
+    /// Format only the distinct result paths, in result order.
+    ///
+    /// Plain and grep print one path per line, NDJSON one JSON string per line, and
+    /// JSON a [`JsonFilesOutput`].
+    fn format_files<W: Write>(
+        &self,
+        results: &[SearchResult],
+        output: &mut W,
+        eol: &str,
+    ) -> Result<()> {
+        let mut seen = HashSet::with_capacity(results.len());
+        let files: Vec<&str> =
+            results.iter().map(|r| r.path.as_str()).filter(|path| seen.insert(*path)).collect();
+
+        match self.config.format {
+            OutputFormat::Plain | OutputFormat::Grep => {
+                let color = self.config.color && self.config.format == OutputFormat::Plain;
+                for path in files {
+                    Self::write_path(output, path, color)?;
+                    write!(output, "{eol}")?;
+                }
+            }
+            OutputFormat::Ndjson => {
+                for path in files {
+                    write!(output, "{}{eol}", serde_json::to_string(path)?)?;
+                    output.flush()?;
+                }
+            }
+            OutputFormat::Json => {
+                let output_struct =
+                    JsonFilesOutput { query: &self.query, count: files.len(), files };
+                if self.config.pretty {
+                    let json = serde_json::to_string_pretty(&output_struct)?;
+                    write!(output, "{}{eol}", json.replace('\n', eol))?;
+                } else {
+                    serde_json::to_writer(&mut *output, &output_struct)?;
+                    write!(output, "{eol}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `path`, bolding its file name when `color` is set.
+    fn write_path<W: Write>(output: &mut W, path: &str, color: bool) -> Result<()> {
+        if !color {
+            write!(output, "{path}")?;
+            return Ok(());
+        }
+        let bold = anstyle::Style::new().bold();
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        if !dir.is_empty() {
+            write!(output, "{dir}/")?;
+        }
+        write!(output, "{bold}{name}{bold:#}")?;
+        Ok(())
+    }
+
+    /// Format results as JSON Lines: one compact object per result, flushed after each.
+    ///
+    /// An empty result set writes nothing.
+    fn format_ndjson<W: Write>(
+        results: &[SearchResult],
+        output: &mut W,
+        eol: &str,
+        position_ranks: bool,
+    ) -> Result<()> {
+        for (i, result) in results.iter().enumerate() {
+            let line = serde_json::to_string(&Self::json_result(i, result, position_ranks))?;
+            write!(output, "{line}{eol}")?;
+            output.flush()?;
         }
+        Ok(())
+    }
+
+    /// Format results as `path:line:text`, one row per matching content line.
+    ///
+    /// Files matched only by name or path have no matching line and are omitted, as
+    /// grep would. Results without located lines (`paths_only`) print the bare path.
+    fn format_grep<W: Write>(results: &[SearchResult], output: &mut W, eol: &str) -> Result<()> {
+        for result in results {
+            match &result.line_matches {
+                Some(lines) => {
+                    for m in lines {
+                        write!(output, "{}:{}:{}{eol}", result.path, m.line, m.text)?;
+                    }
+                }
+                None => write!(output, "{}{eol}", result.path)?,
+            }
+        }
+        Ok(())
     }
 
     /// Format results as plain text (one path per line).
-    fn format_plain<W: Write>(results: &[SearchResult], output: &mut W) -> Result<()> {
+    ///
+    /// With match density, each line is `path<TAB>matches/lines<TAB>density`.
+    /// A ranking breakdown adds `bm25 filename=… path=… content=…`. A snippet, when present, is appended as a final tab-separated field with
+    /// its whitespace collapsed so each result stays on one line; highlighted content
+    /// follows the same way.
+    ///
+    /// With `color`, the file name component of each path is bold.
+    fn format_plain<W: Write>(
+        results: &[SearchResult],
+        output: &mut W,
+        eol: &str,
+        color: bool,
+    ) -> Result<()> {
         for result in results {
-            writeln!(output, "{}", result.path)?;
+            // grep-style rows for editors; files without a matching line keep the plain row
+            if let Some(lines) = result.line_matches.as_deref().filter(|lines| !lines.is_empty()) {
+                for m in lines {
+                    Self::write_path(output, &result.path, color)?;
+                    write!(output, ":{}: {}{eol}", m.line, m.text)?;
+                }
+                continue;
+            }
+            Self::write_path(output, &result.path, color)?;
+            if let Some(density) = result.density {
+                write!(output, "\t{}/{}\t{:.4}", density.matches, density.lines, density.ratio())?;
+            }
+            if let Some(ranking) = result.ranking {
+                write!(
+                    output,
+                    "\tbm25 filename={:.4} path={:.4} content={:.4}",
+                    ranking.filename, ranking.path, ranking.content
+                )?;
+            }
+            if let Some(snippet) = &result.snippet {
+                let flat: Vec<&str> = snippet.split_whitespace().collect();
+                write!(output, "\t{}", flat.join(" "))?;
+            }
+            if let Some(highlighted) = &result.highlighted {
+                let flat: Vec<&str> = highlighted.split_whitespace().collect();
+                write!(output, "\t{}", flat.join(" "))?;
+            }
+            write!(output, "{eol}")?;
         }
         Ok(())
     }
 
-    /// Format results as JSON.
-    fn format_json<W: Write>(results: &[SearchResult], output: &mut W) -> Result<()> {
+    /// JSON view of the result at position `i` (0-based).
+    fn json_result(i: usize, r: &SearchResult, position_ranks: bool) -> JsonSearchResult<'_> {
+        JsonSearchResult {
+            path: r.path.as_str(),
+            rank: if position_ranks { JsonRank::Position(i + 1) } else { JsonRank::Score(r.rank) },
+            snippet: r.snippet.as_deref(),
+            filename: r.filename.as_deref(),
+            matches: r.density.map(|d| d.matches),
+            lines: r.density.map(|d| d.lines),
+            density: r.density.map(MatchDensity::ratio),
+            ranking: r.ranking.map(JsonRankBreakdown::from),
+            match_lines: r
+                .line_matches
+                .as_ref()
+                .map(|lines| lines.iter().map(JsonLineMatch::from).collect()),
+            highlighted: r.highlighted.as_deref(),
+        }
+    }
+
+    /// Format results as a single JSON object: compact on one line, or indented
+    /// when `pretty` is set.
+    fn format_json<W: Write>(
+        &self,
+        results: &[SearchResult],
+        output: &mut W,
+        eol: &str,
+    ) -> Result<()> {
         let json_results: Vec<JsonSearchResult<'_>> = results
             .iter()
-            .map(|r| JsonSearchResult { path: r.path.as_str(), rank: r.rank })
+            .enumerate()
+            .map(|(i, r)| Self::json_result(i, r, self.config.position_ranks))
             .collect();
 
-        let output_struct = JsonOutput { results: json_results };
+        let output_struct =
+            JsonOutput { query: &self.query, count: json_results.len(), results: json_results };
 
-        serde_json::to_writer_pretty(&mut *output, &output_struct)?;
-        writeln!(output)?;
+        if self.config.pretty {
+            // Pretty JSON only contains structural newlines (strings escape theirs)
+            let json = serde_json::to_string_pretty(&output_struct)?;
+            write!(output, "{}{eol}", json.replace('\n', eol))?;
+        } else {
+            serde_json::to_writer(&mut *output, &output_struct)?;
+            write!(output, "{eol}")?;
+        }
         Ok(())
     }
 }
@@ -219,6 +1006,7 @@ mod tests {
     use crate::DB_NAME;
     use crate::cli::OutputFormat;
     use crate::db::PragmaConfig;
+    use std::path::Path;
     use tempfile::tempdir;
 
     #[test]
@@ -301,8 +1089,26 @@ mod tests {
         let searcher = Searcher::new(&mut db, config);
 
         let results = vec![
-            SearchResult { path: "src/main.rs".to_string(), rank: -0.5 },
-            SearchResult { path: "tests/main_test.rs".to_string(), rank: -0.3 },
+            SearchResult {
+                path: "src/main.rs".to_string(),
+                rank: -0.5,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
+            SearchResult {
+                path: "tests/main_test.rs".to_string(),
+                rank: -0.3,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
         ];
 
         let mut output = Vec::new();
@@ -313,6 +1119,112 @@ mod tests {
         assert!(text.contains("tests/main_test.rs"));
     }
 
+    #[test]
+    fn test_crlf_line_ending_output() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        let results = vec![
+            SearchResult {
+                path: "src/main.rs".to_string(),
+                rank: -0.5,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
+            SearchResult {
+                path: "src/lib.rs".to_string(),
+                rank: -0.3,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
+        ];
+
+        let config = SearchConfig { line_ending: LineEnding::CrLf, ..Default::default() };
+        let searcher = Searcher::new(&mut db, config);
+        let mut output = Vec::new();
+        searcher.format_results(&results, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "src/main.rs\r\nsrc/lib.rs\r\n");
+
+        let config = SearchConfig {
+            format: OutputFormat::Json,
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        let searcher = Searcher::new(&mut db, config);
+        let mut output = Vec::new();
+        searcher.format_results(&results, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.ends_with("}\r\n"));
+        assert!(!text.replace("\r\n", "").contains('\n'));
+        assert!(serde_json::from_str::<serde_json::Value>(&text).is_ok());
+    }
+
+    #[test]
+    fn test_position_ranks_are_sequential_from_one() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        let results = vec![
+            SearchResult {
+                path: "a.rs".to_string(),
+                rank: -3.2,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
+            SearchResult {
+                path: "b.rs".to_string(),
+                rank: -1.1,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
+            SearchResult {
+                path: "c.rs".to_string(),
+                rank: -0.4,
+                snippet: None,
+                filename: None,
+                density: None,
+                ranking: None,
+                line_matches: None,
+                highlighted: None,
+            },
+        ];
+
+        let config =
+            SearchConfig { format: OutputFormat::Json, position_ranks: true, ..Default::default() };
+        let searcher = Searcher::new(&mut db, config);
+        let mut output = Vec::new();
+        searcher.format_results(&results, &mut output).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let ranks: Vec<u64> = json["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["rank"].as_u64().unwrap())
+            .collect();
+        assert_eq!(ranks, vec![1, 2, 3]);
+    }
+
     #[test]
     fn test_json_output() {
         let config = SearchConfig { format: OutputFormat::Json, ..Default::default() };
@@ -322,7 +1234,16 @@ mod tests {
         db.init_schema().unwrap();
         let searcher = Searcher::new(&mut db, config);
 
-        let results = vec![SearchResult { path: "src/main.rs".to_string(), rank: -0.5 }];
+        let results = vec![SearchResult {
+            path: "src/main.rs".to_string(),
+            rank: -0.5,
+            snippet: None,
+            filename: None,
+            density: None,
+            ranking: None,
+            line_matches: None,
+            highlighted: None,
+        }];
 
         let mut output = Vec::new();
         searcher.format_results(&results, &mut output).unwrap();
@@ -333,6 +1254,87 @@ mod tests {
         assert!(text.contains("results"));
     }
 
+    #[test]
+    fn test_json_output_compact_by_default_with_fixed_key_order() {
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/main.rs", "fn main() { needle(); }", 0, 23).unwrap();
+        db.upsert_file("src/lib.rs", "pub fn needle() {}", 0, 18).unwrap();
+
+        let config = SearchConfig { format: OutputFormat::Json, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("needle").unwrap();
+        assert_eq!(results.len(), 2);
+
+        let mut output = Vec::new();
+        searcher.format_results(&results, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        // One line: no newlines between result objects
+        assert_eq!(text.matches('\n').count(), 1);
+        assert!(text.ends_with("}\n"));
+        assert!(text.starts_with(r#"{"query":"needle","count":2,"results":["#));
+
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            json["count"].as_u64().unwrap(),
+            json["results"].as_array().unwrap().len() as u64
+        );
+
+        let config =
+            SearchConfig { format: OutputFormat::Json, pretty: true, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("needle").unwrap();
+        let mut output = Vec::new();
+        searcher.format_results(&results, &mut output).unwrap();
+        let pretty = String::from_utf8(output).unwrap();
+        assert!(
+            pretty.starts_with("{\n  \"query\": \"needle\",\n  \"count\": 2,\n  \"results\": [")
+        );
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&pretty).unwrap(), json);
+    }
+
+    #[test]
+    fn test_ndjson_output_one_object_per_line() {
+        let config = SearchConfig { format: OutputFormat::Ndjson, ..Default::default() };
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        let searcher = Searcher::new(&mut db, config);
+
+        let result = |path: &str, rank| SearchResult {
+            path: path.to_string(),
+            rank,
+            snippet: None,
+            filename: None,
+            density: None,
+            ranking: None,
+            line_matches: None,
+            highlighted: None,
+        };
+        let results = vec![result("src/main.rs", -2.0), result("has\nnewline.rs", -1.0)];
+
+        let mut output = Vec::new();
+        searcher.format_results(&results, &mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, expected) in lines.iter().zip(&results) {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["path"], expected.path.as_str());
+            assert_eq!(value["rank"], expected.rank);
+            assert!(value.get("results").is_none());
+        }
+
+        // No results: no output at all (not an empty array)
+        let mut output = Vec::new();
+        searcher.format_results(&[], &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
     #[test]
     fn test_json_escaping() {
         let config = SearchConfig::default();
@@ -517,6 +1519,476 @@ mod tests {
         assert!((results[0].rank + 1000.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_recency_boost_orders_equal_matches_by_mtime() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        // Identical content => identical BM25; older file inserted first
+        db.upsert_file("old.rs", "shared recencyterm body", now - 365 * 86_400, 23).unwrap();
+        db.upsert_file("new.rs", "shared recencyterm body", now, 23).unwrap();
+
+        let mut searcher = Searcher::new(&mut db, SearchConfig::default());
+        let results = searcher.search("recencyterm").unwrap();
+        assert_eq!(results[0].path, "old.rs");
+
+        let config = SearchConfig { recency_boost: Some(1.0), ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("recencyterm").unwrap();
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["new.rs", "old.rs"]);
+
+        // The blend applies before the limit: BM25 alone ranks new.rs second of five
+        // (ties keep insertion order), yet it wins the only slot
+        for i in 0..3 {
+            let path = format!("older_{i}.rs");
+            db.upsert_file(&path, "shared recencyterm body", now - 90 * 86_400, 23).unwrap();
+        }
+        let config =
+            SearchConfig { recency_boost: Some(1.0), max_results: 1, ..Default::default() };
+        let results = Searcher::new(&mut db, config).search("recencyterm").unwrap();
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["new.rs"]);
+    }
+
+    #[test]
+    fn test_snippet_top_n_limits_snippets() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        for i in 0..4 {
+            let content = format!("header {i}\nlet topnterm = {i};\nfooter");
+            db.upsert_file(&format!("file_{i}.rs"), &content, 0, 30).unwrap();
+        }
+
+        let config = SearchConfig { snippet_top_n: Some(2), ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("topnterm").unwrap();
+
+        assert_eq!(results.len(), 4);
+        assert!(
+            results[..2]
+                .iter()
+                .all(|r| r.snippet.as_deref().is_some_and(|s| s.contains("topnterm")))
+        );
+        assert!(results[2..].iter().all(|r| r.snippet.is_none()));
+    }
+
+    #[test]
+    fn test_offset_pages_merged_results() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        // Filename (phase A) and content (phase B) matches in one ranking
+        for i in 0..4 {
+            db.upsert_file(&format!("offsetterm_{i}.rs"), "x", 0, 1).unwrap();
+            db.upsert_file(&format!("other_{i}.rs"), "offsetterm", 0, 10).unwrap();
+        }
+
+        let all = Searcher::new(&mut db, SearchConfig::default()).search("offsetterm").unwrap();
+        assert_eq!(all.len(), 8);
+
+        let page = |db: &mut Database, offset| {
+            let config = SearchConfig { max_results: 3, offset, ..Default::default() };
+            let results = Searcher::new(db, config).search("offsetterm").unwrap();
+            results.into_iter().map(|r| r.path).collect::<Vec<_>>()
+        };
+        let expected: Vec<String> = all.into_iter().map(|r| r.path).collect();
+        assert_eq!(page(&mut db, 0), expected[..3]);
+        assert_eq!(page(&mut db, 3), expected[3..6]);
+        assert_eq!(page(&mut db, 6), expected[6..]);
+        assert!(page(&mut db, 8).is_empty());
+    }
+
+    #[test]
+    fn test_highlighted_snippets_in_output() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/lib.rs", "header\nlet glowterm = 1;\nfooter", 0, 30).unwrap();
+        db.upsert_file("glowterm.md", "unrelated prose", 0, 15).unwrap();
+
+        let config = SearchConfig { snippets: true, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("glowterm").unwrap();
+        assert_eq!(results.len(), 2);
+
+        let mut plain = Vec::new();
+        searcher.format_results(&results, &mut plain).unwrap();
+        let plain = String::from_utf8(plain).unwrap();
+        assert!(plain.contains("glowterm.md\t\n"));
+        assert!(
+            plain.lines().any(|l| l.starts_with("src/lib.rs\t") && l.contains("<b>glowterm</b>"))
+        );
+
+        let mut json = Vec::new();
+        searcher.format_json(&results, &mut json, "\n").unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let lib = json["results"].as_array().unwrap().iter().find(|r| r["path"] == "src/lib.rs");
+        assert!(lib.unwrap()["snippet"].as_str().unwrap().contains("<b>glowterm</b>"));
+
+        // paths_only never generates snippets
+        let config = SearchConfig { snippets: true, paths_only: true, ..Default::default() };
+        let results = Searcher::new(&mut db, config).search("glowterm").unwrap();
+        assert!(results.iter().all(|r| r.snippet.is_none()));
+    }
+
+    #[test]
+    fn test_line_numbers_in_output() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/lib.rs", "// über\nfn other() {}\nlet lineterm = 1;", 0, 41).unwrap();
+        db.upsert_file("lineterm.md", "unrelated prose", 0, 15).unwrap();
+
+        let config = SearchConfig { line_numbers: true, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("lineterm").unwrap();
+
+        let mut plain = Vec::new();
+        searcher.format_results(&results, &mut plain).unwrap();
+        let plain = String::from_utf8(plain).unwrap();
+        assert_eq!(plain, "lineterm.md\nsrc/lib.rs:3: let lineterm = 1;\n");
+
+        let mut json = Vec::new();
+        searcher.format_json(&results, &mut json, "\n").unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let lib = &json["results"][1];
+        assert_eq!(lib["match_lines"][0]["line"], 3);
+        assert_eq!(lib["match_lines"][0]["text"], "let lineterm = 1;");
+        assert_eq!(json["results"][0]["match_lines"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_files_with_matches_lists_each_path_once() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        // Matches by name and by content, so both search phases find it
+        db.upsert_file("listterm.rs", "let listterm = 1;\nlistterm();", 0, 28).unwrap();
+        db.upsert_file("src/other.rs", "listterm", 0, 8).unwrap();
+
+        let config = SearchConfig {
+            files_with_matches: true,
+            line_numbers: true,
+            format: OutputFormat::Json,
+            ..Default::default()
+        };
+        let mut searcher = Searcher::new(&mut db, config);
+        let mut results = searcher.search("listterm").unwrap();
+        assert!(results.iter().all(|r| r.line_matches.is_none()));
+
+        let mut json = Vec::new();
+        searcher.format_results(&results, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "query": "listterm",
+                "count": 2,
+                "files": ["listterm.rs", "src/other.rs"]
+            })
+        );
+
+        // Repeated rows still print once
+        results.push(results[0].clone());
+        searcher.config.format = OutputFormat::Plain;
+        let mut plain = Vec::new();
+        searcher.format_results(&results, &mut plain).unwrap();
+        assert_eq!(String::from_utf8(plain).unwrap(), "listterm.rs\nsrc/other.rs\n");
+    }
+
+    #[test]
+    fn test_highlight_marks_content_unless_paths_only() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/app.rs", "fn main() {}", 0, 12).unwrap();
+
+        let config = SearchConfig {
+            highlight: Some(HighlightMarkers::default()),
+            format: OutputFormat::Json,
+            ..Default::default()
+        };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("main").unwrap();
+        assert_eq!(results[0].highlighted.as_deref(), Some("fn [main]() {}"));
+        let mut json = Vec::new();
+        searcher.format_results(&results, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["results"][0]["highlighted"], "fn [main]() {}");
+
+        searcher.config.paths_only = true;
+        let results = searcher.search("app").unwrap();
+        assert!(results.iter().all(|r| r.highlighted.is_none()));
+    }
+
+    #[test]
+    fn test_with_filename_matches_path_file_name() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        // Filename phase match plus content-only FTS matches
+        db.upsert_file("src/namedterm.rs", "unrelated", 0, 9).unwrap();
+        db.upsert_file("src/nested/other.rs", "mentions namedterm", 0, 18).unwrap();
+        db.upsert_file("docs/guide.md", "namedterm guide", 0, 15).unwrap();
+
+        let config = SearchConfig { with_filename: true, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("namedterm").unwrap();
+
+        assert_eq!(results.len(), 3);
+        for result in &results {
+            let expected = Path::new(&result.path).file_name().unwrap().to_str().unwrap();
+            assert_eq!(result.filename.as_deref(), Some(expected));
+        }
+
+        // Off by default
+        let mut searcher = Searcher::new(&mut db, SearchConfig::default());
+        assert!(searcher.search("namedterm").unwrap().iter().all(|r| r.filename.is_none()));
+    }
+
+    #[test]
+    fn test_verify_exists_drops_deleted_files() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        for name in ["kept.rs", "deleted.rs"] {
+            std::fs::write(dir.path().join(name), "cachedterm").unwrap();
+            db.upsert_file(name, "cachedterm", 0, 10).unwrap();
+        }
+        std::fs::remove_file(dir.path().join("deleted.rs")).unwrap();
+
+        // Default: stale entry is still returned until the next index run
+        let mut searcher = Searcher::new(&mut db, SearchConfig::default());
+        assert_eq!(searcher.search("cachedterm").unwrap().len(), 2);
+
+        let config = SearchConfig {
+            verify_exists: true,
+            project_root: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let mut searcher = Searcher::new(&mut db, config);
+        let paths: Vec<String> =
+            searcher.search("cachedterm").unwrap().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["kept.rs"]);
+
+        let config = SearchConfig { verify_exists: true, ..Default::default() };
+        assert!(matches!(
+            Searcher::new(&mut db, config).search("cachedterm"),
+            Err(IndexerError::ConfigInvalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_verify_exists_resolves_project_and_extra_roots() {
+        // The database lives outside both roots (`--db-path`)
+        let project = tempdir().unwrap();
+        let other_parent = tempdir().unwrap();
+        let other = other_parent.path().join("other");
+        let db_dir = tempdir().unwrap();
+        std::fs::create_dir(&other).unwrap();
+        let mut db =
+            Database::open(&db_dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        for (stored, disk) in [
+            ("a.rs", project.path().join("a.rs")),
+            ("gone.rs", project.path().join("gone.rs")),
+            ("@other/c.md", other.join("c.md")),
+            ("@other/gone.md", other.join("gone.md")),
+        ] {
+            std::fs::write(&disk, "rootsterm").unwrap();
+            db.upsert_file(stored, "rootsterm", 0, 9).unwrap();
+        }
+        std::fs::remove_file(project.path().join("gone.rs")).unwrap();
+        std::fs::remove_file(other.join("gone.md")).unwrap();
+
+        let config = SearchConfig {
+            verify_exists: true,
+            project_root: Some(project.path().to_path_buf()),
+            extra_roots: vec![other],
+            ..Default::default()
+        };
+        let mut paths: Vec<String> = Searcher::new(&mut db, config)
+            .search("rootsterm")
+            .unwrap()
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["@other/c.md", "a.rs"]);
+    }
+
+    #[test]
+    fn test_sort_density_puts_pervasive_matches_first() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+
+        // Sparse: one mention in a long file; dense: mentioned on most lines
+        let sparse = format!("densityterm once\n{}", "filler line\n".repeat(99));
+        let dense = "densityterm a\ndensityterm b\nDensityTerm c\nother\n";
+        db.upsert_file("sparse.rs", &sparse, 0, 1).unwrap();
+        db.upsert_file("dense.rs", dense, 0, 1).unwrap();
+
+        let config = SearchConfig {
+            columns: ColumnSet::CONTENT,
+            sort: SortOrder::Density,
+            ..Default::default()
+        };
+        let mut searcher = Searcher::new(&mut db, config);
+        let results = searcher.search("densityterm").unwrap();
+
+        let paths: Vec<&str> = results.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, vec!["dense.rs", "sparse.rs"]);
+        assert_eq!(results[0].density, Some(MatchDensity { matches: 3, lines: 4 }));
+        assert_eq!(results[1].density, Some(MatchDensity { matches: 1, lines: 100 }));
+        assert!(results[0].density.unwrap().ratio() > results[1].density.unwrap().ratio());
+    }
+
+    #[test]
+    fn test_content_columns_skip_filename_phase() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("columnterm.rs", "unrelated", 0, 9).unwrap();
+        db.upsert_file("other.rs", "mentions columnterm", 0, 19).unwrap();
+
+        let config = SearchConfig { columns: ColumnSet::CONTENT, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let paths: Vec<String> =
+            searcher.search("columnterm").unwrap().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["other.rs"]);
+
+        // paths_only still wins over columns for compatibility
+        let config =
+            SearchConfig { paths_only: true, columns: ColumnSet::CONTENT, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let paths: Vec<String> =
+            searcher.search("columnterm").unwrap().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["columnterm.rs"]);
+    }
+
+    #[test]
+    fn test_build_fts_query_modes() {
+        let q = "main function";
+        assert_eq!(Searcher::build_fts_query(q, QueryMode::And), r#""main" "function""#);
+        assert_eq!(Searcher::build_fts_query(q, QueryMode::Or), r#""main" OR "function""#);
+        assert_eq!(Searcher::build_fts_query(q, QueryMode::Phrase), r#""main function""#);
+        assert_eq!(
+            Searcher::build_fts_query(q, QueryMode::Near(5)),
+            r#"NEAR("main" "function", 5)"#
+        );
+        assert_eq!(Searcher::build_fts_query("main", QueryMode::Near(5)), r#""main""#);
+        assert_eq!(
+            Searcher::build_fts_query("hello world*", QueryMode::Or),
+            r#""hello" OR "world"*"#
+        );
+        assert_eq!(
+            Searcher::build_fts_query("hello world*", QueryMode::Phrase),
+            r#""hello world"*"#
+        );
+
+        // Operators in user input are searched literally
+        assert_eq!(
+            Searcher::build_fts_query("cats NOT dogs", QueryMode::And),
+            r#""cats" "NOT" "dogs""#
+        );
+        assert_eq!("near:3".parse::<QueryMode>(), Ok(QueryMode::Near(3)));
+        assert_eq!("NEAR".parse::<QueryMode>(), Ok(QueryMode::Near(DEFAULT_NEAR_DISTANCE)));
+        assert!("xor".parse::<QueryMode>().is_err());
+    }
+
+    #[test]
+    fn test_sanitize_fts_query_quotes_each_term() {
+        assert_eq!(sanitize_fts_query("test-function"), r#""test-function""#);
+        assert_eq!(sanitize_fts_query("  foo.bar   a(b) "), r#""foo.bar" "a(b)""#);
+        assert_eq!(sanitize_fts_query(r#"say "hi""#), r#""say" """hi""""#);
+        assert_eq!(sanitize_fts_query("x OR y*"), r#""x" "OR" "y*""#);
+        assert_eq!(sanitize_fts_query("   "), "");
+    }
+
+    #[test]
+    fn test_raw_query_keeps_fts5_operators() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("a.rs", "alpha only", 0, 10).unwrap();
+        db.upsert_file("b.rs", "beta only", 0, 9).unwrap();
+
+        let config = SearchConfig { raw_query: true, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        assert_eq!(searcher.search("alpha OR beta").unwrap().len(), 2);
+        assert!(searcher.search("alpha NOT").is_err());
+
+        let mut searcher = Searcher::new(&mut db, SearchConfig::default());
+        assert!(searcher.search("alpha OR beta").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_prefix_mode_matches_word_starts() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("config.rs", "fn a() {}", 0, 9).unwrap();
+        db.upsert_file("reconnect.rs", "fn b() {}", 0, 9).unwrap();
+        db.upsert_file("notes.txt", "about connections", 0, 17).unwrap();
+
+        let config = SearchConfig { prefix: true, ..Default::default() };
+        let mut searcher = Searcher::new(&mut db, config);
+        let paths: Vec<String> =
+            searcher.search("con").unwrap().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["config.rs", "notes.txt"]);
+    }
+
+    #[test]
+    fn test_query_modes_filter_content_matches() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_NAME);
+        let mut db = Database::open(&db_path, &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("a.rs", "fn main function body", 0, 21).unwrap();
+        db.upsert_file("b.rs", "function one two three four five six main", 0, 41).unwrap();
+        db.upsert_file("c.rs", "only the function here", 0, 22).unwrap();
+        db.upsert_file("d.rs", "keep cats NOT dogs", 0, 18).unwrap();
+
+        let mut paths = |query: &str, query_mode| {
+            let config = SearchConfig { query_mode, ..Default::default() };
+            let mut paths: Vec<String> = Searcher::new(&mut db, config)
+                .search(query)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(paths("main function", QueryMode::And), vec!["a.rs", "b.rs"]);
+        assert_eq!(paths("main function", QueryMode::Or), vec!["a.rs", "b.rs", "c.rs"]);
+        assert_eq!(paths("main function", QueryMode::Phrase), vec!["a.rs"]);
+        assert_eq!(paths("main function", QueryMode::Near(3)), vec!["a.rs"]);
+        assert_eq!(paths("main function", QueryMode::Near(10)), vec!["a.rs", "b.rs"]);
+        // Without quoting, NOT would exclude d.rs
+        assert_eq!(paths("cats NOT dogs", QueryMode::And), vec!["d.rs"]);
+    }
+
     #[test]
     fn test_content_only_match_when_no_filename_match() {
         // When no filename matches, content matches should still work
@@ -537,4 +2009,30 @@ mod tests {
         // This should be a BM25 rank, not the synthetic -1000.0
         assert!(results[0].rank > -1000.0);
     }
+
+    #[test]
+    fn test_search_many_returns_result_sets_in_order() {
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("alpha.rs", "fn batchterm_one() {}", 0, 21).unwrap();
+        db.upsert_file("beta.rs", "fn batchterm_two() {}", 0, 21).unwrap();
+
+        let mut searcher = Searcher::new(&mut db, SearchConfig::default());
+        let queries: Vec<String> =
+            ["batchterm_two", "absentterm", "batchterm_one"].map(String::from).to_vec();
+        let sets = searcher.search_many(&queries).unwrap();
+
+        assert_eq!(sets.len(), 3);
+        assert_eq!(sets[0].iter().map(|r| r.path.as_str()).collect::<Vec<_>>(), vec!["beta.rs"]);
+        assert!(sets[1].is_empty());
+        assert_eq!(sets[2].iter().map(|r| r.path.as_str()).collect::<Vec<_>>(), vec!["alpha.rs"]);
+
+        let mut output = Vec::new();
+        searcher.format_batch(&queries, &sets, &mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["query"], "batchterm_two");
+        assert_eq!(json[1]["count"], 0);
+        assert_eq!(json[2]["results"][0]["path"], "alpha.rs");
+    }
 }
@@ -0,0 +1,217 @@
+//! Minimal HTTP search server (`ffts-grep serve`).
+//!
+//! # Endpoints
+//!
+//! | Request | Response |
+//! |---------|----------|
+//! | `GET /search?q=...&limit=N&paths=1` | `200`, the object `search --format json` prints |
+//! | `GET /health` | `200` if [`check_health_at`] says usable, else `503`; `{"status": ..., "usable": ...}` |
+//!
+//! Errors are `{"error": "..."}` with `400` (bad parameters), `404`, `405` or `500`.
+//!
+//! # Design
+//!
+//! - **Blocking**: [`tiny_http`] plus a fixed pool of worker threads; no async runtime
+//! - **One connection per worker**: each worker opens its own read-only
+//!   [`Database`], so searches run concurrently (readers don't block under WAL)
+//! - **Graceful shutdown**: workers poll a flag between requests (see
+//!   `daemon::install_shutdown_handler` on Unix)
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use tiny_http::{Header, Method, Response};
+
+use crate::cli::{MAX_SEARCH_LIMIT, OutputFormat};
+use crate::db::Database;
+use crate::error::{IndexerError, Result};
+use crate::health::check_health_at;
+use crate::search::{SearchConfig, Searcher};
+
+/// Default worker thread count for `serve`.
+pub const DEFAULT_WORKERS: usize = 4;
+
+/// How long a worker waits for a request before re-checking for shutdown.
+const RECV_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Query parameters of `GET /search`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchParams {
+    query: String,
+    limit: Option<u32>,
+    paths: bool,
+}
+
+/// HTTP server bound to an address, searching the database at `db_path`.
+pub struct Server {
+    http: tiny_http::Server,
+    db_path: PathBuf,
+    config: SearchConfig,
+}
+
+impl Server {
+    /// Bind `addr` (e.g. `127.0.0.1:7878`; port `0` picks a free one).
+    ///
+    /// `config` is the base for every search; its format is forced to JSON.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Io` if the address cannot be bound.
+    pub fn bind(addr: &str, db_path: &Path, config: SearchConfig) -> Result<Self> {
+        let http = tiny_http::Server::http(addr)
+            .map_err(|e| IndexerError::Io { source: std::io::Error::other(e) })?;
+        Ok(Self {
+            http,
+            db_path: db_path.to_path_buf(),
+            config: SearchConfig { format: OutputFormat::Json, pretty: false, ..config },
+        })
+    }
+
+    /// Address actually bound (resolves port `0`).
+    #[must_use]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    /// Serve requests on `workers` threads until `shutdown` becomes true.
+    ///
+    /// # Errors
+    /// Returns `IndexerError` if a worker's database connection cannot be opened.
+    pub fn serve(&self, workers: usize, shutdown: &AtomicBool) -> Result<()> {
+        let connections = (0..workers.max(1))
+            .map(|_| Database::open_readonly(&self.db_path))
+            .collect::<Result<Vec<_>>>()?;
+
+        thread::scope(|scope| {
+            for mut db in connections {
+                scope.spawn(move || self.worker(&mut db, shutdown));
+            }
+        });
+        Ok(())
+    }
+
+    /// Answer requests with one connection until shutdown.
+    fn worker(&self, db: &mut Database, shutdown: &AtomicBool) {
+        while !shutdown.load(Ordering::SeqCst) {
+            let request = match self.http.recv_timeout(RECV_POLL_INTERVAL) {
+                Ok(Some(request)) => request,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::debug!(error = %e, "Failed to receive HTTP request");
+                    continue;
+                }
+            };
+
+            let (status, body) = self.route(db, request.method(), request.url());
+            let content_type = Header::from_bytes("Content-Type", "application/json")
+                .expect("static header is valid");
+            let response =
+                Response::from_string(body).with_status_code(status).with_header(content_type);
+            if let Err(e) = request.respond(response) {
+                tracing::debug!(error = %e, "Failed to send HTTP response");
+            }
+        }
+    }
+
+    /// Status code and JSON body for one request.
+    fn route(&self, db: &mut Database, method: &Method, url: &str) -> (u16, String) {
+        if *method != Method::Get {
+            return error_response(405, "only GET is supported");
+        }
+
+        let (path, query_string) = url.split_once('?').unwrap_or((url, ""));
+        match path {
+            "/health" => {
+                let health = check_health_at(&self.db_path);
+                let status = if health.is_usable() { 200 } else { 503 };
+                let body =
+                    serde_json::json!({ "status": health.label(), "usable": health.is_usable() });
+                (status, body.to_string())
+            }
+            "/search" => match parse_search_params(query_string) {
+                Ok(params) => self.search(db, params),
+                Err(reason) => error_response(400, &reason),
+            },
+            _ => error_response(404, "not found"),
+        }
+    }
+
+    /// Run a search and render it as `search --format json` would.
+    fn search(&self, db: &mut Database, params: SearchParams) -> (u16, String) {
+        let mut config = self.config.clone();
+        if let Some(limit) = params.limit {
+            config.max_results = limit;
+        }
+        config.paths_only = params.paths;
+
+        let mut searcher = Searcher::new(db, config);
+        let mut body = Vec::new();
+        let result = searcher
+            .search(&params.query)
+            .and_then(|results| searcher.format_results(&results, &mut body));
+        match result {
+            Ok(()) => (200, String::from_utf8_lossy(&body).into_owned()),
+            Err(e) => error_response(500, &e.to_string()),
+        }
+    }
+}
+
+/// `{"error": reason}` with `status`.
+fn error_response(status: u16, reason: &str) -> (u16, String) {
+    (status, serde_json::json!({ "error": reason }).to_string())
+}
+
+/// Parse `q` (required), `limit` and `paths` from a URL query string.
+fn parse_search_params(query_string: &str) -> std::result::Result<SearchParams, String> {
+    let mut query = None;
+    let mut limit = None;
+    let mut paths = false;
+
+    for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+        match &*key {
+            "q" => query = Some(value.into_owned()),
+            "limit" => {
+                limit = match value.parse() {
+                    Ok(n @ 1..=MAX_SEARCH_LIMIT) => Some(n),
+                    _ => return Err(format!("limit must be between 1 and {MAX_SEARCH_LIMIT}")),
+                };
+            }
+            "paths" => {
+                paths = match &*value {
+                    "1" | "true" => true,
+                    "0" | "false" => false,
+                    _ => return Err("paths must be true or false".to_string()),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    match query {
+        Some(query) if !query.trim().is_empty() => Ok(SearchParams { query, limit, paths }),
+        _ => Err("missing query parameter 'q'".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_search_params() {
+        assert_eq!(
+            parse_search_params("q=fn+main%21&limit=5&paths=true&other=x"),
+            Ok(SearchParams { query: "fn main!".to_string(), limit: Some(5), paths: true })
+        );
+        assert_eq!(
+            parse_search_params("q=main"),
+            Ok(SearchParams { query: "main".to_string(), limit: None, paths: false })
+        );
+        assert!(parse_search_params("limit=5").is_err());
+        assert!(parse_search_params("q=%20").is_err());
+        assert!(parse_search_params("q=main&limit=0").is_err());
+        assert!(parse_search_params("q=main&paths=maybe").is_err());
+    }
+}
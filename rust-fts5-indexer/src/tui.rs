@@ -0,0 +1,237 @@
+//! Interactive fuzzy-finder TUI (`--features tui`).
+//!
+//! Type a query, see live ranked results, press Enter to print the selection.
+//!
+//! # Design
+//!
+//! - **Reuses search**: every refresh is a plain [`Searcher::search`] call
+//! - **Debounced**: searches run once typing pauses for [`DEBOUNCE`], not per keystroke
+//! - **Testable**: key handling and debouncing live in [`App`], independent of the terminal
+
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::db::{Database, SearchResult};
+use crate::error::Result;
+use crate::search::{SearchConfig, Searcher};
+
+/// Pause in typing before the query is re-run.
+pub const DEBOUNCE: Duration = Duration::from_millis(75);
+
+/// Outcome of handling a key press.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Keep running the event loop
+    Continue,
+    /// Exit without a selection
+    Quit,
+    /// Exit and print this path
+    Select(String),
+}
+
+/// Fuzzy-finder state: query, results, selection, and pending search.
+#[derive(Debug, Default)]
+pub struct App {
+    query: String,
+    results: Vec<SearchResult>,
+    selected: usize,
+    /// Time of the last query edit not yet searched
+    pending_since: Option<Instant>,
+}
+
+impl App {
+    /// Create an empty finder.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current query text.
+    #[must_use]
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Current results (as of the last completed search).
+    #[must_use]
+    pub fn results(&self) -> &[SearchResult] {
+        &self.results
+    }
+
+    /// Index of the highlighted result.
+    #[must_use]
+    pub const fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Apply a key press received at `now`.
+    pub fn handle_key(&mut self, key: KeyEvent, now: Instant) -> Action {
+        if key.kind != KeyEventKind::Press {
+            return Action::Continue;
+        }
+
+        match key.code {
+            KeyCode::Esc => Action::Quit,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+            KeyCode::Enter => self
+                .results
+                .get(self.selected)
+                .map_or(Action::Continue, |result| Action::Select(result.path.clone())),
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+                Action::Continue
+            }
+            KeyCode::Down => {
+                if self.selected + 1 < self.results.len() {
+                    self.selected += 1;
+                }
+                Action::Continue
+            }
+            KeyCode::Backspace => {
+                if self.query.pop().is_some() {
+                    self.pending_since = Some(now);
+                }
+                Action::Continue
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.pending_since = Some(now);
+                Action::Continue
+            }
+            _ => Action::Continue,
+        }
+    }
+
+    /// Run the pending search if typing paused for at least [`DEBOUNCE`].
+    ///
+    /// Returns `true` if a search ran.
+    ///
+    /// # Errors
+    /// Returns `IndexerError` if the search fails.
+    pub fn tick(&mut self, db: &mut Database, config: &SearchConfig, now: Instant) -> Result<bool> {
+        let Some(since) = self.pending_since else {
+            return Ok(false);
+        };
+        if now.duration_since(since) < DEBOUNCE {
+            return Ok(false);
+        }
+
+        self.pending_since = None;
+        self.results = Searcher::new(db, config.clone()).search(&self.query)?;
+        self.selected = 0;
+        Ok(true)
+    }
+
+    fn draw(&self, frame: &mut Frame<'_>) {
+        let [input_area, list_area] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(frame.area());
+
+        let input = Paragraph::new(self.query.as_str())
+            .block(Block::bordered().title(" Search (Enter: select, Esc: quit) "));
+        frame.render_widget(input, input_area);
+
+        let items: Vec<ListItem<'_>> =
+            self.results.iter().map(|result| ListItem::new(result.path.as_str())).collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(format!(" {} results ", self.results.len())))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, list_area, &mut state);
+    }
+}
+
+/// Run the interactive finder until the user selects a result or quits.
+///
+/// Takes over the terminal (raw mode, alternate screen) and restores it on exit.
+///
+/// # Errors
+/// Returns `IndexerError` if terminal I/O or a search fails.
+pub fn run(db: &mut Database, config: &SearchConfig) -> Result<Option<String>> {
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, db, config);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    db: &mut Database,
+    config: &SearchConfig,
+) -> Result<Option<String>> {
+    let mut app = App::new();
+
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+
+        if event::poll(DEBOUNCE)? {
+            if let Event::Key(key) = event::read()? {
+                match app.handle_key(key, Instant::now()) {
+                    Action::Continue => {}
+                    Action::Quit => return Ok(None),
+                    Action::Select(path) => return Ok(Some(path)),
+                }
+            }
+        }
+
+        app.tick(db, config, Instant::now())?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DB_NAME;
+    use crate::db::PragmaConfig;
+    use tempfile::tempdir;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_event_loop_debounces_and_selects() {
+        let dir = tempdir().unwrap();
+        let mut db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        db.upsert_file("src/alpha.rs", "fn alpha() {}", 0, 13).unwrap();
+        db.upsert_file("src/alphabet.rs", "fn alphabet() {}", 0, 16).unwrap();
+
+        let config = SearchConfig::default();
+        let mut app = App::new();
+        let start = Instant::now();
+
+        for c in "alpha".chars() {
+            assert_eq!(app.handle_key(press(KeyCode::Char(c)), start), Action::Continue);
+        }
+        assert_eq!(app.query(), "alpha");
+
+        // Still typing: no search yet
+        assert!(!app.tick(&mut db, &config, start).unwrap());
+        assert!(app.results().is_empty());
+
+        // Typing paused: search runs once
+        assert!(app.tick(&mut db, &config, start + DEBOUNCE).unwrap());
+        assert_eq!(app.results().len(), 2);
+        assert!(!app.tick(&mut db, &config, start + DEBOUNCE * 2).unwrap());
+
+        app.handle_key(press(KeyCode::Down), start);
+        app.handle_key(press(KeyCode::Down), start);
+        assert_eq!(app.selected(), 1);
+
+        let expected = app.results()[1].path.clone();
+        assert_eq!(app.handle_key(press(KeyCode::Enter), start), Action::Select(expected));
+        assert_eq!(app.handle_key(press(KeyCode::Esc), start), Action::Quit);
+    }
+
+    #[test]
+    fn test_enter_without_results_continues() {
+        let mut app = App::new();
+        assert_eq!(app.handle_key(press(KeyCode::Enter), Instant::now()), Action::Continue);
+    }
+}
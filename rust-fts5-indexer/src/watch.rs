@@ -0,0 +1,407 @@
+//! Watch mode: keep the index fresh while files change.
+//!
+//! # Design
+//!
+//! - **Notifications**: the platform backend (inotify, `FSEvents`,
+//!   `ReadDirectoryChangesW`, kqueue via [`notify`]) reports changed paths, which are
+//!   re-checked with the indexing filters; nothing is scanned while the tree is idle
+//! - **Polling fallback**: when no backend is available, each poll re-walks the tree
+//!   and compares every file's mtime and size with the previous scan
+//! - **Debounced**: changes queue up until the tree has been quiet for the debounce
+//!   window, then are applied in one transaction via [`Indexer::reindex_paths`]
+//! - **Interruptible**: every batch commits before the next one starts, so stopping
+//!   with Ctrl-C never leaves a half-applied batch
+//! - **Testable**: queueing and debouncing live in [`Watcher::poll`], independent of
+//!   the blocking loop
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{MAIN_SEPARATOR, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::time::{Duration, Instant, SystemTime};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::error::Result;
+use crate::indexer::{IndexStats, Indexer};
+
+/// Quiet period after the last change before a batch is indexed.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Time between scans of the tree when polling.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a scan records per file: enough to notice a write without reading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// Where changes come from.
+enum Source {
+    /// Platform notifications; `_watcher` keeps them coming while it is alive
+    Events { _watcher: RecommendedWatcher, events: Receiver<notify::Result<Event>> },
+    /// Last scan, keyed by root-relative path
+    Polling { stamps: HashMap<PathBuf, FileStamp> },
+}
+
+/// Watcher that re-indexes changed files and drops deleted ones.
+pub struct Watcher {
+    indexer: Indexer,
+    debounce: Duration,
+    source: Source,
+    /// Root-relative paths changed since the last applied batch
+    pending: BTreeSet<PathBuf>,
+    /// Notifications were lost, so the next batch re-indexes the whole tree
+    rescan: bool,
+    /// Time the most recent change was seen
+    last_change: Option<Instant>,
+}
+
+impl Watcher {
+    /// Start watching `indexer`'s root; the current tree is the baseline.
+    ///
+    /// Uses platform notifications, or polls (see [`Watcher::polling`]) with a
+    /// warning if they are unavailable. Changes made before this call are not picked
+    /// up, so index the tree first.
+    #[must_use]
+    pub fn new(indexer: Indexer, debounce: Duration) -> Self {
+        match subscribe(indexer.root()) {
+            Ok(source) => Self::with_source(indexer, debounce, source),
+            Err(e) => {
+                tracing::warn!(error = %e, "File notifications unavailable; polling the tree");
+                Self::polling(indexer, debounce)
+            }
+        }
+    }
+
+    /// Start watching `indexer`'s root by re-walking the tree on every poll.
+    #[must_use]
+    pub fn polling(indexer: Indexer, debounce: Duration) -> Self {
+        let stamps = scan(&indexer);
+        Self::with_source(indexer, debounce, Source::Polling { stamps })
+    }
+
+    const fn with_source(indexer: Indexer, debounce: Duration, source: Source) -> Self {
+        Self {
+            indexer,
+            debounce,
+            source,
+            pending: BTreeSet::new(),
+            rescan: false,
+            last_change: None,
+        }
+    }
+
+    /// The wrapped indexer (and, through it, the database).
+    #[must_use]
+    pub const fn indexer(&self) -> &Indexer {
+        &self.indexer
+    }
+
+    /// Whether changes are found by re-walking the tree rather than by notifications.
+    #[must_use]
+    pub const fn is_polling(&self) -> bool {
+        matches!(self.source, Source::Polling { .. })
+    }
+
+    /// Queue the changes seen by `now` (received notifications, or one scan when
+    /// polling), then apply them if the tree has been quiet for the debounce window.
+    ///
+    /// Returns the batch stats if a batch was applied.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if applying the batch fails; the pending
+    /// changes are kept and retried on the next poll.
+    pub fn poll(&mut self, now: Instant) -> Result<Option<IndexStats>> {
+        let changed = match &mut self.source {
+            Source::Events { events, .. } => {
+                let mut received = Vec::new();
+                let disconnected = loop {
+                    match events.try_recv() {
+                        Ok(event) => received.push(event),
+                        Err(TryRecvError::Empty) => break false,
+                        Err(TryRecvError::Disconnected) => break true,
+                    }
+                };
+                let mut changed = false;
+                for event in received {
+                    changed |= self.queue_event(event);
+                }
+                if disconnected {
+                    tracing::warn!("File notifications stopped; polling the tree");
+                    self.source = Source::Polling { stamps: scan(&self.indexer) };
+                    self.rescan = true;
+                    changed = true;
+                }
+                changed
+            }
+            Source::Polling { stamps } => {
+                let current = scan(&self.indexer);
+                let mut changed = false;
+                for (path, stamp) in &current {
+                    if stamps.get(path) != Some(stamp) {
+                        self.pending.insert(path.clone());
+                        changed = true;
+                    }
+                }
+                for path in stamps.keys() {
+                    if !current.contains_key(path) {
+                        self.pending.insert(path.clone());
+                        changed = true;
+                    }
+                }
+                *stamps = current;
+                changed
+            }
+        };
+        if changed {
+            self.last_change = Some(now);
+        }
+
+        let Some(last_change) = self.last_change else {
+            return Ok(None);
+        };
+        if now.duration_since(last_change) < self.debounce {
+            return Ok(None);
+        }
+
+        let batch: Vec<PathBuf> = self.pending.iter().cloned().collect();
+        let stats = if self.rescan {
+            self.indexer.index_directory()?
+        } else if self.is_polling() {
+            self.indexer.reindex_paths(&batch)?
+        } else {
+            self.apply_notified(&batch)?
+        };
+        self.pending.clear();
+        self.rescan = false;
+        self.last_change = None;
+        Ok(Some(stats))
+    }
+
+    /// Poll until the process is interrupted, logging each batch.
+    ///
+    /// Between polls this waits for the next notification (or the end of the
+    /// debounce window), or sleeps for `interval` when polling.
+    ///
+    /// # Errors
+    /// Returns `IndexerError::Database` if a batch cannot be applied.
+    pub fn run(&mut self, interval: Duration) -> Result<()> {
+        loop {
+            if let Some(stats) = self.poll(Instant::now())? {
+                tracing::info!(
+                    files = stats.files_indexed,
+                    skipped = stats.files_skipped,
+                    bytes = stats.bytes_indexed,
+                    duration_secs = %format!("{:.2}", stats.duration.as_secs_f64()),
+                    "Watch batch indexed"
+                );
+            }
+            self.wait(interval);
+        }
+    }
+
+    /// Block until the next poll is due.
+    fn wait(&mut self, interval: Duration) {
+        let Source::Events { events, .. } = &self.source else {
+            std::thread::sleep(interval);
+            return;
+        };
+        let received = match self.last_change {
+            Some(last_change) => {
+                events.recv_timeout(self.debounce.saturating_sub(last_change.elapsed()))
+            }
+            None => events.recv().map_err(RecvTimeoutError::from),
+        };
+        // A disconnect is picked up (and falls back to polling) by the next poll
+        if let Ok(event) = received {
+            if self.queue_event(event) {
+                self.last_change = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Queue the paths of one notification; returns whether anything was queued.
+    fn queue_event(&mut self, event: notify::Result<Event>) -> bool {
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!(error = %e, "File notification error; rescanning the tree");
+                self.rescan = true;
+                return true;
+            }
+        };
+        if event.need_rescan() {
+            self.rescan = true;
+            return true;
+        }
+        // Reads (including the indexer's own) change nothing
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+
+        let mut queued = false;
+        for path in &event.paths {
+            let Ok(rel_path) = self.indexer.root_relative(path) else { continue };
+            // Database writes would otherwise trigger a batch after every batch
+            if !self.indexer.never_indexes(&rel_path) {
+                self.pending.insert(rel_path);
+                queued = true;
+            }
+        }
+        queued
+    }
+
+    /// Apply notified paths: re-index the files at or below them that the walk
+    /// accepts, and drop the entries of those that are gone.
+    fn apply_notified(&mut self, batch: &[PathBuf]) -> Result<IndexStats> {
+        let root = self.indexer.root().to_path_buf();
+        let gone: Vec<&PathBuf> =
+            batch.iter().filter(|path| fs::symlink_metadata(root.join(path)).is_err()).collect();
+        let mut changed: BTreeSet<PathBuf> = self
+            .indexer
+            .walk_candidates_at(batch)
+            .iter()
+            .filter_map(|entry| entry.path().strip_prefix(&root).ok().map(Path::to_path_buf))
+            .collect();
+        changed.extend(gone.iter().map(|path| (*path).clone()));
+        let mut stats = self.indexer.reindex_paths(&changed.into_iter().collect::<Vec<_>>())?;
+
+        // A directory moved away is reported by its own path only
+        let gone_dirs: Vec<(String, PathBuf)> = gone
+            .iter()
+            .map(|path| (format!("{}{MAIN_SEPARATOR}", path.to_string_lossy()), root.join(path)))
+            .collect();
+        if !gone_dirs.is_empty() {
+            let roots: Vec<(&str, &Path)> =
+                gone_dirs.iter().map(|(prefix, dir)| (prefix.as_str(), dir.as_path())).collect();
+            let pruned = self.indexer.db_mut().prune_missing_files_under(&roots)?;
+            stats.pruned += pruned as u64;
+        }
+        Ok(stats)
+    }
+}
+
+/// Subscribe to change notifications for everything below `root`.
+fn subscribe(root: &Path) -> notify::Result<Source> {
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(Source::Events { _watcher: watcher, events })
+}
+
+/// Stat every file the indexer would walk, keyed by root-relative path.
+fn scan(indexer: &Indexer) -> HashMap<PathBuf, FileStamp> {
+    indexer
+        .walk_candidates()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let rel_path = entry.path().strip_prefix(indexer.root()).ok()?.to_path_buf();
+            Some((rel_path, FileStamp { modified: metadata.modified().ok(), len: metadata.len() }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DB_NAME;
+    use crate::db::{Database, PragmaConfig};
+    use crate::indexer::IndexerConfig;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_polling_applies_changes_after_debounce() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("kept.rs"), "fn oldterm() {}").unwrap();
+        fs::write(dir.path().join("gone.rs"), "fn goneterm() {}").unwrap();
+
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        let mut indexer = Indexer::new(dir.path(), db, IndexerConfig::default());
+        indexer.index_directory().unwrap();
+
+        let mut watcher = Watcher::polling(indexer, DEFAULT_DEBOUNCE);
+        let start = Instant::now();
+        assert!(watcher.poll(start).unwrap().is_none());
+
+        fs::write(dir.path().join("kept.rs"), "fn newterm() { /* edited */ }").unwrap();
+        fs::write(dir.path().join("added.rs"), "fn addedterm() {}").unwrap();
+        fs::remove_file(dir.path().join("gone.rs")).unwrap();
+        fs::write(dir.path().join(".hidden.rs"), "fn hiddenterm() {}").unwrap();
+
+        // Change seen, but still inside the debounce window
+        assert!(watcher.poll(start).unwrap().is_none());
+        assert!(watcher.indexer().db().search("newterm", false, 10).unwrap().is_empty());
+
+        let stats = watcher.poll(start + DEFAULT_DEBOUNCE).unwrap().expect("batch applied");
+        assert_eq!(stats.files_indexed, 2);
+
+        let db = watcher.indexer().db();
+        assert_eq!(db.search("newterm", false, 10).unwrap().len(), 1);
+        assert!(db.search("oldterm", false, 10).unwrap().is_empty());
+        assert_eq!(db.search("addedterm", false, 10).unwrap().len(), 1);
+        assert!(db.search("goneterm", false, 10).unwrap().is_empty());
+        assert!(db.search("hiddenterm", false, 10).unwrap().is_empty());
+
+        // Quiet tree: nothing more to do
+        assert!(watcher.poll(start + DEFAULT_DEBOUNCE * 2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_notifications_apply_changes() {
+        let dir = tempdir().unwrap();
+        let outside = tempdir().unwrap();
+        // .gitignore applies inside a git repository
+        fs::create_dir(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+        fs::write(dir.path().join("kept.rs"), "fn oldterm() {}").unwrap();
+        fs::write(dir.path().join("gone.rs"), "fn goneterm() {}").unwrap();
+        fs::create_dir(dir.path().join("moved")).unwrap();
+        fs::write(dir.path().join("moved/inner.rs"), "fn movedterm() {}").unwrap();
+
+        let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+        db.init_schema().unwrap();
+        let mut indexer = Indexer::new(dir.path(), db, IndexerConfig::default());
+        indexer.index_directory().unwrap();
+
+        let debounce = Duration::from_millis(50);
+        let mut watcher = Watcher::new(indexer, debounce);
+        if watcher.is_polling() {
+            // No notification backend here; polling is covered above
+            return;
+        }
+
+        fs::write(dir.path().join("kept.rs"), "fn newterm() { /* edited */ }").unwrap();
+        fs::write(dir.path().join("added.rs"), "fn addedterm() {}").unwrap();
+        fs::remove_file(dir.path().join("gone.rs")).unwrap();
+        fs::rename(dir.path().join("moved"), outside.path().join("moved")).unwrap();
+        fs::create_dir(dir.path().join("build")).unwrap();
+        fs::write(dir.path().join("build/out.rs"), "fn ignoredterm() {}").unwrap();
+
+        let hits = |watcher: &Watcher, term: &str| {
+            watcher.indexer().db().search(term, false, 10).unwrap().len()
+        };
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while hits(&watcher, "newterm") == 0
+            || hits(&watcher, "addedterm") == 0
+            || hits(&watcher, "goneterm") > 0
+            || hits(&watcher, "movedterm") > 0
+        {
+            assert!(Instant::now() < deadline, "notified changes were not applied");
+            watcher.poll(Instant::now()).unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(hits(&watcher, "oldterm"), 0);
+        assert_eq!(hits(&watcher, "ignoredterm"), 0);
+
+        // The batches' own database writes must not queue another batch
+        std::thread::sleep(debounce * 4);
+        assert!(watcher.poll(Instant::now()).unwrap().is_none());
+        std::thread::sleep(debounce * 2);
+        assert!(watcher.poll(Instant::now()).unwrap().is_none());
+    }
+}
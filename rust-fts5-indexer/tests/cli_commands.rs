@@ -0,0 +1,1185 @@
+use assert_cmd::Command;
+use ffts_indexer::DB_NAME;
+use ffts_indexer::db::{Database, PragmaConfig};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_hash_matches_stored_content_hash() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    fs::write(&file, "pub fn hashed() {}\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["hash", file.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let cli_hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+    let stored: String = db
+        .conn()
+        .query_row("SELECT content_hash FROM files WHERE path = 'lib.rs'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(cli_hash, stored);
+}
+
+#[test]
+fn test_hash_rejects_non_utf8_file() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("blob.bin");
+    fs::write(&file, [0xff, 0xfe, 0x00]).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["hash", file.to_str().unwrap()])
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn test_search_validate_only_reports_syntax() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "index"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "search", "--validate-only", "main"])
+        .assert()
+        .success()
+        .stdout("valid\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args([
+            "--project-dir",
+            dir.path().to_str().unwrap(),
+            "search",
+            "--validate-only",
+            "\"main",
+        ])
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn test_benchmark_stats_json_structure() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() { test_struct(); }").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args([
+            "--quiet",
+            "--project-dir",
+            dir.path().to_str().unwrap(),
+            "search",
+            "--benchmark",
+            "--stats-json",
+            "--iterations",
+            "3",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["file_count"], 1);
+    assert_eq!(report["iterations"], 3);
+    assert!(report["db_size_bytes"].as_u64().unwrap() > 0);
+
+    let queries = report["queries"].as_array().unwrap();
+    assert!(!queries.is_empty());
+    for query in queries {
+        assert!(query["query"].is_string());
+        assert!(query["results"].is_u64());
+        let p50 = query["p50_ms"].as_f64().unwrap();
+        let p95 = query["p95_ms"].as_f64().unwrap();
+        assert!(p50 >= 0.0 && p95 >= p50);
+    }
+}
+
+#[test]
+fn test_search_reports_auto_init_file_count() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn alpha() {}").unwrap();
+    fs::write(dir.path().join("b.rs"), "fn beta() {}").unwrap();
+    fs::write(dir.path().join("c.md"), "# gamma").unwrap();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "search", "alpha"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("auto-indexed 3 files before searching"), "stderr: {stderr}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "a.rs\n");
+
+    // Reused index: nothing to report
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "search", "alpha"])
+        .output()
+        .unwrap();
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("auto-indexed"));
+}
+
+#[test]
+fn test_search_quiet_suppresses_auto_init_report() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn alpha() {}").unwrap();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--quiet", "--project-dir", dir.path().to_str().unwrap(), "search", "alpha"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("auto-indexed"));
+}
+
+#[test]
+fn test_delete_journal_mode_index_search_doctor() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn journal_probe() {}").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--journal-mode", "DELETE", "--project-dir", project, "index"])
+        .assert()
+        .success();
+    assert!(!dir.path().join(format!("{DB_NAME}-wal")).exists());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--journal-mode", "DELETE", "--project-dir", project, "search", "journal_probe"])
+        .assert()
+        .success()
+        .stdout("main.rs\n");
+
+    let db = Database::open_readonly(&dir.path().join(DB_NAME)).unwrap();
+    assert_eq!(db.get_journal_mode().as_deref(), Some("delete"));
+    drop(db);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args([
+            "--journal-mode",
+            "DELETE",
+            "--project-dir",
+            project,
+            "doctor",
+            "--fail-on",
+            "error",
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_search_limit_caps_results() {
+    let dir = tempdir().unwrap();
+    for i in 0..100 {
+        fs::write(dir.path().join(format!("file_{i}.rs")), "fn limitterm() {}").unwrap();
+    }
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--limit", "5", "limitterm"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 5);
+
+    // Default keeps the historical cap of 50
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "limitterm"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout).lines().count(), 50);
+}
+
+#[test]
+fn test_stats_json_reports_index_metrics() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/small.rs"), "fn a() {}").unwrap();
+    fs::write(dir.path().join("src/large.rs"), "fn large() { /* more bytes */ }").unwrap();
+    fs::write(dir.path().join("notes.md"), "notes").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "stats", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["file_count"], 3);
+    assert_eq!(json["total_indexed_bytes"], 9 + 31 + 5);
+    assert_eq!(json["average_file_bytes"], 15);
+    assert_eq!(json["largest_file"]["path"], "src/large.rs");
+    assert_eq!(json["largest_file"]["size_bytes"], 31);
+    assert!(json["db_size_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(json["journal_mode"], "wal");
+
+    // Filters scope the count; plain output keeps the `files:` line first
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "stats", "--in", "src"])
+        .assert()
+        .success()
+        .stdout(predicates::str::starts_with("files: 2\n"))
+        .stdout(predicates::str::contains("largest file: src/large.rs (31 bytes)\n"));
+}
+
+#[test]
+fn test_prune_removes_deleted_files() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    for name in ["keep.rs", "gone_a.rs", "gone_b.rs"] {
+        fs::write(dir.path().join(name), "fn pruned() {}").unwrap();
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+    fs::remove_file(dir.path().join("gone_a.rs")).unwrap();
+    fs::remove_file(dir.path().join("gone_b.rs")).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "prune", "--optimize"])
+        .assert()
+        .success()
+        .stdout("pruned: 2\n");
+
+    let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+    assert_eq!(db.get_file_count().unwrap(), 1);
+}
+
+#[test]
+fn test_prune_without_database_is_data_error() {
+    let dir = tempdir().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "prune"])
+        .assert()
+        .failure()
+        .code(2);
+    assert!(!dir.path().join(DB_NAME).exists());
+}
+
+#[test]
+fn test_compact_reports_sizes_and_writes_copy() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn compacted() {}").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "compact"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("before: ") && stdout.contains("\nafter: "), "{stdout}");
+
+    let copy = dir.path().join("copy.db");
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "compact", "--into", copy.to_str().unwrap()])
+        .assert()
+        .success();
+    let db = Database::open_readonly(&copy).unwrap();
+    assert_eq!(db.get_file_count().unwrap(), 1);
+}
+
+#[test]
+fn test_index_stdin_under_virtual_name() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index", "--stdin", "--name", "generated/report.txt"])
+        .write_stdin("build output\npipedneedle\n")
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--no-auto-init", "pipedneedle"])
+        .assert()
+        .success()
+        .stdout("generated/report.txt\n");
+
+    // Invalid UTF-8 is rejected before touching the index
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index", "--stdin", "--name", "blob.bin"])
+        .write_stdin(vec![0xff, 0xfe])
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn test_max_file_size_flag_raises_limit() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    // ~1.5MB: over the 1MB default, under 2M
+    let line = "bigfileneedle filler text for the size limit test\n";
+    fs::write(dir.path().join("big.txt"), line.repeat(1_572_864 / line.len())).unwrap();
+    fs::write(dir.path().join("small.txt"), "small").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--no-auto-init", "bigfileneedle"])
+        .assert()
+        .success()
+        .stdout("");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--max-file-size", "2M", "index"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--no-auto-init", "bigfileneedle"])
+        .assert()
+        .success()
+        .stdout("big.txt\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--max-file-size", "0", "index"])
+        .assert()
+        .failure()
+        .code(2);
+}
+
+#[test]
+fn test_index_json_reports_stats_on_stdout() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+    fs::write(dir.path().join("b.rs"), "fn b() {}").unwrap();
+
+    let run = || {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "index", "--json"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout.lines().count(), 1, "JSON must be the only stdout output: {stdout}");
+        serde_json::from_str::<serde_json::Value>(&stdout).unwrap()
+    };
+
+    let stats = run();
+    assert_eq!(stats["files_indexed"], 2);
+    assert_eq!(stats["files_skipped"], 0);
+    assert_eq!(stats["bytes_indexed"], 18);
+    assert!(stats["duration_ms"].is_u64());
+    assert_eq!(stats["pruned"], 0);
+
+    fs::remove_file(dir.path().join("b.rs")).unwrap();
+    let stats = run();
+    assert_eq!(stats["pruned"], 1);
+}
+
+#[test]
+fn test_config_max_file_size_honored_and_flag_overrides() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::write(dir.path().join(".ffts.toml"), "max_file_size = 32\n").unwrap();
+    fs::write(dir.path().join("big.txt"), "configneedle ".repeat(10)).unwrap();
+    fs::write(dir.path().join("small.txt"), "configneedle").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--no-auto-init", "configneedle"])
+        .assert()
+        .success()
+        .stdout("small.txt\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--max-file-size", "1K", "index"])
+        .assert()
+        .success();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--no-auto-init", "configneedle"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.lines().any(|line| line == "big.txt"), "{stdout}");
+}
+
+#[test]
+fn test_completions_bash_mentions_binary() {
+    let dir = tempdir().unwrap();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", dir.path().to_str().unwrap(), "completions", "bash"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let script = String::from_utf8(output.stdout).unwrap();
+    assert!(!script.is_empty());
+    assert!(script.contains("ffts-grep"), "{script}");
+    assert!(!dir.path().join(DB_NAME).exists(), "completions must not create a database");
+}
+
+#[test]
+fn test_manpage_renders_title_and_subcommands() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "manpage"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let roff = String::from_utf8(output.stdout).unwrap();
+    assert!(roff.contains(".TH FFTS-GREP 1"), "{roff}");
+    assert!(roff.contains("search"));
+
+    let out = dir.path().join("ffts-grep.1");
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "manpage", "--out", out.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read_to_string(out).unwrap(), roff);
+}
+
+#[test]
+fn test_serve_answers_search_and_health_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::process::Stdio;
+
+    fn get(addr: &str, target: &str) -> (String, serde_json::Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {target} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().to_string();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("server.rs"), "fn http_needle() {}").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let mut server = std::process::Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "serve", "--addr", "127.0.0.1:0", "--workers", "2"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut banner = String::new();
+    BufReader::new(server.stdout.take().unwrap()).read_line(&mut banner).unwrap();
+    let addr = banner.trim().strip_prefix("Listening on http://").unwrap().to_string();
+
+    let (status, body) = get(&addr, "/search?q=http_needle&limit=5");
+    assert!(status.contains(" 200 "), "{status}");
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results[0]["path"], "server.rs");
+
+    let (status, body) = get(&addr, "/health");
+    assert!(status.contains(" 200 "), "{status}");
+    assert_eq!(body["status"], "healthy");
+
+    let (status, body) = get(&addr, "/search?limit=5");
+    assert!(status.contains(" 400 "), "{status}");
+    assert!(body["error"].is_string());
+
+    server.kill().unwrap();
+    server.wait().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_daemon_answers_json_and_stops_on_sigterm() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::{Duration, Instant};
+
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn daemon_needle() {}").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let socket = dir.path().join("ffts.sock");
+    let mut daemon = std::process::Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "daemon", "--socket", socket.to_str().unwrap()])
+        .spawn()
+        .unwrap();
+
+    let started = Instant::now();
+    let stream = loop {
+        match UnixStream::connect(&socket) {
+            Ok(stream) => break stream,
+            Err(_) if started.elapsed() < Duration::from_secs(10) => {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Err(e) => panic!("daemon did not start: {e}"),
+        }
+    };
+    let mut writer = stream.try_clone().unwrap();
+    writer.write_all(b"{\"query\": \"daemon_needle\", \"limit\": 3}\n").unwrap();
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(response["results"][0]["path"], "main.rs", "{line}");
+
+    let pid = libc::pid_t::try_from(daemon.id()).unwrap();
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGTERM) }, 0);
+    assert!(daemon.wait().unwrap().success());
+    assert!(!socket.exists(), "socket is removed on shutdown");
+}
+
+#[test]
+fn test_transaction_threshold_above_batch_size_is_rejected() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--batch-size", "5", "--transaction-threshold", "10"])
+        .args(["index"])
+        .assert()
+        .code(2);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--batch-size", "5", "--transaction-threshold", "5"])
+        .args(["index"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_rebuild_migrates_legacy_schema_and_restores_search() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().to_str().unwrap();
+    {
+        // v0.9 schema: no filename column, 2-column FTS5
+        let conn = rusqlite::Connection::open(dir.path().join(DB_NAME)).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE files (
+                id INTEGER PRIMARY KEY,
+                path TEXT UNIQUE NOT NULL,
+                content_hash TEXT,
+                mtime INTEGER,
+                size INTEGER,
+                indexed_at INTEGER,
+                content TEXT
+            );
+            CREATE VIRTUAL TABLE files_fts USING fts5(
+                path, content, content='files', content_rowid='id', tokenize='porter unicode61'
+            );
+            CREATE TRIGGER files_ai AFTER INSERT ON files BEGIN
+                INSERT INTO files_fts(rowid, path, content) VALUES (new.id, new.path, new.content);
+            END;
+            INSERT INTO files (path, content_hash, mtime, size, indexed_at, content)
+            VALUES ('src/legacy.rs', 'hash', 0, 20, 0, 'fn legacy_needle() {}');",
+        )
+        .unwrap();
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "rebuild"])
+        .assert()
+        .success()
+        .stdout("migrated: 1\nreindexed: 1\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "legacy_needle", "--no-auto-init"])
+        .assert()
+        .success()
+        .stdout("src/legacy.rs\n");
+
+    // Already current: nothing to do
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "rebuild"])
+        .assert()
+        .success()
+        .stdout("migrated: 0\nreindexed: 0\n");
+}
+
+#[test]
+fn test_color_never_emits_no_escape_codes() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/colorful.rs"), "fn colorful_needle() {}").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    for args in [&["search", "colorful_needle"][..], &["doctor"][..]] {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "--color", "never"])
+            .args(args)
+            .env("CLICOLOR_FORCE", "1")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(!stdout.contains('\x1b'), "{args:?}: {stdout:?}");
+    }
+
+    // `always` colors even when piped: bold file name, green passes
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--color", "always", "search", "colorful_needle"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "src/\x1b[1mcolorful.rs\x1b[0m\n");
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--color", "always", "doctor"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8(output.stdout).unwrap().contains("\x1b[32m\u{2713}\x1b[0m"));
+}
+
+#[test]
+fn test_search_count_prints_only_the_number() {
+    let dir = tempdir().unwrap();
+    for i in 0..7 {
+        fs::write(dir.path().join(format!("file{i}.rs")), "fn counted_term() {}").unwrap();
+    }
+    fs::write(dir.path().join("notes.py"), "counted_term = 1").unwrap();
+    fs::write(dir.path().join("other.rs"), "fn unrelated() {}").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    // --limit does not cap the count; filters still apply
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--count", "--limit", "2", "--lang", "rust"])
+        .arg("counted_term")
+        .assert()
+        .success()
+        .stdout("7\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--count", "counted_term"])
+        .assert()
+        .success()
+        .stdout("8\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--count", "absent_term"])
+        .assert()
+        .success()
+        .stdout("0\n");
+}
+
+#[test]
+fn test_search_count_matches_auto_prefix_results() {
+    let dir = tempdir().unwrap();
+    for i in 1..=3 {
+        fs::write(dir.path().join(format!("file{i}.rs")), format!("let configuration{i} = 1;"))
+            .unwrap();
+    }
+    fs::write(dir.path().join("other.rs"), "fn unrelated() {}").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    // A trailing `_` or `-` auto-prefixes the last term, for the count as for the results
+    for query in ["config_", "config-"] {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "search", query])
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap().lines().count(), 3);
+
+        for extra in [&[][..], &["--lang", "rust"][..]] {
+            Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+                .args(["--project-dir", project, "search", "--count"])
+                .args(extra)
+                .arg(query)
+                .assert()
+                .success()
+                .stdout("3\n");
+        }
+    }
+}
+
+#[test]
+fn test_search_files_with_matches_prints_each_path_once() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("listed.rs"), "listed\nlisted again\nlisted once more").unwrap();
+    fs::write(dir.path().join("notes.md"), "a listed item").unwrap();
+    fs::write(dir.path().join("other.txt"), "nothing here").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "-l", "-n", "listed"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut paths: Vec<&str> = stdout.lines().collect();
+    paths.sort_unstable();
+    assert_eq!(paths, ["listed.rs", "notes.md"]);
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--files-with-matches", "--format", "json"])
+        .arg("listed")
+        .output()
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["count"], 2);
+    assert!(json["files"].as_array().unwrap().iter().all(serde_json::Value::is_string));
+    assert!(json.get("results").is_none());
+}
+
+#[test]
+fn test_search_fuzzy_matches_misspelled_filename() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("config.rs"), "pub struct Settings;").unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "condig"])
+        .assert()
+        .success()
+        .stdout("");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--fuzzy", "condig"])
+        .assert()
+        .success()
+        .stdout("config.rs\n");
+}
+
+#[test]
+fn test_tokenizer_survives_plain_index_run() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("user.rs"), "fn getUserById(id: u64) {}\n").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--tokenizer", "trigram", "index"])
+        .assert()
+        .success();
+    fs::write(dir.path().join("order.rs"), "fn getOrderById() {}\n").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "UserBy"])
+        .assert()
+        .success()
+        .stdout("user.rs\n");
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--tokenizer", "bigram", "index"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_compress_content_index_stays_searchable() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("lib.rs"), "fn squeezed_term() {}\n".repeat(50)).unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--compress-content", "index"])
+        .assert()
+        .success();
+
+    let db = rusqlite::Connection::open(dir.path().join(DB_NAME)).unwrap();
+    let kind: String =
+        db.query_row("SELECT typeof(content) FROM files", [], |row| row.get(0)).unwrap();
+    assert_eq!(kind, "blob");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--format", "grep", "squeezed_term"])
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("lib.rs:1:fn squeezed_term() {}"));
+}
+
+#[test]
+fn test_stdin_json_array_runs_batch_search() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("alpha.rs"), "fn stdinbatch_alpha() {}").unwrap();
+    std::fs::write(dir.path().join("beta.rs"), "fn stdinbatch_beta() {}").unwrap();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .arg("--project-dir")
+        .arg(dir.path())
+        .arg("--quiet")
+        .write_stdin("[\"stdinbatch_beta\", \"stdinbatch_missing\", \"stdinbatch_alpha\"]\n")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 3);
+    assert_eq!(json[0]["results"][0]["path"], "beta.rs");
+    assert_eq!(json[1]["count"], 0);
+    assert_eq!(json[2]["results"][0]["path"], "alpha.rs");
+}
+
+#[test]
+fn test_search_lang_filters_by_detected_language() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("parser.rs"), "fn tokenize_input() {}").unwrap();
+    fs::write(dir.path().join("parser.py"), "def tokenize_input(): pass").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    // Both the filename phase ("parser") and the content phase are filtered
+    for query in ["parser", "tokenize_input"] {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "search", "--lang", "rust", query])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "parser.rs", "{query}");
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--lang", "klingon", "parser"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_search_sort_mtime_newest_first() {
+    use std::time::{Duration, SystemTime};
+
+    let dir = tempdir().unwrap();
+    let now = SystemTime::now();
+    // The older file ranks better (more matches, and its name matches the query)
+    for (name, content, age_days) in
+        [("sortterm.rs", "sortterm sortterm", 30), ("newer.rs", "sortterm", 1)]
+    {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(now - Duration::from_secs(age_days * 86_400)).unwrap();
+    }
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let search = |extra: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "search"])
+            .args(extra)
+            .arg("sortterm")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect::<Vec<_>>()
+    };
+
+    assert_eq!(search(&[]), ["sortterm.rs", "newer.rs"]);
+    assert_eq!(search(&["--sort", "mtime"]), ["newer.rs", "sortterm.rs"]);
+}
+
+#[test]
+fn test_search_since_until_mtime_window() {
+    use std::time::{Duration, SystemTime};
+
+    let dir = tempdir().unwrap();
+    let now = SystemTime::now();
+    for (name, age_days) in [("recent.rs", 2), ("stale.rs", 60)] {
+        let path = dir.path().join(name);
+        fs::write(&path, "fn windowterm() {}").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(now - Duration::from_secs(age_days * 86_400)).unwrap();
+    }
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let search = |window: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "search"])
+            .args(window)
+            .arg("windowterm")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    assert_eq!(search(&["--since", "7d"]), "recent.rs");
+    assert_eq!(search(&["--until", "30d"]), "stale.rs");
+    assert_eq!(search(&["--since", "90d", "--until", "30d"]), "stale.rs");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "search", "--since", "1d", "--until", "7d", "windowterm"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_search_min_size_excludes_small_files() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("sizeterm_stub.txt"), "sizeterm!!").unwrap();
+    fs::write(dir.path().join("sizeterm_big.txt"), format!("sizeterm {}", "x".repeat(10_231)))
+        .unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let search = |extra: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "search"])
+            .args(extra)
+            .arg("sizeterm")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let mut lines: Vec<String> =
+            String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect();
+        lines.sort();
+        lines
+    };
+
+    assert_eq!(search(&[]), ["sizeterm_big.txt", "sizeterm_stub.txt"]);
+    assert_eq!(search(&["--min-size", "1K"]), ["sizeterm_big.txt"]);
+    assert_eq!(search(&["--paths", "--min-size", "1K"]), ["sizeterm_big.txt"]);
+    assert_eq!(search(&["--max-size", "1K"]), ["sizeterm_stub.txt"]);
+}
+
+#[test]
+fn test_export_dumps_every_indexed_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.rs"), "fn a() {}").unwrap();
+    fs::write(dir.path().join("b.md"), "# \"Title\" ünïcödé\n").unwrap();
+    fs::write(dir.path().join("c.txt"), "plain").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "export"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let records = records.as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    let b = records.iter().find(|r| r["path"] == "b.md").unwrap();
+    assert_eq!(b["content"], "# \"Title\" ünïcödé\n");
+    for key in ["filename", "content_hash", "mtime", "size"] {
+        assert!(records[0].get(key).is_some(), "{key}");
+    }
+
+    let out = dir.path().join("dump.ndjson");
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "export", "--ndjson", "--out", out.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout("");
+    let dump = fs::read_to_string(&out).unwrap();
+    assert_eq!(dump.lines().count(), 3);
+    for line in dump.lines() {
+        serde_json::from_str::<serde_json::Value>(line).unwrap();
+    }
+}
+
+#[test]
+fn test_import_round_trips_export() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("alpha.rs"), "fn roundtrip_alpha() {}").unwrap();
+    fs::write(dir.path().join("beta.md"), "roundtrip notes for beta").unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/gamma.py"), "def roundtrip(): pass").unwrap();
+    let project = dir.path().to_str().unwrap();
+    let dump = tempdir().unwrap();
+    let dump_path = dump.path().join("index.json");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "index"])
+        .assert()
+        .success();
+
+    let search = || {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+            .args(["--project-dir", project, "search", "--no-auto-init", "--format", "json"])
+            .arg("roundtrip")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    let before = search();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "export", "--out", dump_path.to_str().unwrap()])
+        .assert()
+        .success();
+
+    // An existing index is only replaced with --force
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "import", dump_path.to_str().unwrap()])
+        .assert()
+        .code(2);
+
+    for entry in fs::read_dir(dir.path()).unwrap() {
+        let path = entry.unwrap().path();
+        if path.file_name().unwrap().to_string_lossy().starts_with(DB_NAME) {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "import"])
+        .write_stdin(fs::read(&dump_path).unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "imported: 3\nskipped: 0\n");
+    assert_eq!(search(), before);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "import", "--force", dump_path.to_str().unwrap()])
+        .assert()
+        .success();
+    assert_eq!(search(), before);
+}
+
+#[test]
+fn test_db_path_flag_uses_custom_database_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn custom_location() {}").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--db-path", "index/custom.idx", "index"])
+        .assert()
+        .success();
+
+    assert!(dir.path().join("index/custom.idx").exists());
+    assert!(!dir.path().join(DB_NAME).exists());
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--db-path", "index/custom.idx", "export"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let paths: Vec<&str> =
+        records.as_array().unwrap().iter().map(|r| r["path"].as_str().unwrap()).collect();
+    assert_eq!(paths, ["main.rs"], "The custom database must not index itself");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .args(["--project-dir", project, "--db-path", "index/custom.idx", "search"])
+        .args(["--no-auto-init", "custom_location"])
+        .assert()
+        .success()
+        .stdout("main.rs\n");
+}
+
+#[test]
+fn test_db_path_env_derives_gitignore_and_wal_names() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+    let project = dir.path().to_str().unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .env("FFTS_DB_PATH", "custom.idx")
+        .args(["--project-dir", project, "init"])
+        .assert()
+        .success();
+
+    assert!(dir.path().join("custom.idx").exists());
+    let gitignore = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+    for entry in ["custom.idx", "custom.idx-shm", "custom.idx-wal", "custom.idx.tmp*"] {
+        assert!(gitignore.lines().any(|line| line == entry), "missing {entry}");
+    }
+    assert!(!gitignore.contains(DB_NAME));
+
+    // An orphan WAL is recognized by the custom name
+    fs::remove_file(dir.path().join("custom.idx")).unwrap();
+    fs::write(dir.path().join("custom.idx-wal"), "orphan").unwrap();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("ffts-grep"))
+        .env("FFTS_DB_PATH", "custom.idx")
+        .args(["--project-dir", project, "doctor", "--json"])
+        .output()
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let checks = report["checks"].as_array().unwrap();
+    let orphan = checks.iter().find(|c| c["name"] == "Orphan WAL files").unwrap();
+    assert_eq!(orphan["status"], "warning");
+    assert_eq!(orphan["details"]["orphans"][0], "custom.idx-wal");
+    let gitignore_check = checks.iter().find(|c| c["name"] == "Gitignore").unwrap();
+    assert_eq!(gitignore_check["status"], "pass");
+}
@@ -116,6 +116,48 @@ fn test_search_json_output() {
     assert!(text.contains("\"results\""));
 }
 
+#[test]
+fn test_search_grep_output() {
+    let dir = tempdir().unwrap();
+    let mut indexer = create_test_indexer(&dir);
+
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(
+        dir.path().join("src/lib.rs"),
+        "use std::fs;\n\nfn grepterm() {}\n// grepterm: a:b\nfn other() {}\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("grepterm.md"), "name match only").unwrap();
+    indexer.index_directory().unwrap();
+
+    let db = indexer.db_mut();
+    let mut searcher =
+        Searcher::new(db, SearchConfig { format: OutputFormat::Grep, ..Default::default() });
+    let results = searcher.search("grepterm").unwrap();
+
+    let mut output = Vec::new();
+    searcher.format_results(&results, &mut output).unwrap();
+    let text = String::from_utf8(output).unwrap();
+
+    // Every row is path:line:text; the filename-only match has no line to show
+    assert_eq!(text, "src/lib.rs:3:fn grepterm() {}\nsrc/lib.rs:4:// grepterm: a:b\n");
+    for row in text.lines() {
+        let mut parts = row.splitn(3, ':');
+        assert_eq!(parts.next(), Some("src/lib.rs"));
+        assert!(parts.next().unwrap().parse::<u32>().is_ok());
+        assert!(parts.next().unwrap().contains("grepterm"));
+    }
+
+    // --paths still wins: bare paths, no line lookup
+    let config =
+        SearchConfig { format: OutputFormat::Grep, paths_only: true, ..Default::default() };
+    let mut searcher = Searcher::new(db, config);
+    let results = searcher.search("grepterm").unwrap();
+    let mut output = Vec::new();
+    searcher.format_results(&results, &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "grepterm.md\n");
+}
+
 #[test]
 fn test_search_paths_only() {
     let dir = tempdir().unwrap();
@@ -504,37 +546,32 @@ fn test_fts5_query_error_handling() {
     let mut indexer = create_test_indexer(&dir);
 
     // Create test file
-    fs::write(dir.path().join("test.rs"), "function test() {}").unwrap();
+    fs::write(dir.path().join("test.rs"), "function test() {}\ntest-function(foo.bar, a(b));")
+        .unwrap();
     indexer.index_directory().unwrap();
 
     let db = indexer.db_mut();
 
-    // Test queries - some may fail with FTS5 syntax errors, but should not panic
+    // User input is quoted term-by-term, so punctuation never reaches FTS5 as syntax
     let test_queries = vec![
-        ("test", true),             // Simple query - should work
-        ("\"test\"", true),         // Quoted phrase - should work
-        ("test OR function", true), // Boolean operator - should work
-        ("test*", false),           // Wildcard - may fail (FTS5 syntax dependent)
-        ("test-function", false),   // Hyphen - expected to fail (FTS5 limitation)
+        ("test", true),              // Simple query
+        ("\"test\"", true),          // Quoted phrase
+        ("test OR function", false), // OR is a literal term, not an operator
+        ("test*", true),             // Wildcard stripped by the tokenizer
+        ("test-function", true),     // Hyphenated identifier
+        ("foo.bar", true),           // Dotted identifier
+        ("a(b)", true),              // Parentheses
     ];
 
-    for (query, should_succeed) in test_queries {
+    for (query, has_results) in test_queries {
         let result = db.search(query, false, 10);
-
-        if should_succeed {
-            assert!(result.is_ok(), "Query '{}' should succeed but got: {:?}", query, result.err());
-        } else {
-            // Query may fail with FTS5 syntax error - verify it's handled gracefully
-            // (not panic, just return error)
-            match result {
-                Ok(_) => {} // Unexpectedly succeeded, but that's OK
-                Err(e) => {
-                    // Verify error is properly structured (not a panic)
-                    let _ = format!("{e:?}"); // Should not panic
-                }
-            }
-        }
+        assert!(result.is_ok(), "Query '{}' should succeed but got: {:?}", query, result.err());
+        assert_eq!(!result.unwrap().is_empty(), has_results, "Query '{query}'");
     }
+
+    // Raw expressions keep FTS5 operators (and their syntax errors)
+    assert_eq!(db.search_raw("test OR missingterm", false, 10, 0).unwrap().len(), 1);
+    assert!(db.search_raw("a(b)", false, 10, 0).is_err());
 }
 
 /// Verify permission denied errors are handled gracefully during indexing.
@@ -684,6 +721,54 @@ fn test_doctor_json_output_valid() {
     assert!(parsed["checks"].is_array());
 }
 
+/// Verify `doctor --fix` appends missing gitignore entries and reports the change.
+#[test]
+fn test_doctor_fix_adds_gitignore_entries() {
+    let dir = tempdir().unwrap();
+    let db = Database::open(&dir.path().join(DB_NAME), &PragmaConfig::default()).unwrap();
+    db.init_schema().unwrap();
+    db.upsert_file("test.rs", "fn main() {}", 0, 12).unwrap();
+    drop(db);
+    fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+    assert!(!check_gitignore(dir.path()).is_empty());
+
+    let mut doctor = Doctor::new(dir.path(), false);
+    doctor.run();
+    doctor.fix(&PragmaConfig::default(), IndexerConfig::default());
+
+    assert!(check_gitignore(dir.path()).is_empty(), "Missing entries should be appended");
+    let content = fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+    assert!(content.starts_with("target/\n"), "Existing entries must be preserved");
+
+    let fix = doctor.fixes().iter().find(|f| f.check == "Gitignore").unwrap();
+    assert_eq!(fix.before, Severity::Warning);
+    assert_eq!(fix.after, Severity::Pass);
+    assert!(fix.error.is_none());
+}
+
+/// Verify `doctor --fix` deletes WAL files left behind without a main database.
+#[test]
+fn test_doctor_fix_removes_orphan_wal() {
+    let dir = tempdir().unwrap();
+    let wal_path = dir.path().join(DB_WAL_NAME);
+    fs::write(&wal_path, "orphan").unwrap();
+
+    let mut doctor = Doctor::new(dir.path(), false);
+    doctor.run();
+    let summary = doctor.fix(&PragmaConfig::default(), IndexerConfig::default());
+
+    assert!(!wal_path.exists(), "Orphan WAL should be deleted");
+    let fix = doctor.fixes().iter().find(|f| f.check == "Orphan WAL files").unwrap();
+    assert_eq!(fix.before, Severity::Warning);
+    assert_eq!(fix.after, Severity::Pass);
+
+    let mut output = Vec::new();
+    doctor.output_json(&mut output, &summary).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    let fixes = json["fixes"].as_array().unwrap();
+    assert!(fixes.iter().any(|f| f["check"] == "Orphan WAL files" && f["after"] == "pass"));
+}
+
 /// Verify doctor verbose mode includes step numbers.
 #[test]
 fn test_doctor_verbose_includes_steps() {
@@ -867,6 +952,33 @@ fn test_deep_nesting_indexed() {
     assert_eq!(results.len(), 1);
 }
 
+#[cfg(windows)]
+#[test]
+fn test_long_path_indexed_on_windows() {
+    let dir = tempdir().unwrap();
+    let mut path = dir.path().to_path_buf();
+    for i in 0..6 {
+        path.push(format!("{i}_{}", "x".repeat(60)));
+    }
+    let file_path = path.join("long.rs");
+    assert!(file_path.as_os_str().len() > 260, "Expected a path beyond MAX_PATH");
+
+    // Create through the extended-length prefix so setup does not depend on the
+    // system-wide long path setting
+    let absolute = std::path::absolute(&path).unwrap();
+    let verbatim = std::path::PathBuf::from(format!(r"\\?\{}", absolute.display()));
+    fs::create_dir_all(&verbatim).unwrap();
+    fs::write(verbatim.join("long.rs"), "fn longpathterm() {}").unwrap();
+
+    let mut indexer = create_test_indexer(&dir);
+    let stats = indexer.index_directory().unwrap();
+    assert_eq!(stats.files_indexed, 1);
+
+    let results = indexer.db_mut().search("longpathterm", false, 10).unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].path.ends_with("long.rs"));
+}
+
 #[test]
 fn test_concurrent_index_operations_do_not_corrupt() {
     let dir = tempdir().unwrap();
@@ -928,6 +1040,40 @@ fn test_atomic_reindex_recovers_from_corrupt_db() {
     assert_eq!(db.get_file_count().unwrap(), 0);
 }
 
+#[cfg(unix)]
+#[test]
+fn test_reopen_after_atomic_reindex_sees_new_content() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join(DB_NAME);
+    let config = PragmaConfig::default();
+    fs::write(dir.path().join("old.rs"), "fn oldterm() {}").unwrap();
+    atomic_reindex(dir.path(), &config).unwrap();
+
+    // Long-lived "server" handle on the current index
+    let mut server = Database::open(&db_path, &config).unwrap();
+    assert_eq!(server.search("oldterm", false, 10).unwrap().len(), 1);
+
+    fs::remove_file(dir.path().join("old.rs")).unwrap();
+    fs::write(dir.path().join("new.rs"), "fn newterm() {}").unwrap();
+    atomic_reindex(dir.path(), &config).unwrap();
+
+    // Until it reopens, the server keeps answering from the old index
+    assert_eq!(server.search("oldterm", false, 10).unwrap().len(), 1);
+    assert!(server.search("newterm", false, 10).unwrap().is_empty());
+
+    server.reopen(&db_path, &config).unwrap();
+    assert!(server.search("oldterm", false, 10).unwrap().is_empty());
+    assert_eq!(server.search("newterm", false, 10).unwrap()[0].path, "new.rs");
+
+    // A bad path leaves the handle on the current index
+    assert!(server.reopen(&dir.path().join("missing/dir.db"), &config).is_err());
+    assert_eq!(server.search("newterm", false, 10).unwrap().len(), 1);
+
+    drop(server);
+    let fresh = Database::open(&db_path, &config).unwrap();
+    assert_eq!(fresh.search("newterm", false, 10).unwrap().len(), 1);
+}
+
 // =============================================================================
 // State Machine Verification Tests
 //
@@ -1142,10 +1288,10 @@ fn test_lazy_invalidation_via_db_layer() {
     assert_eq!(results.len(), 1, "FTS5 should have updated content");
 }
 
-/// Verify doctor check pipeline runs all 10 checks in order
+/// Verify doctor check pipeline runs all 14 checks in order
 /// (docs/state-machines/05-doctor-diagnostics.md).
 #[test]
-fn test_doctor_10_check_pipeline() {
+fn test_doctor_14_check_pipeline() {
     let dir = tempdir().unwrap();
 
     // Setup healthy database
@@ -1159,9 +1305,9 @@ fn test_doctor_10_check_pipeline() {
     let mut doctor = Doctor::new(dir.path(), true); // verbose mode
     let summary = doctor.run();
 
-    // Verify all 10 checks ran
+    // Verify all 14 checks ran
     let checks = doctor.checks();
-    assert_eq!(checks.len(), 10, "Doctor should run exactly 10 checks");
+    assert_eq!(checks.len(), 14, "Doctor should run exactly 14 checks");
 
     // Verify check names match documented order
     let expected_names = [
@@ -1175,6 +1321,10 @@ fn test_doctor_10_check_pipeline() {
         "Gitignore",
         "Binary availability",
         "Orphan WAL files",
+        "Stale entries",
+        "Orphaned rows",
+        "Disk space",
+        "FTS sync",
     ];
 
     for (i, expected) in expected_names.iter().enumerate() {
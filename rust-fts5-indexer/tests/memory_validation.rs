@@ -20,6 +20,7 @@ use std::fs;
 use std::process::Command;
 use tempfile::tempdir;
 
+#[cfg(target_os = "macos")]
 #[allow(clippy::cast_precision_loss)]
 fn bytes_to_mb(bytes: u64) -> f64 {
     bytes as f64 / 1_000_000.0